@@ -1,65 +1,191 @@
+// The roster of the game.
+//
+// Every character is described by a json file in assets/characters, the files are
+// read once at startup and the characters are ordered by the file name.
+// The web build can not list a directory, so the built-in files are used there
+// (and also when nothing can be read from the directory).
+use crate::{ingame::{frame_data::MoveKind, input_buffer::MotionBinding, projectile::ProjectileDef, sim::SpriteKind}, layout::LayoutProfile};
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::OnceLock};
 
-pub const ENERGY_MAX: u8 = 100;
+// energy of one stock of the meter and the number of stocks it holds
+pub const ENERGY_STOCK: u16 = 100;
+pub const ENERGY_STOCKS: u16 = 3;
+pub const ENERGY_MAX: u16 = ENERGY_STOCK * ENERGY_STOCKS;
+// energy spent by the skill, by the second special and by the EX version of a move
+pub const SKILL_COST: u16 = 2 * ENERGY_STOCK;
+pub const SPECIAL_COST: u16 = ENERGY_STOCK / 2;
+pub const EX_COST: u16 = ENERGY_STOCK;
 pub const FIRE_CHARGE_MAX: u16 = 300;
 
+/// The special move started with a full energy gauge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillKind {
+    // 神速雷光: warp to the opponent with a thunder
+    Thunder,
+    // 魂吸収: absorb the health of the opponent
+    SoulAbsorb,
+    // 鉄拳制裁: drop a giant fist on the opponent
+    IronFist,
+}
+
+/// The second special of a character, cheaper than the skill and without the cut-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecialKind {
+    // 疾風突き: dash forward with the fist out
+    Rush,
+    // 気功: recover some health and the whole guard
+    Heal,
+    // 地響き: stomp the floor and knock down an opponent on the ground nearby
+    Quake,
+}
+
+impl SpecialKind {
+    /// The melee move of the special, None for a special which does not strike
+    pub fn move_kind(&self) -> Option<MoveKind> {
+        match self {
+            SpecialKind::Rush => Some(MoveKind::Rush),
+            SpecialKind::Heal => None,
+            SpecialKind::Quake => Some(MoveKind::Quake),
+        }
+    }
+}
+
 // TODO: update radar chart(remove dexterity and add the power of skill)
+#[derive(Debug, Clone, Deserialize)]
 pub struct CharacterProfile {
-    pub name: &'static str,
-    pub description: &'static str,
-    pub skill_name: &'static str,
-    pub skill_description: &'static str,
+    // index in the roster, given when the roster is loaded
+    #[serde(skip)]
+    pub id: isize,
+    pub name: String,
+    pub description: String,
+    pub skill: SkillKind,
+    pub skill_name: String,
+    pub skill_description: String,
+    pub special: SpecialKind,
+    pub special_name: String,
     pub health: u32,
     pub agility: f32,
+    // agility in the phone layout, where the arena is smaller
+    #[serde(default)]
+    agility_phone: Option<f32>,
     pub power: f32,
     pub defense: f32,
+    // color of the souls of 魂吸収
+    soul_color: [f32; 3],
+    // directory of the sprite sheets under images/
+    pub textures: String,
+    // images under images/
+    pub portrait: String,
+    pub chart: String,
+    pub skill_name_image: String,
+    // number of frames of the sprite sheets which differ from the default
+    #[serde(default)]
+    frames: HashMap<SpriteKind, usize>,
+    // motion inputs of the special moves, see input_buffer.rs
+    #[serde(default)]
+    pub motions: Vec<MotionBinding>,
+    // projectile of the ranged attack, the fire arrow when it is not given
+    #[serde(default)]
+    pub projectile: ProjectileDef,
 }
 
-pub const SOUL_COLOR: [Color; 3] = [
-    Color::srgb(0.0, 20.0, 18.0),
-    Color::srgb(0.0, 20.0, 15.0),
-    Color::srgb(1.0, 15.0, 20.0),
-];
+impl CharacterProfile {
+    pub fn soul_color(&self) -> Color {
+        Color::srgb(self.soul_color[0], self.soul_color[1], self.soul_color[2])
+    }
+    /// agility of the character in the arena of the layout
+    pub fn agility_in(&self, layout: LayoutProfile) -> f32 {
+        match layout {
+            LayoutProfile::Desktop => self.agility,
+            LayoutProfile::Phone => self.agility_phone.unwrap_or(self.agility / 2.0),
+        }
+    }
+    /// number of frames in the sprite sheet of this character
+    pub fn frames(&self, sprite: SpriteKind) -> usize {
+        self.frames.get(&sprite).copied().unwrap_or(sprite.frames())
+    }
+}
 
-// FIXME: jumping motion of Momma is broken bacause of its dexterity
-pub const CHARACTER_PROFILES: [CharacterProfile; 3] = [
-    CharacterProfile {
-        name: "Momma",
-        description: "このゲームの開発者で、俊足が自慢のファイター。体力は低いが、強力な攻撃を素早く繰り出すことができる。",
-        skill_name: "神速雷光",
-        skill_description: "一瞬で敵に近づき、確定でダメージを与える",
-        health: 900,
-        #[cfg(not(feature="phone"))]
-        agility: 4.0,
-        #[cfg(feature="phone")]
-        agility: 2.0,
-        power: 160.0,
-        defense: 85.0
-    },
-    CharacterProfile {
-        name: "Miyaguchi",
-        description: "縁日班アトラク部門長で、バランス型のファイター。標準的なステータスで扱いやすい",
-        skill_name: "魂吸収",
-        skill_description: "相手に一定ダメージを与えて、その分自分が回復する",
-        health: 1000,
-        #[cfg(not(feature="phone"))]
-        agility: 3.0,
-        #[cfg(feature="phone")]
-        agility: 1.5,
-        power: 100.0,
-        defense: 100.0
-    },
-    CharacterProfile {
-        name: "Matsumoto",
-        description: "ボットの作成者で、体力の多いファイター。スピードは遅いが強靭な肉体とパワーで全てを解決する。",
-        skill_name: "鉄拳制裁",
-        skill_description: "巨大な拳で相手をたたき、地面にいる敵に大ダメージを与える",
-        health: 1200,
-        #[cfg(not(feature="phone"))]
-        agility: 2.8,
-        #[cfg(feature="phone")]
-        agility: 1.0,
-        power: 130.0,
-        defense: 130.0
-    },
+// FIXME: jumping motion of Momma (character1.json) is broken bacause of its dexterity
+const BUILTIN_CHARACTERS: [(&str, &str); 3] = [
+    ("character1.json", include_str!("../assets/characters/character1.json")),
+    ("character2.json", include_str!("../assets/characters/character2.json")),
+    ("character3.json", include_str!("../assets/characters/character3.json")),
 ];
+
+static ROSTER: OnceLock<Vec<CharacterProfile>> = OnceLock::new();
+
+fn parse_character(file: &str, json: &str) -> Option<CharacterProfile> {
+    match serde_json::from_str::<CharacterProfile>(json) {
+        Ok(profile) => Some(profile),
+        Err(e) => {
+            warn!("{} is not a valid character: {}", file, e);
+            None
+        }
+    }
+}
+
+fn builtin_roster() -> Vec<CharacterProfile> {
+    BUILTIN_CHARACTERS
+        .iter()
+        .filter_map(|(file, json)| parse_character(file, json))
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_roster() -> Vec<CharacterProfile> {
+    use bevy::asset::io::file::FileAssetReader;
+    let dir = FileAssetReader::get_base_path().join("assets/characters");
+    let mut files = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            warn!("failed to read {}: {}", dir.display(), e);
+            Vec::new()
+        }
+    };
+    files.sort();
+    let roster = files
+        .iter()
+        .filter_map(|path| {
+            let file = path.display().to_string();
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| warn!("failed to read {}: {}", file, e))
+                .ok()?;
+            parse_character(&file, &json)
+        })
+        .collect::<Vec<_>>();
+    // a fight needs two different characters
+    if roster.len() < 2 {
+        warn!("not enough characters in {}, using the built-in ones", dir.display());
+        return builtin_roster();
+    }
+    roster
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_roster() -> Vec<CharacterProfile> {
+    builtin_roster()
+}
+
+/// All the characters, loaded on the first call
+pub fn characters() -> &'static [CharacterProfile] {
+    ROSTER.get_or_init(|| {
+        let mut roster = load_roster();
+        for (id, profile) in roster.iter_mut().enumerate() {
+            profile.id = id as isize;
+        }
+        roster
+    })
+}
+
+/// The character with the given id (index in the roster)
+pub fn character(character_id: isize) -> &'static CharacterProfile {
+    &characters()[character_id as usize]
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
-    character_def::*, AppState, GameConfig, GameMode, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT,
+    character_def::*, ingame::rules::{Handicap, MatchRules}, layout::Layout, AppState, GameConfig, GameMode, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT,
     PATH_EXTRA_BOLD_JP_FONT, PATH_IMAGE_PREFIX, TITLE_FONT_SIZE,
 };
 
@@ -11,12 +11,19 @@ struct ChooseCharacter;
 #[derive(Component)]
 struct CharacterID(isize);
 
+// place of the character in the teams of a team battle
+#[derive(Component)]
+struct TeamOrder(isize);
+
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    layout: Res<Layout>,
     mut config: ResMut<GameConfig>,
+    rules: Res<MatchRules>,
 ) {
     info!("setup");
+    let team_battle = config.is_team_battle(&rules);
     commands
         .spawn((
             ImageNode::new(
@@ -46,10 +53,7 @@ fn setup(
                             Node {
                                 justify_self: JustifySelf::Start,
                                 align_self: AlignSelf::Start,
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(2.0)),
                                 ..default()
                             },
                             BorderRadius::MAX,
@@ -71,10 +75,7 @@ fn setup(
                             Node {
                                 justify_self: JustifySelf::End,
                                 align_self: AlignSelf::Start,
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(2.0)),
                                 ..default()
                             },
                             BorderRadius::MAX,
@@ -91,11 +92,19 @@ fn setup(
                             TextColor(Color::BLACK),
                         ));
                 });
-            if config.mode == GameMode::SinglePlayer {
+            if config.mode.has_bot() {
                 config.characters_id = [0, choose_rand_character(0)];
             } else {
-                config.characters_id = [0, 2];
+                config.characters_id = [0, characters().len() as isize - 1];
             }
+            config.handicaps = [Handicap::default(); 2];
+            // the teams start in the order of the roster, the one of the bot is random
+            let roster = (0..characters().len() as isize).collect::<Vec<_>>();
+            config.teams = if config.mode.has_bot() {
+                [roster, rand_team()]
+            } else {
+                [roster.clone(), roster.into_iter().rev().collect()]
+            };
             spawner
                 .spawn(Node {
                     width: Val::Percent(100.0),
@@ -109,7 +118,7 @@ fn setup(
                 })
                 .with_children(|spawner| {
                     spawner.spawn((
-                        Text::new("キャラクターを選んでください"),
+                        Text::new(if team_battle { "出場順を決めてください" } else { "キャラクターを選んでください" }),
                         TextFont {
                             font: asset_server.load(PATH_EXTRA_BOLD_JP_FONT),
                             font_size: TITLE_FONT_SIZE,
@@ -122,11 +131,23 @@ fn setup(
                             ..default()
                         },
                     ));
+                    if team_battle {
+                        spawner.spawn((
+                            Text::new("キャラクターを選んで W（コントローラーは十字キーの上）を押すと先頭に出ます"),
+                            TextFont {
+                                font: asset_server.load(PATH_BOLD_JP_FONT),
+                                font_size: DEFAULT_FONT_SIZE * 0.6,
+                                ..Default::default()
+                            },
+                            TextColor(Color::BLACK),
+                            TextLayout::new_with_justify(JustifyText::Center),
+                        ));
+                    }
                     spawner
                         .spawn((
                             Node {
                                 width: Val::Percent(90.0),
-                                height: Val::Percent(90.0),
+                                height: Val::Percent(if team_battle { 80.0 } else { 90.0 }),
                                 flex_direction: FlexDirection::Row,
                                 align_self: AlignSelf::Center,
                                 justify_self: JustifySelf::Center,
@@ -139,8 +160,8 @@ fn setup(
                             BorderRadius::all(Val::Px(20.0)),
                         ))
                         .with_children(|spawner| {
-                            for i in 0..3 {
-                                create_character_box(spawner, &asset_server, &mut config, i as isize);
+                            for i in 0..characters().len() {
+                                create_character_box(spawner, &asset_server, &layout, &mut config, i as isize, team_battle);
                             }
                         });
                 });
@@ -151,14 +172,18 @@ fn setup(
 fn create_character_box(
     spawner: &mut ChildSpawnerCommands,
     asset_server: &Res<AssetServer>,
+    layout: &Layout,
     config: &mut GameConfig,
     character_id: isize,
+    team_battle: bool,
 ) {
-    let profile = &CHARACTER_PROFILES[character_id as usize];
+    let profile = character(character_id);
+    // the boxes share the row whatever the number of characters
+    let width = 90.0 / characters().len() as f32;
     spawner
         .spawn(
             Node {
-                width: Val::Percent(30.0),
+                width: Val::Percent(width),
                 height: Val::Percent(100.0),
                 flex_direction: FlexDirection::Column,
                 ..default()
@@ -201,26 +226,20 @@ fn create_character_box(
             ))
             .with_children(|spawner| {
                 spawner.spawn((
-                    Text::new(profile.name),
+                    Text::new(profile.name.clone()),
                     TextFont {
                         font: asset_server.load(PATH_BOLD_FONT),
-                        #[cfg(not(feature="phone"))]
                         font_size: 40.0,
-                        #[cfg(feature="phone")]
-                        font_size: 15.0,
                         ..Default::default()
                     },
                     TextLayout::new_with_justify(JustifyText::Center),
                     TextColor(Color::BLACK),
                 ));
                 spawner.spawn((
-                    Text::new(profile.description),
+                    Text::new(profile.description.clone()),
                     TextFont {
                         font: asset_server.load(PATH_BOLD_JP_FONT),
-                        #[cfg(not(feature="phone"))]
                         font_size: 30.0,
-                        #[cfg(feature="phone")]
-                        font_size: 10.0,
                         ..Default::default()
                     },
                     TextLayout::new_with_justify(JustifyText::Left),
@@ -233,20 +252,30 @@ fn create_character_box(
                     )),
                     TextFont {
                         font: asset_server.load(PATH_BOLD_JP_FONT),
-                        #[cfg(not(feature="phone"))]
                         font_size: 30.0,
-                        #[cfg(feature="phone")]
-                        font_size: 10.0,
                         ..Default::default()
                     },
                     TextLayout::new_with_justify(JustifyText::Left),
                     TextColor(Color::BLACK),
                 ));
                 spawner.spawn((ImageNode::new(asset_server.load(format!(
-                    "{}character_{}_chart.png",
-                    PATH_IMAGE_PREFIX, character_id
+                    "{}{}",
+                    layout.image_prefix, profile.chart
                 ))),));
             });
+            if team_battle {
+                spawner.spawn((
+                    Text::new(team_order_text(config, character_id)),
+                    TeamOrder(character_id),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    TextFont {
+                        font: asset_server.load(PATH_BOLD_JP_FONT),
+                        font_size: DEFAULT_FONT_SIZE * 0.6,
+                        ..Default::default()
+                    },
+                    TextColor(Color::BLACK),
+                ));
+            }
             if config.mode == GameMode::MultiPlayer {
                 spawner.spawn(
                     Node {
@@ -258,7 +287,7 @@ fn create_character_box(
                     }
                 )
                 .with_child((
-                    if character_id == 2 {
+                    if character_id == characters().len() as isize - 1 {
                         Text::new("Player 2")
                     } else {
                         Text::new("")
@@ -276,10 +305,53 @@ fn create_character_box(
         });
 }
 
+// place of the character in each team, the bot keeps its order to itself
+fn team_order_text(config: &GameConfig, character_id: isize) -> String {
+    let order = |team: &Vec<isize>| team.iter().position(|id| *id == character_id).unwrap_or(0) + 1;
+    if config.mode.has_bot() {
+        format!("{}番手", order(&config.teams[0]))
+    } else {
+        format!("1P {}番手 / 2P {}番手", order(&config.teams[0]), order(&config.teams[1]))
+    }
+}
+
+// moves the character to the front of the team, the others keep their order
+fn move_to_front(team: &mut Vec<isize>, character_id: isize) {
+    team.retain(|id| *id != character_id);
+    team.insert(0, character_id);
+}
+
+fn update_team_order(config: Res<GameConfig>, mut text_query: Query<(&mut Text, &TeamOrder)>) {
+    if !config.is_changed() {
+        return;
+    }
+    for (mut text, order) in text_query.iter_mut() {
+        text.0 = team_order_text(&config, order.0);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn rand_team() -> Vec<isize> {
+    use rand::seq::SliceRandom;
+    let mut team = (0..characters().len() as isize).collect::<Vec<_>>();
+    team.shuffle(&mut rand::rng());
+    team
+}
+
+#[cfg(target_arch = "wasm32")]
+fn rand_team() -> Vec<isize> {
+    let mut team = (0..characters().len() as isize).collect::<Vec<_>>();
+    for i in (1..team.len()).rev() {
+        let j = (web_sys::js_sys::Math::random() * (i + 1) as f64).floor() as usize;
+        team.swap(i, j);
+    }
+    team
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn choose_rand_character(id: isize) -> isize {
     use rand::seq::IteratorRandom;
-    let mut available_nums = vec![0, 1, 2];
+    let mut available_nums = (0..characters().len() as isize).collect::<Vec<_>>();
     available_nums.retain(|&x| x != id);
     available_nums
         .iter()
@@ -290,9 +362,9 @@ fn choose_rand_character(id: isize) -> isize {
 
 #[cfg(target_arch = "wasm32")]
 fn choose_rand_character(id: isize) -> isize {
-    let mut available_nums = vec![0, 1, 2];
+    let mut available_nums = (0..characters().len() as isize).collect::<Vec<_>>();
     available_nums.retain(|&x| x != id);
-    let random_index = (web_sys::js_sys::Math::random() * 2.0).floor() as usize;
+    let random_index = (web_sys::js_sys::Math::random() * available_nums.len() as f64).floor() as usize;
     available_nums[random_index]
 }
 
@@ -301,6 +373,7 @@ fn controller_input(
     mut next_state: ResMut<NextState<AppState>>,
     mut text_query: Query<(&mut Text, &TextColor, &CharacterID)>,
     mut config: ResMut<GameConfig>,
+    rules: Res<MatchRules>,
     gamepads: Query<(&Gamepad, Entity)>,
 ) {
     #[allow(unused_assignments)]
@@ -325,9 +398,9 @@ fn controller_input(
             }
         }
         if gamepad.just_pressed(GamepadButton::DPadRight) {
-            if character_id != 2 {
+            if character_id < characters().len() as isize - 1 {
                 config.characters_id[id] = character_id + 1;
-                if config.mode == GameMode::SinglePlayer {
+                if config.mode.has_bot() {
                     config.characters_id[1-id] = choose_rand_character(config.characters_id[id]);
                 }
                 for (mut text, text_color, character_id_text) in text_query.iter_mut() {
@@ -344,7 +417,7 @@ fn controller_input(
         } else if gamepad.just_pressed(GamepadButton::DPadLeft) {
             if character_id != 0 {
                 config.characters_id[id] = character_id - 1;
-                if config.mode == GameMode::SinglePlayer {
+                if config.mode.has_bot() {
                     config.characters_id[1-id] = choose_rand_character(config.characters_id[id]);
                 }
                 for (mut text, text_color, character_id_text) in text_query.iter_mut() {
@@ -359,6 +432,13 @@ fn controller_input(
                 }
             }
         }
+        // the team of the bot is not for the player to order
+        if gamepad.just_pressed(GamepadButton::DPadUp)
+            && config.is_team_battle(&rules)
+            && (id == 0 || config.mode == GameMode::MultiPlayer)
+        {
+            move_to_front(&mut config.teams[id], character_id);
+        }
         if gamepad.just_pressed(GamepadButton::East) {
             next_state.set(AppState::ConnectController);
         } else if gamepad.just_pressed(GamepadButton::West) {
@@ -367,10 +447,10 @@ fn controller_input(
     }
 }
 
-#[cfg(not(feature="phone"))]
 fn keyboard_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut config: ResMut<GameConfig>,
+    rules: Res<MatchRules>,
     mut text_query: Query<(&mut Text, &TextColor, &CharacterID)>,
 ) {
     /*
@@ -392,8 +472,12 @@ fn keyboard_input(
             }
         }
     }
+    if keys.just_pressed(KeyCode::KeyW) && config.is_team_battle(&rules) {
+        let side = if config.mode == GameMode::MultiPlayer { 1 } else { 0 };
+        move_to_front(&mut config.teams[side], character_id);
+    }
     if keys.just_pressed(KeyCode::KeyD) {
-        if character_id != 2 {
+        if character_id < characters().len() as isize - 1 {
             if config.mode == GameMode::MultiPlayer {
                 config.characters_id[1] = character_id + 1;
             } else {
@@ -524,11 +608,14 @@ impl Plugin for ChooseCharacterPlugin {
                 Update,
                 check_buttons.run_if(in_state(AppState::ChooseCharacter)),
             );
-        #[cfg(not(feature="phone"))]
         app
             .add_systems(
                 Update,
                 keyboard_input.run_if(in_state(AppState::ChooseCharacter)),
+            )
+            .add_systems(
+                Update,
+                update_team_order.run_if(in_state(AppState::ChooseCharacter)),
             );
         
         #[cfg(not(target_arch = "wasm32"))]
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
 use crate::{
-    AppState, GameConfig, GameMode, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT,
+    character_def::character,
+    ingame::rules::{Handicap, MatchRules, HANDICAP_RATIOS},
+    layout::Layout, AppState, GameConfig, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT,
     PATH_EXTRA_BOLD_JP_FONT, PATH_IMAGE_PREFIX, TITLE_FONT_SIZE,
 };
 
@@ -14,8 +16,91 @@ struct StartGameTimer(Timer);
 #[derive(Component)]
 struct CountText;
 
-fn setup(mut commands: Commands, config: Res<GameConfig>, asset_server: Res<AssetServer>) {
+/// A setting of the handicap of a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HandicapItem {
+    Health,
+    Damage,
+    Meter,
+    AutoBlock,
+}
+
+const HANDICAP_ITEMS: [HandicapItem; 4] = [
+    HandicapItem::Health,
+    HandicapItem::Damage,
+    HandicapItem::Meter,
+    HandicapItem::AutoBlock,
+];
+
+impl HandicapItem {
+    fn name(&self) -> &'static str {
+        match self {
+            HandicapItem::Health => "体力",
+            HandicapItem::Damage => "攻撃力",
+            HandicapItem::Meter => "ゲージ増加",
+            HandicapItem::AutoBlock => "オートガード",
+        }
+    }
+    fn text(&self, handicap: &Handicap) -> String {
+        match self {
+            HandicapItem::Health => format!("×{}", handicap.health),
+            HandicapItem::Damage => format!("×{}", handicap.damage),
+            HandicapItem::Meter => format!("×{}", handicap.meter),
+            HandicapItem::AutoBlock => if handicap.auto_block { "オン" } else { "オフ" }.to_string(),
+        }
+    }
+    /// Moves the setting `step` choices up or down
+    fn change(&self, handicap: &mut Handicap, step: i32) {
+        let ratio = match self {
+            HandicapItem::Health => &mut handicap.health,
+            HandicapItem::Damage => &mut handicap.damage,
+            HandicapItem::Meter => &mut handicap.meter,
+            HandicapItem::AutoBlock => {
+                handicap.auto_block = step > 0;
+                return;
+            }
+        };
+        let index = HANDICAP_RATIOS.iter().position(|value| value == ratio).unwrap_or(2) as i32;
+        *ratio = HANDICAP_RATIOS[(index + step).clamp(0, HANDICAP_RATIOS.len() as i32 - 1) as usize];
+    }
+}
+
+// value of a handicap setting
+#[derive(Component)]
+struct HandicapValue {
+    player: usize,
+    item: HandicapItem,
+}
+
+// "<" and ">" of a handicap setting
+#[derive(Component)]
+struct HandicapButton {
+    player: usize,
+    item: HandicapItem,
+    step: i32,
+}
+
+// row of a handicap setting, framed while a gamepad selects it
+#[derive(Component)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+struct HandicapRow {
+    player: usize,
+    item: usize,
+}
+
+// setting of the handicap each player's gamepad selects
+#[derive(Resource, Default)]
+struct HandicapCursor([usize; 2]);
+
+fn setup(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    layout: Res<Layout>,
+    asset_server: Res<AssetServer>,
+    rules: Res<MatchRules>,
+) {
     info!("setup");
+    commands.insert_resource(HandicapCursor::default());
     commands
     .spawn((
         ImageNode::new(
@@ -45,10 +130,7 @@ fn setup(mut commands: Commands, config: Res<GameConfig>, asset_server: Res<Asse
                         Node {
                             justify_self: JustifySelf::Start,
                             align_self: AlignSelf::Start,
-                            #[cfg(not(feature="phone"))]
                             border: UiRect::all(Val::Px(5.0)),
-                            #[cfg(feature="phone")]
-                            border: UiRect::all(Val::Px(2.0)),
                             ..default()
                         },
                         BorderRadius::MAX,
@@ -142,7 +224,7 @@ fn setup(mut commands: Commands, config: Res<GameConfig>, asset_server: Res<Asse
                             BackgroundColor(Color::srgba(0.6, 0.8, 0.9, 0.8)),
                         ))
                             .with_children(|spawner| {
-                                create_player_box(spawner, &asset_server, 0, config.characters_id[0], false);
+                                create_player_box(spawner, &asset_server, &layout, 0, &config, &rules, false);
                                 spawner.spawn((
                                     Text::new("VS"),
                                     TextFont {
@@ -153,7 +235,7 @@ fn setup(mut commands: Commands, config: Res<GameConfig>, asset_server: Res<Asse
                                     TextColor(Color::srgba(20.0, 0.0, 0.0, 1.0)),
                                     TextLayout::new_with_justify(JustifyText::Center),
                                 ));
-                                create_player_box(spawner, &asset_server, 1, config.characters_id[1], config.mode == GameMode::SinglePlayer);
+                                create_player_box(spawner, &asset_server, &layout, 1, &config, &rules, config.mode.has_bot());
                             });
                         spawner.spawn((
                             Button,
@@ -185,10 +267,14 @@ fn setup(mut commands: Commands, config: Res<GameConfig>, asset_server: Res<Asse
 fn create_player_box(
     spawner: &mut ChildSpawnerCommands,
     asset_server: &Res<AssetServer>,
+    layout: &Layout,
     player_id: u8,
-    character_id: isize,
+    config: &GameConfig,
+    rules: &MatchRules,
     agent: bool,
 ) {
+    let character_id = config.starting_character(player_id as usize, rules);
+    let handicap = &config.handicaps[player_id as usize];
     spawner.spawn((
         Node {
             width: Val::Percent(40.0),
@@ -213,10 +299,29 @@ fn create_player_box(
             TextColor(Color::BLACK),
             TextLayout::new_with_justify(JustifyText::Center),
         ));
+        // the lineup of a team battle, the portrait is the one fighting first
+        if config.is_team_battle(rules) {
+            spawner.spawn((
+                Text::new(
+                    config.teams[player_id as usize]
+                        .iter()
+                        .map(|id| character(*id).name.clone())
+                        .collect::<Vec<_>>()
+                        .join(" → "),
+                ),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_JP_FONT),
+                    font_size: DEFAULT_FONT_SIZE * 0.6,
+                    ..Default::default()
+                },
+                TextColor(Color::BLACK),
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+        }
         spawner.spawn((
             Node {
                 width: Val::Percent(100.0),
-                height: Val::Percent(80.0),
+                height: Val::Percent(if config.is_team_battle(rules) { 42.0 } else { 50.0 }),
                 flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
                 justify_items: JustifyItems::Center,
@@ -226,14 +331,167 @@ fn create_player_box(
         .with_children(|spawner| {
             spawner.spawn((
                 ImageNode::new(asset_server.load(format!(
-                    "{}character_{}.png",
-                    PATH_IMAGE_PREFIX, character_id + 1
+                    "{}{}",
+                    layout.image_prefix, character(character_id).portrait
                 ))),
+                Node {
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
             ));
         });
+        for (index, item) in HANDICAP_ITEMS.iter().enumerate() {
+            create_handicap_row(spawner, asset_server, player_id as usize, index, *item, handicap);
+        }
     });
 }
 
+fn create_handicap_row(
+    spawner: &mut ChildSpawnerCommands,
+    asset_server: &Res<AssetServer>,
+    player: usize,
+    index: usize,
+    item: HandicapItem,
+    handicap: &Handicap,
+) {
+    let font = TextFont {
+        font: asset_server.load(PATH_BOLD_JP_FONT),
+        font_size: DEFAULT_FONT_SIZE * 0.6,
+        ..Default::default()
+    };
+    spawner
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(9.0),
+                border: UiRect::all(Val::Px(3.0)),
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            // the gamepads start on the first setting
+            BorderColor(Color::srgba(10.0, 0.0, 0.0, if cfg!(not(target_arch = "wasm32")) && index == 0 { 0.8 } else { 0.0 })),
+            BorderRadius::all(Val::Px(10.0)),
+            HandicapRow { player, item: index },
+        ))
+        .with_children(|spawner| {
+            spawner.spawn((
+                Text::new(item.name()),
+                font.clone(),
+                TextColor(Color::BLACK),
+                Node {
+                    width: Val::Percent(45.0),
+                    ..default()
+                },
+            ));
+            for (label, step) in [("<", -1), (">", 1)] {
+                if step > 0 {
+                    spawner.spawn((
+                        Text::new(item.text(handicap)),
+                        font.clone(),
+                        TextColor(Color::BLACK),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        Node {
+                            width: Val::Percent(30.0),
+                            ..default()
+                        },
+                        HandicapValue { player, item },
+                    ));
+                }
+                spawner
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Percent(10.0),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BackgroundColor(Color::WHITE),
+                        BorderRadius::MAX,
+                        HandicapButton { player, item, step },
+                    ))
+                    .with_child((
+                        Text::new(label),
+                        font.clone(),
+                        TextColor(Color::BLACK),
+                    ));
+            }
+        });
+}
+
+/// Changes a handicap setting and shows its new value.
+/// The countdown starts over so the players have time to choose.
+fn change_handicap(
+    config: &mut GameConfig,
+    timer: &mut StartGameTimer,
+    value_query: &mut Query<(&mut Text, &HandicapValue)>,
+    player: usize,
+    item: HandicapItem,
+    step: i32,
+) {
+    item.change(&mut config.handicaps[player], step);
+    for (mut text, value) in value_query.iter_mut() {
+        if value.player == player && value.item == item {
+            text.0 = item.text(&config.handicaps[player]);
+        }
+    }
+    timer.0.reset();
+}
+
+fn handicap_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut config: ResMut<GameConfig>,
+    mut timer: ResMut<StartGameTimer>,
+    interaction_query: Query<(&Interaction, &HandicapButton), Changed<Interaction>>,
+    mut value_query: Query<(&mut Text, &HandicapValue)>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        commands.spawn((
+            AudioPlayer::new(asset_server.load(format!("{}setting_button.ogg", PATH_SOUND_PREFIX))),
+            SoundEffect,
+        ));
+        change_handicap(&mut config, &mut timer, &mut value_query, button.player, button.item, button.step);
+    }
+}
+
+// each player chooses the own handicap with the d-pad of the own gamepad
+#[cfg(not(target_arch = "wasm32"))]
+fn controller_handicap(
+    mut config: ResMut<GameConfig>,
+    mut timer: ResMut<StartGameTimer>,
+    mut cursor: ResMut<HandicapCursor>,
+    gamepads: Query<(&Gamepad, Entity)>,
+    mut row_query: Query<(&mut BorderColor, &HandicapRow)>,
+    mut value_query: Query<(&mut Text, &HandicapValue)>,
+) {
+    for (gamepad, entity) in gamepads.iter() {
+        let player = if config.gamepads[0] == entity { 0 } else { 1 };
+        if gamepad.just_pressed(GamepadButton::DPadUp) {
+            cursor.0[player] = cursor.0[player].saturating_sub(1);
+        } else if gamepad.just_pressed(GamepadButton::DPadDown) {
+            cursor.0[player] = (cursor.0[player] + 1).min(HANDICAP_ITEMS.len() - 1);
+        } else if gamepad.just_pressed(GamepadButton::DPadLeft) || gamepad.just_pressed(GamepadButton::DPadRight) {
+            let step = if gamepad.just_pressed(GamepadButton::DPadRight) { 1 } else { -1 };
+            let item = HANDICAP_ITEMS[cursor.0[player]];
+            change_handicap(&mut config, &mut timer, &mut value_query, player, item, step);
+            continue;
+        } else {
+            continue;
+        }
+        for (mut border_color, row) in row_query.iter_mut() {
+            if row.player == player {
+                border_color.0.set_alpha(if row.item == cursor.0[player] { 0.8 } else { 0.0 });
+            }
+        }
+        timer.0.reset();
+    }
+}
+
 fn update(
     time: Res<Time>,
     mut timer: ResMut<StartGameTimer>,
@@ -330,6 +588,9 @@ impl Plugin for ConfirmPlugin {
             .add_systems(OnExit(AppState::Confirm), exit)
             .add_systems(Update, update.run_if(in_state(AppState::Confirm)))
             .add_systems(Update, check_buttons.run_if(in_state(AppState::Confirm)))
+            .add_systems(Update, handicap_buttons.run_if(in_state(AppState::Confirm)))
             .add_systems(Update, controller_input.run_if(in_state(AppState::Confirm)));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, controller_handicap.run_if(in_state(AppState::Confirm)));
     }
 }
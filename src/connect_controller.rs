@@ -4,7 +4,7 @@ use bevy::{
 };
 
 use crate::{
-    AppState, GameConfig, GameMode, SoundEffect, PATH_SOUND_PREFIX, PATH_BOLD_FONT, PATH_BOLD_JP_FONT, PATH_EXTRA_BOLD_JP_FONT,
+    AppState, GameConfig, SoundEffect, PATH_SOUND_PREFIX, PATH_BOLD_FONT, PATH_BOLD_JP_FONT, PATH_EXTRA_BOLD_JP_FONT,
     PATH_IMAGE_PREFIX, TITLE_FONT_SIZE,
 };
 
@@ -132,7 +132,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, game_config: Re
                             BorderRadius::all(Val::Px(20.0)),
                         ))
                         .with_children(|spawner| {
-                            if game_config.mode == GameMode::SinglePlayer {
+                            if game_config.mode.has_bot() {
                                 create_controller_box(
                                     spawner,
                                     &asset_server,
@@ -265,7 +265,7 @@ fn update_controller(
     connection_event.clear();
 
     // Count required controllers based on game mode
-    let required_controllers = if game_config.mode == GameMode::SinglePlayer {
+    let required_controllers = if game_config.mode.has_bot() {
         1
     } else {
         2
@@ -1,14 +1,18 @@
 use crate::{
-    CharacterTextures,
     character_def::*,
-    ingame::{player::*, pose::*, rand, Fighting},
-    AppState, GameConfig, GameMode,
+    ingame::{frame_data::MoveKind, player::*, rand, sim::*, Fighting, Game, LogicStep},
+    GameConfig, GameMode,
 };
 use bevy::prelude::*;
 
-// Agent select action every 1/AGENT_FREQUENCY seconds
+// Agent selects an action on every logic step and updates its policy every AGENT_FREQUENCY * 2 steps
 const AGENT_FREQUENCY: f32 = 30.0;
 
+// energy thresholds of the policies are given in percent of the cost of the skill
+fn skill_percent(percent: u16) -> u16 {
+    SKILL_COST * percent / 100
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Level {
     Easy = 1,
@@ -84,6 +88,12 @@ enum Action {
     Punch,
     // Special skill attack(varies depending on character)
     Skill,
+    // Second special of the character, cheaper than the skill
+    Special,
+    // Grab and throw the opponent at close range(beats guarding and bending down)
+    Throw,
+    // Press throw while being thrown to escape
+    TechThrow,
     // No action
     None,
 }
@@ -119,6 +129,9 @@ impl ActionState {
             Action::BackKick => (49, ActionPriority::Medium),
             Action::RangedAttack => (32, ActionPriority::Medium),
             Action::Skill => (180, ActionPriority::High),
+            Action::Special => (50, ActionPriority::High),
+            Action::Throw => (33, ActionPriority::Medium),
+            Action::TechThrow => (1, ActionPriority::High),
             Action::None => (0, ActionPriority::Low),
         };
         
@@ -152,19 +165,20 @@ struct Environment {
     distance: f32,
     player_state: PlayerState,
     agent_facing: bool,
-    agent_energy: u8,
+    agent_energy: u16,
     agent_fire_charge: u16,
-    player_energy: u8,
+    player_energy: u16,
     player_fire_charge: u16,
     health_advantage: f32,  // positive if agent has more health
     energy_advantage: i16,  // positive if agent has more energy
     fire_charge_advantage: i32, // positive if agent has more fire charge
     is_player_vulnerable: bool, // player is in stunned or cooldown state
+    player_wakeup: u16, // frames left until the player can be hit after getting up
+    agent_special: Option<SpecialKind>,
 }
 
 #[derive(Resource)]
 pub struct Agent {
-    timer: Timer,
     count: u32,
     level: Level,
     policy: Policy,
@@ -177,7 +191,6 @@ impl Agent {
     /// Create a new agent with specified difficulty level
     pub fn new(level: Level) -> Self {
         Self {
-            timer: Timer::from_seconds(0.12 / AGENT_FREQUENCY, TimerMode::Repeating),
             count: 0,
             level,
             policy: Policy::Neutral,
@@ -233,7 +246,7 @@ impl Agent {
                             self.policy = if policy_score.offensive > 0.4 { Policy::Offensive } else { Policy::Neutral };
                         } else {
                             // Proactive engagement at medium range
-                            self.policy = if environment.player_energy > 80 { Policy::Defensive } else { Policy::Offensive };
+                            self.policy = if environment.player_energy > skill_percent(80) { Policy::Defensive } else { Policy::Offensive };
                         }
                     }
                 } else if !environment.agent_state.is_idle() {
@@ -256,7 +269,7 @@ impl Agent {
                         // Medium health - balanced but proactive approach
                         if environment.is_player_vulnerable {
                             self.policy = Policy::Offensive;
-                        } else if environment.distance < 250.0 && environment.player_energy < 50 {
+                        } else if environment.distance < 250.0 && environment.player_energy < skill_percent(50) {
                             // Player is tired at close range - be aggressive
                             self.policy = Policy::Offensive;
                         } else if environment.distance < 350.0 {
@@ -283,7 +296,7 @@ impl Agent {
                             self.policy = Policy::Offensive;
                         } else if environment.distance < 100.0 {
                             // Very close - use situation to our advantage
-                            self.policy = if environment.player_energy < 30 { Policy::Offensive } else { Policy::Defensive };
+                            self.policy = if environment.player_energy < skill_percent(30) { Policy::Offensive } else { Policy::Defensive };
                         } else {
                             // Optimal engagement range
                             let best_policy = policy_score.get_best_policy();
@@ -316,9 +329,9 @@ impl Agent {
                         }
                     } else if environment.health_advantage < -0.2 {
                         // Health disadvantage but still engage strategically
-                        if environment.distance < 180.0 && environment.player_energy > 70 {
+                        if environment.distance < 180.0 && environment.player_energy > skill_percent(70) {
                             self.policy = Policy::Defensive;
-                        } else if environment.energy_advantage > 15 || environment.fire_charge_advantage > 30 {
+                        } else if environment.energy_advantage > skill_percent(15) as i16 || environment.fire_charge_advantage > 30 {
                             // Use resource advantage
                             self.policy = Policy::Offensive;
                         } else {
@@ -373,7 +386,7 @@ impl Agent {
         }
         
         // Engage if we have significant resource advantages
-        if environment.energy_advantage > 30 || environment.fire_charge_advantage > 50 {
+        if environment.energy_advantage > skill_percent(30) as i16 || environment.fire_charge_advantage > 50 {
             return true;
         }
         
@@ -406,9 +419,9 @@ impl Agent {
         }
         
         // Energy advantage scoring
-        if environment.energy_advantage > 15 {
+        if environment.energy_advantage > skill_percent(15) as i16 {
             offensive_score += 0.2;
-        } else if environment.energy_advantage < -15 {
+        } else if environment.energy_advantage < -(skill_percent(15) as i16) {
             defensive_score += 0.2;
         }
         
@@ -455,7 +468,7 @@ impl Agent {
             }
             Level::Hard => {
                 offensive_score += 0.15; // More aggressive
-                if environment.agent_energy == ENERGY_MAX {
+                if environment.agent_energy >= SKILL_COST {
                     offensive_score += 0.1; // Ready for skill usage
                 }
             }
@@ -486,10 +499,10 @@ impl Agent {
             }
         } else {
             // Medium distance - assess situation carefully
-            if environment.player_energy < 20 {
+            if environment.player_energy < skill_percent(20) {
                 // Player is tired - risky but potentially rewarding attack
                 Policy::Offensive
-            } else if environment.energy_advantage > 10 {
+            } else if environment.energy_advantage > skill_percent(10) as i16 {
                 // We have energy advantage despite low health
                 Policy::Offensive
             } else {
@@ -504,10 +517,35 @@ impl Agent {
         // First, update the current action state timer
         self.action_state.tick();
         
+        // Being thrown - try to escape within the tech window
+        if environment.agent_state.check(PlayerState::THROWN) {
+            if self.should_tech_throw() {
+                self.action_state = ActionState::new(Action::TechThrow);
+                return Action::TechThrow;
+            }
+            return Action::None;
+        }
+        
+        // Knocked down - get up at once or roll away while lying on the floor
+        if environment.agent_state.check(PlayerState::KNOCKDOWN) {
+            // One press is enough, the agent is free to act once it stands
+            self.action_state = ActionState::new(Action::None);
+            return self.select_wake_up_action(environment);
+        }
+        
         if environment.agent_state.check(PlayerState::COOLDOWN) {
             return Action::None;
         }
         
+        // Player is knocked down - wait next to it and attack as it gets up
+        if let Some(new_action) = self.select_okizeme_action(environment) {
+            if self.can_interrupt_for_action(new_action) {
+                self.action_state = ActionState::new(new_action);
+                return new_action;
+            }
+            return self.action_state.current_action;
+        }
+        
         // Check if we should continue current action
         if self.should_continue_current_action(environment) {
             return self.action_state.current_action;
@@ -522,6 +560,22 @@ impl Agent {
             }
         }
         
+        if self.should_use_special(environment) {
+            let new_action = Action::Special;
+            if self.can_interrupt_for_action(new_action) {
+                self.action_state = ActionState::new(new_action);
+                return new_action;
+            }
+        }
+        
+        if self.should_throw(environment) {
+            let new_action = Action::Throw;
+            if self.can_interrupt_for_action(new_action) {
+                self.action_state = ActionState::new(new_action);
+                return new_action;
+            }
+        }
+        
         if self.should_counter_attack(environment) {
             let new_action = self.get_counter_action(environment);
             if self.can_interrupt_for_action(new_action) {
@@ -652,6 +706,9 @@ impl Agent {
             Action::Kick | Action::BackKick | Action::Punch => ActionPriority::Medium,
             Action::RangedAttack => ActionPriority::Medium,
             Action::Skill => ActionPriority::High,
+            Action::Special => ActionPriority::High,
+            Action::Throw => ActionPriority::Medium,
+            Action::TechThrow => ActionPriority::High,
             Action::None => ActionPriority::Low,
         };
         
@@ -660,13 +717,129 @@ impl Agent {
     
     /// Check if agent should use skill ability
     fn should_use_skill(&self, environment: &Environment) -> bool {
-        environment.agent_energy == ENERGY_MAX && 
+        environment.agent_energy >= SKILL_COST && 
         (environment.player_state.check(PlayerState::IDLE) || 
          environment.player_state.check(PlayerState::WALKING) ||
          environment.is_player_vulnerable) &&
         environment.distance < 400.0
     }
     
+    /// Check if agent should use the second special of its character
+    fn should_use_special(&self, environment: &Environment) -> bool {
+        if environment.agent_energy < SPECIAL_COST
+            || !(environment.agent_state.is_idle() || environment.agent_state.is_just_walk())
+        {
+            return false;
+        }
+        let player_jumping = environment.player_state.check(
+            PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD | PlayerState::JUMP_BACKWARD
+        );
+        let suitable = match environment.agent_special {
+            // Dash in from the middle distance at a player who is not guarding
+            Some(SpecialKind::Rush) => {
+                environment.distance > 200.0 && environment.distance < 500.0
+                    && !environment.player_state.check(PlayerState::GUARD)
+            }
+            // Recover while the player is far away
+            Some(SpecialKind::Heal) => environment.agent_health < 0.5 && environment.distance > 400.0,
+            // Stomp next to a player on the ground
+            Some(SpecialKind::Quake) => environment.distance < 300.0 && !player_jumping,
+            None => false,
+        };
+        if !suitable {
+            return false;
+        }
+        // Checked on every frame, so the chance per frame is small
+        let special_probability = match self.level {
+            Level::Easy => 0.002,
+            Level::Normal => 0.01,
+            Level::Hard => 0.03,
+        };
+        rand() < special_probability
+    }
+    
+    /// Check if agent should throw a player who is blocking or crouching at close range
+    fn should_throw(&self, environment: &Environment) -> bool {
+        // Throws only reach a player standing on the ground right in front
+        if environment.distance > 150.0
+            || !(environment.agent_state.is_idle() || environment.agent_state.is_just_walk())
+        {
+            return false;
+        }
+        let player_state = environment.player_state;
+        let turtling = player_state.check(PlayerState::GUARD | PlayerState::BEND_DOWN);
+        if !(turtling || player_state.is_idle() || player_state.is_just_walk()) {
+            return false;
+        }
+        // Checked on every frame, so the chance per frame is small
+        let throw_probability = match self.level {
+            Level::Easy => if turtling { 0.01 } else { 0.002 },
+            Level::Normal => if turtling { 0.04 } else { 0.01 },
+            Level::Hard => if turtling { 0.1 } else { 0.02 },
+        };
+        rand() < throw_probability
+    }
+    
+    /// Check if agent should press throw to escape, checked on every frame of the throw
+    fn should_tech_throw(&self) -> bool {
+        let tech_probability = match self.level {
+            Level::Easy => 0.02,
+            Level::Normal => 0.1,
+            Level::Hard => 0.3,
+        };
+        rand() < tech_probability
+    }
+    
+    /// Select how to get up while lying on the floor, checked on every frame of the knockdown
+    fn select_wake_up_action(&self, environment: &Environment) -> Action {
+        // Getting up early is only possible while lying (phase 1)
+        if environment.agent_animation_phase != 1 {
+            return Action::None;
+        }
+        let wake_up_probability = match self.level {
+            Level::Easy => 0.01,
+            Level::Normal => 0.05,
+            Level::Hard => 0.2,
+        };
+        if rand() >= wake_up_probability {
+            return Action::None;
+        }
+        // Roll away from a player waiting right next to us, otherwise get up at once
+        if environment.distance < 200.0 {
+            Action::RollBackward
+        } else {
+            Action::JumpUP
+        }
+    }
+    
+    /// Select the okizeme (pressure on a player getting up), None while the player is not knocked down.
+    /// The attack is pressed so that its active frames start when the wake-up invulnerability ends.
+    fn select_okizeme_action(&self, environment: &Environment) -> Option<Action> {
+        if !environment.player_state.check(PlayerState::KNOCKDOWN) && environment.player_wakeup == 0 {
+            return None;
+        }
+        // Easy agent does not know about okizeme
+        if self.level == Level::Easy
+            || !(environment.agent_state.is_idle() || environment.agent_state.is_just_walk())
+        {
+            return None;
+        }
+        // Walk up to the player while it is lying
+        if environment.distance > 130.0 {
+            return Some(Action::MoveForward);
+        }
+        let kick_startup = MoveKind::Kick.frame_data().startup;
+        let throw_startup = MoveKind::Throw.frame_data().startup;
+        // Hard agent mixes a meaty kick with a throw against a player who guards on wake-up
+        if environment.player_wakeup == kick_startup && (self.level == Level::Normal || rand() < 0.5) {
+            Some(Action::Kick)
+        } else if environment.player_wakeup == throw_startup && self.level == Level::Hard {
+            Some(Action::Throw)
+        } else {
+            Some(Action::None)
+        }
+    }
+    
     /// Check if agent should counter-attack player's action
     fn should_counter_attack(&self, environment: &Environment) -> bool {
         environment.player_state.check(PlayerState::KICKING | PlayerState::PUNCHING | PlayerState::BACK_KICKING)
@@ -1428,351 +1601,99 @@ impl Agent {
 
 /// Main agent system that controls AI behavior and decision making
 pub fn agent_system(
-    mut commands: Commands,
-    mut fighting: ResMut<Fighting>,
-    time: Res<Time>,
     game_config: Res<GameConfig>,
     mut agent: ResMut<Agent>,
-    character_textures: Res<CharacterTextures>,
-    mut player_query: Query<(&mut Player, &PlayerID, &mut Sprite, &mut Transform)>,
+    game: Res<Game>,
+    mut command_query: Query<(&mut FighterCommand, &PlayerID)>,
 ) {
     // Skip if multiplayer
     if game_config.mode == GameMode::MultiPlayer {
         return;
     }
-    agent.timer.tick(time.delta());
-    if agent.timer.finished() {
-        // update facing
-        update_facing(&mut player_query);
-
-        agent.count += 1;
-        let mut environment = Environment::default();
-        
-        // Collect environment data
-        if let Some((player, _, _, transform)) = player_query.iter().find(|(_, id, _, _)| id.0 == 0) {
-            environment.player_health = player.health as f32
-                / CHARACTER_PROFILES[player.character_id as usize].health as f32;
-            environment.player_state = player.state;
-            environment.distance = transform.translation.x;
-            environment.player_energy = player.energy;
-            environment.player_fire_charge = player.fire_charge;
-        }
-        if let Some((player, _, _, transform)) = player_query.iter().find(|(_, id, _, _)| id.0 == 1) {
-            environment.agent_health = player.health as f32
-                / CHARACTER_PROFILES[player.character_id as usize].health as f32;
-            environment.agent_animation_phase = player.animation.phase;
-            environment.agent_facing = player.pose.facing;
-            environment.distance = (transform.translation.x - environment.distance).abs();
-            environment.agent_state = player.state;
-            environment.agent_energy = player.energy;
-            environment.agent_fire_charge = player.fire_charge;
-        }
-        
-        // Calculate enhanced environment variables
-        environment.health_advantage = environment.agent_health - environment.player_health;
-        environment.energy_advantage = environment.agent_energy as i16 - environment.player_energy as i16;
-        environment.fire_charge_advantage = environment.agent_fire_charge as i32 - environment.player_fire_charge as i32;
-        environment.is_player_vulnerable = environment.player_state.check(
-            PlayerState::STUN | PlayerState::COOLDOWN | PlayerState::SKILL
-        ) || (environment.player_state.check(PlayerState::KICKING | PlayerState::PUNCHING | PlayerState::BACK_KICKING) 
-              && environment.distance > 200.0);
-        
-        // Update policy every 2 seconds (was every 2 seconds before)
-        if agent.count >= AGENT_FREQUENCY as u32 * 2 {
-            agent.count = 0;
-            agent.select_policy(&environment);
-        }
-        
-        // Select action with continuity
-        let action = agent.select_action(&environment);
-        
-        // Execute action on agent
-        if let Some((mut player, player_id, mut sprite, _)) = player_query.iter_mut().find(|(_, id, _, _)| id.0 == 1) {
-            // Reset engagement timer when taking action
-            if action != Action::None {
-                agent.engagement_timer = 0;
-            }
-            
-            // Handle action execution with better state management
-            execute_agent_action(action, &mut player, player_id, &mut sprite, &character_textures, &mut commands, &mut fighting);
+    agent.count += 1;
+    let mut environment = Environment::default();
+    
+    // Collect environment data
+    let player = &game.players[0];
+    environment.player_health = health_ratio(player);
+    environment.player_state = player.state;
+    environment.player_energy = player.energy;
+    environment.player_fire_charge = player.fire_charge;
+    environment.player_wakeup = player.wakeup;
+    let player = &game.players[1];
+    environment.agent_health = health_ratio(player);
+    environment.agent_animation_phase = player.animation.phase;
+    environment.agent_facing = player.pose.facing;
+    environment.distance = (game.positions[1].x - game.positions[0].x).abs();
+    environment.agent_state = player.state;
+    environment.agent_energy = player.energy;
+    environment.agent_fire_charge = player.fire_charge;
+    environment.agent_special = Some(player.profile().special);
+    
+    // Calculate enhanced environment variables
+    environment.health_advantage = environment.agent_health - environment.player_health;
+    environment.energy_advantage = environment.agent_energy as i16 - environment.player_energy as i16;
+    environment.fire_charge_advantage = environment.agent_fire_charge as i32 - environment.player_fire_charge as i32;
+    environment.is_player_vulnerable = environment.player_state.check(
+        PlayerState::STUN | PlayerState::DIZZY | PlayerState::COOLDOWN | PlayerState::SKILL
+    ) || (environment.player_state.check(PlayerState::KICKING | PlayerState::PUNCHING | PlayerState::BACK_KICKING) 
+          && environment.distance > 200.0);
+    
+    // Update policy every 2 seconds
+    if agent.count >= AGENT_FREQUENCY as u32 * 2 {
+        agent.count = 0;
+        agent.select_policy(&environment);
+    }
+    
+    // Select action with continuity
+    let action = agent.select_action(&environment);
+    
+    // Execute action on agent
+    if let Some((mut command, _)) = command_query.iter_mut().find(|(_, id)| id.0 == 1) {
+        // Reset engagement timer when taking action
+        if action != Action::None {
+            agent.engagement_timer = 0;
         }
+
+        // the action is applied with the other players' input by step_match
+        command.write(&agent_input(action, environment.agent_facing));
     }
 }
 
-/// Execute the selected action on the agent player
-fn execute_agent_action(
-    action: Action,
-    player: &mut Player,
-    player_id: &PlayerID,
-    sprite: &mut Sprite,
-    character_textures: &CharacterTextures,
-    commands: &mut Commands,
-    fighting: &mut Fighting,
-) {
-    // Only reset to idle state if we're changing to a non-movement action
-    if action != Action::MoveForward && action != Action::MoveBackward && player.state.check(PlayerState::WALKING) {
-        // Reset to idle for new actions
-        player.state &= !PlayerState::WALKING;
-        player.velocity = Vec2::ZERO;
-        if player.state.is_idle() {
-            sprite.image = character_textures.textures[player.character_id as usize].idle.clone();
-            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-            player.animation_frame_max = FRAMES_IDLE;
-            player.pose.set(IDLE_POSE1);
-            player.set_animation(IDLE_POSE2, 0, 15);
-        }
-    }
-    
-    // Only remove BEND_DOWN state when not bending
-    if action != Action::Bend && player.state.check(PlayerState::BEND_DOWN) && player.animation.phase != 2 {
-        // player is bending down
-        // then stop bending down
-        player.set_animation(BEND_DOWN_POSE1, 2, 23);
-        return;
-    }
-    
+/// Convert the selected action into the buttons of the agent player
+///
+/// # Arguments
+/// * `action` - The action selected by the agent
+/// * `facing` - Whether the agent is facing right
+fn agent_input(action: Action, facing: bool) -> FighterInput {
+    // forward and backward depend on which side the opponent is
+    let forward = FighterInput {
+        left: !facing,
+        right: facing,
+        ..Default::default()
+    };
+    let backward = FighterInput {
+        left: facing,
+        right: !facing,
+        ..Default::default()
+    };
     match action {
-        Action::MoveForward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].walk.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_WALK;
-                player.state |= PlayerState::WALKING;
-                player.pose.set(WALKING_POSE1);
-                player.set_animation(WALKING_POSE2, 1, 15);
-            }
-            if player.pose.facing {
-                player.state |= PlayerState::DIRECTION;
-            } else {
-                player.state &= !PlayerState::DIRECTION;
-            }
-        }
-        Action::MoveBackward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].walk.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = FRAMES_WALK - 1);
-                player.animation_frame_max = FRAMES_WALK;
-                player.state |= PlayerState::WALKING;
-                player.pose.set(WALKING_POSE1);
-                player.set_animation(WALKING_POSE2, 1, 15);
-            }
-            if player.pose.facing {
-                player.state &= !PlayerState::DIRECTION;
-            } else {
-                player.state |= PlayerState::DIRECTION;
-            }
-        }
-        Action::RollForward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].roll.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_ROLL;
-                player.state |= PlayerState::ROLL_FORWARD;
-                player.pose.set(ROLL_FORWARD_POSE1);
-                player.set_animation(ROLL_FORWARD_POSE2, 0, 11);
-                if player.pose.facing {
-                    player.state |= PlayerState::DIRECTION;
-                } else {
-                    player.state &= !PlayerState::DIRECTION;
-                }
-                let x_vel = if player.state.is_forward() { 1.0 } else { -1.0 }
-                    * CHARACTER_PROFILES[player.character_id as usize].agility * 2.0;
-                player.velocity = Vec2::new(x_vel, 0.0);
-            }
-        }
-        Action::RollBackward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].roll.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = FRAMES_ROLL - 1);
-                player.animation_frame_max = FRAMES_ROLL;
-                player.state |= PlayerState::ROLL_BACK;
-                player.pose.set(ROLL_BACK_POSE1);
-                player.set_animation(ROLL_BACK_POSE2, 0, 4);
-                if player.pose.facing {
-                    player.state &= !PlayerState::DIRECTION;
-                } else {
-                    player.state |= PlayerState::DIRECTION;
-                }
-                let x_vel = if player.state.is_forward() { -1.0 } else { 1.0 }
-                    * CHARACTER_PROFILES[player.character_id as usize].agility * 2.0;
-                player.velocity = Vec2::new(x_vel, 0.0);
-            }
-        }
-        Action::JumpUP => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_JUMP;
-                player.state |= PlayerState::JUMP_UP;
-                player.pose.set(JUMP_POSE1);
-                player.set_animation(JUMP_POSE2, 0, 11);
-                player.velocity = Vec2::new(0.0, 12.0);
-                player.energy += 1;
-            } else if !player.state.check(
-                PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD | PlayerState::JUMP_BACKWARD,
-            ) && player.state.check(PlayerState::WALKING) {
-                if player.state.check(PlayerState::DIRECTION) {
-                    sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                    sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                    player.animation_frame_max = FRAMES_JUMP;
-                    player.state |= PlayerState::JUMP_FORWARD;
-                    player.pose.set(JUMP_POSE1);
-                    player.set_animation(JUMP_POSE2, 0, 11);
-                    let x_vel = CHARACTER_PROFILES[player.character_id as usize].agility;
-                    player.velocity = Vec2::new(x_vel, 12.0);
-                } else {
-                    sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                    sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                    player.animation_frame_max = FRAMES_JUMP;
-                    player.state |= PlayerState::JUMP_BACKWARD;
-                    player.pose.set(JUMP_POSE1);
-                    player.set_animation(JUMP_POSE2, 0, 11);
-                    let x_vel = CHARACTER_PROFILES[player.character_id as usize].agility;
-                    player.velocity = Vec2::new(-x_vel, 12.0);
-                }
-                player.energy += 1;
-            }
-        }
-        Action::JumpForward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_JUMP;
-                if player.pose.facing {
-                    player.state |= PlayerState::DIRECTION;
-                } else {
-                    player.state &= !PlayerState::DIRECTION;
-                }
-                player.state |= PlayerState::JUMP_FORWARD;
-                player.pose.set(JUMP_POSE1);
-                player.set_animation(JUMP_POSE2, 0, 11);
-                player.velocity = Vec2::ZERO;
-                player.energy += 1;
-            }
-        }
-        Action::JumpBackward => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_JUMP;
-                if !player.pose.facing {
-                    player.state &= !PlayerState::DIRECTION;
-                } else {
-                    player.state |= PlayerState::DIRECTION;
-                }
-                player.state |= PlayerState::JUMP_BACKWARD;
-                player.pose.set(JUMP_POSE1);
-                player.set_animation(JUMP_POSE2, 0, 11);
-                player.velocity = Vec2::ZERO;
-                player.energy += 1;
-            }
-        }
-        Action::JumpKick => {
-            // player is jumping
-            // then just adding state
-            sprite.image = character_textures.textures[player.character_id as usize].jump_kick.clone();
-            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-            player.animation_frame_max = 1;
-            player.pose.set(JUMP_KICK_POSE);
-            player.state |= PlayerState::KICKING;
-            player.energy += 2;
-        }
-        Action::Bend => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].bend_down.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_BEND_DOWN;
-                player.state |= PlayerState::BEND_DOWN;
-                player.pose.set(BEND_DOWN_POSE1);
-                player.set_animation(BEND_DOWN_POSE2, 0, 27);
-            }
-        }
-        Action::Kick => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].kick.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_KICK;
-                player.state |= PlayerState::KICKING;
-                player.pose.set(KICK_POSE1);
-                player.set_animation(KICK_POSE2, 0, 21);
-                player.energy += 2;
-            } else if player
-                .state
-                .check(PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD)
-                && !player.state.check(PlayerState::KICKING)
-                && player.animation.phase > 0
-                && player.animation.phase < 4
-            {
-                // player is jumping
-                // then just adding state
-                sprite.image = character_textures.textures[player.character_id as usize].jump_kick.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = 1;
-                player.pose.set(JUMP_KICK_POSE);
-                player.state |= PlayerState::KICKING;
-                player.energy += 2;
-            }
-        }
-        Action::BackKick => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].back_kick.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_BACK_KICK;
-                player.state |= PlayerState::BACK_KICKING;
-                player.pose.set(BACK_KICK_POSE1);
-                player.set_animation(BACK_KICK_POSE2, 0, 11);
-                player.energy += 2;
-            }
-        }
-        Action::RangedAttack => {
-            if player.state.is_idle() && player.fire_charge == FIRE_CHARGE_MAX {
-                sprite.image = character_textures.textures[player.character_id as usize].punch.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_PUNCH;
-                player.fire_charge = 0;
-                player.state |= PlayerState::RANGED_ATTACK;
-                // the first pose for punch similar to that of kick
-                player.pose.set(KICK_POSE1);
-                player.set_animation(PUNCH_POSE, 0, 19);
-                player.energy += 2;
-            }
-        }
-        Action::Punch => {
-            if player.state.is_idle() {
-                sprite.image = character_textures.textures[player.character_id as usize].punch.clone();
-                sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                player.animation_frame_max = FRAMES_PUNCH;
-                player.state |= PlayerState::PUNCHING;
-                // the first pose for punch similar to that of kick
-                player.pose.set(KICK_POSE1);
-                player.set_animation(PUNCH_POSE, 0, 19);
-                player.energy += 2;
-            }
-        }
-        Action::Skill => {
-            if player.state.is_idle() && player.energy == ENERGY_MAX {
-                player.energy = 0;
-                player.state |= PlayerState::SKILL;
-                fighting.0 = player_id.0 + 1;
-                player.animation.phase = 0;
-                player.animation.count = 0;
-                if player.character_id == 1 {
-                    commands.insert_resource(SoulAbsorb);
-                }
-            }
-        }
-        Action::None => {
-            if player.state.check(PlayerState::WALKING) {
-                player.state &= !PlayerState::WALKING;
-                if player.state.is_idle() {
-                    sprite.image = character_textures.textures[player.character_id as usize].idle.clone();
-                    sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                    player.animation_frame_max = FRAMES_IDLE;
-                    player.pose.set(IDLE_POSE1);
-                    player.set_animation(IDLE_POSE2, 0, 15);
-                }
-            }
-        }
+        Action::MoveForward => forward,
+        Action::MoveBackward => backward,
+        Action::RollForward => FighterInput { down: true, ..forward },
+        Action::RollBackward => FighterInput { down: true, ..backward },
+        Action::JumpUP => FighterInput { jump: true, ..Default::default() },
+        Action::JumpForward => FighterInput { jump: true, ..forward },
+        Action::JumpBackward => FighterInput { jump: true, ..backward },
+        Action::JumpKick | Action::Kick => FighterInput { kick: true, ..Default::default() },
+        Action::Bend => FighterInput { down: true, ..Default::default() },
+        Action::BackKick => FighterInput { back_kick: true, ..Default::default() },
+        Action::RangedAttack => FighterInput { fire: true, ..Default::default() },
+        Action::Punch => FighterInput { punch: true, ..Default::default() },
+        Action::Skill => FighterInput { skill: true, ..Default::default() },
+        Action::Special => FighterInput { special: true, ..Default::default() },
+        Action::Throw | Action::TechThrow => FighterInput { throw: true, ..Default::default() },
+        Action::None => FighterInput::default(),
     }
 }
 
@@ -1781,8 +1702,10 @@ pub struct AgentPlugin;
 impl Plugin for AgentPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Agent::new(Level::Hard)).add_systems(
-            Update,
-            agent_system.run_if(in_state(AppState::Ingame).and(resource_exists::<Fighting>)),
+            FixedUpdate,
+            agent_system
+                .in_set(LogicStep::Input)
+                .run_if(resource_exists::<Fighting>),
         );
     }
 }
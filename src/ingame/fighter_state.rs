@@ -0,0 +1,545 @@
+// State machine of a fighter.
+//
+// `PlayerState` stays the set of flags the view, the agent and the hit checks read, but
+// the main action in those flags only changes through `Player::enter`. The allowed
+// changes are listed in `TRANSITIONS` with the window in which they may happen, and
+// entering an action always does the same work (sprite sheet, first key of the pose,
+// velocity), so an input path can no longer leave a fighter in a mix of flags it never
+// gets out of.
+use super::{frame_data::{ActiveMove, MoveKind}, pose::*, sim::*};
+use bevy::{log::error, math::Vec2};
+
+/// The main action of a fighter, at most one at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Idle,
+    Walk,
+    BendDown,
+    RollForward,
+    RollBack,
+    JumpUp,
+    JumpForward,
+    JumpBackward,
+    // a kick in the middle of a jump, the jump flag stays set
+    JumpKick,
+    Kick,
+    Punch,
+    RangedAttack,
+    BackKick,
+    Skill,
+    // the second special of the character, see `start_special`
+    Special,
+    Stun,
+    // a longer stagger when the dizzy meter is full, until the meter is empty
+    Dizzy,
+    // lying on the floor after a heavy hit, until getting up
+    KnockedDown,
+    // standing still with the guard up
+    Guard,
+    // grabbing and throwing the opponent
+    Throw,
+    // being thrown, the thrower moves this fighter
+    Thrown,
+}
+
+/// When a transition may happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    // at any time
+    Always,
+    // while the animation of the current action is in one of the phases
+    Phases(u8, u8),
+    // when the current segment of the animation is over
+    End,
+}
+
+pub struct Transition {
+    pub from: Action,
+    pub to: Action,
+    pub window: Window,
+}
+
+const fn transition(from: Action, to: Action, window: Window) -> Transition {
+    Transition { from, to, window }
+}
+
+/// Every change of action a fighter can make.
+/// Getting stunned, dizzy or knocked down is allowed from any action and is not listed.
+pub const TRANSITIONS: &[Transition] = &[
+    transition(Action::Idle, Action::Walk, Window::Always),
+    transition(Action::Idle, Action::BendDown, Window::Always),
+    transition(Action::Idle, Action::JumpUp, Window::Always),
+    transition(Action::Idle, Action::Kick, Window::Always),
+    transition(Action::Idle, Action::Punch, Window::Always),
+    transition(Action::Idle, Action::RangedAttack, Window::Always),
+    transition(Action::Idle, Action::BackKick, Window::Always),
+    transition(Action::Idle, Action::Skill, Window::Always),
+    transition(Action::Idle, Action::Special, Window::Always),
+    transition(Action::Idle, Action::Guard, Window::Always),
+    transition(Action::Idle, Action::Throw, Window::Always),
+    // a dash from a double tap
+    transition(Action::Idle, Action::RollForward, Window::Always),
+    transition(Action::Idle, Action::RollBack, Window::Always),
+    transition(Action::Walk, Action::Idle, Window::Always),
+    transition(Action::Walk, Action::RollForward, Window::Always),
+    transition(Action::Walk, Action::RollBack, Window::Always),
+    transition(Action::Walk, Action::JumpForward, Window::Always),
+    transition(Action::Walk, Action::JumpBackward, Window::Always),
+    transition(Action::Walk, Action::Guard, Window::Always),
+    transition(Action::Walk, Action::Throw, Window::Always),
+    transition(Action::Walk, Action::Special, Window::Always),
+    transition(Action::Guard, Action::Idle, Window::Always),
+    // bending down with the guard up is the low guard
+    transition(Action::Guard, Action::BendDown, Window::Always),
+    transition(Action::Guard, Action::Throw, Window::Always),
+    transition(Action::Guard, Action::Special, Window::Always),
+    // a throw beats standing, walking, the guard and bending down
+    transition(Action::Idle, Action::Thrown, Window::Always),
+    transition(Action::Walk, Action::Thrown, Window::Always),
+    transition(Action::Guard, Action::Thrown, Window::Always),
+    transition(Action::BendDown, Action::Thrown, Window::Always),
+    // both fighters let go at any time when the throw is teched
+    transition(Action::Throw, Action::Idle, Window::Always),
+    transition(Action::Thrown, Action::Idle, Window::Always),
+    transition(Action::BendDown, Action::Idle, Window::End),
+    transition(Action::RollForward, Action::Idle, Window::End),
+    transition(Action::RollBack, Action::Idle, Window::End),
+    // a jump kick can only be cancelled into while in the air
+    transition(Action::JumpUp, Action::JumpKick, Window::Phases(1, 3)),
+    transition(Action::JumpForward, Action::JumpKick, Window::Phases(1, 3)),
+    transition(Action::JumpUp, Action::Idle, Window::End),
+    transition(Action::JumpForward, Action::Idle, Window::End),
+    transition(Action::JumpBackward, Action::Idle, Window::End),
+    transition(Action::JumpKick, Action::JumpUp, Window::End),
+    transition(Action::JumpKick, Action::JumpForward, Window::End),
+    transition(Action::Kick, Action::Idle, Window::End),
+    transition(Action::Punch, Action::Idle, Window::End),
+    transition(Action::RangedAttack, Action::Idle, Window::End),
+    transition(Action::BackKick, Action::Idle, Window::End),
+    transition(Action::Skill, Action::Idle, Window::End),
+    transition(Action::Special, Action::Idle, Window::End),
+    transition(Action::Stun, Action::Idle, Window::End),
+    // the end of a dizzy is given by the meter, not by the animation
+    transition(Action::Dizzy, Action::Idle, Window::Always),
+    transition(Action::KnockedDown, Action::Idle, Window::End),
+    // a recovery roll instead of getting up, only while lying on the floor
+    transition(Action::KnockedDown, Action::RollForward, Window::Phases(1, 1)),
+    transition(Action::KnockedDown, Action::RollBack, Window::Phases(1, 1)),
+];
+
+// flags which are not an action and are kept when the action changes
+fn modifiers(state: PlayerState) -> PlayerState {
+    let mut modifiers = state;
+    modifiers &= PlayerState::DIRECTION | PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+    modifiers
+}
+
+impl Action {
+    /// The main action in a set of flags
+    pub fn of(state: PlayerState) -> Self {
+        let jumping = state.check(PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD | PlayerState::JUMP_BACKWARD);
+        if state.check(PlayerState::STUN) {
+            Action::Stun
+        } else if state.check(PlayerState::DIZZY) {
+            Action::Dizzy
+        } else if state.check(PlayerState::KNOCKDOWN) {
+            Action::KnockedDown
+        } else if state.check(PlayerState::THROWN) {
+            Action::Thrown
+        } else if state.check(PlayerState::SKILL) {
+            Action::Skill
+        } else if state.check(PlayerState::SPECIAL) {
+            Action::Special
+        } else if state.check(PlayerState::THROWING) {
+            Action::Throw
+        } else if jumping && state.check(PlayerState::KICKING) {
+            Action::JumpKick
+        } else if state.check(PlayerState::JUMP_UP) {
+            Action::JumpUp
+        } else if state.check(PlayerState::JUMP_FORWARD) {
+            Action::JumpForward
+        } else if state.check(PlayerState::JUMP_BACKWARD) {
+            Action::JumpBackward
+        } else if state.check(PlayerState::ROLL_FORWARD) {
+            Action::RollForward
+        } else if state.check(PlayerState::ROLL_BACK) {
+            Action::RollBack
+        } else if state.check(PlayerState::BEND_DOWN) {
+            Action::BendDown
+        } else if state.check(PlayerState::KICKING) {
+            Action::Kick
+        } else if state.check(PlayerState::PUNCHING) {
+            Action::Punch
+        } else if state.check(PlayerState::RANGED_ATTACK) {
+            Action::RangedAttack
+        } else if state.check(PlayerState::BACK_KICKING) {
+            Action::BackKick
+        } else if state.check(PlayerState::GUARD) {
+            Action::Guard
+        } else if state.check(PlayerState::WALKING) {
+            Action::Walk
+        } else {
+            Action::Idle
+        }
+    }
+    /// The flag of the action
+    pub fn flag(&self) -> PlayerState {
+        match self {
+            Action::Idle => PlayerState::IDLE,
+            Action::Walk => PlayerState::WALKING,
+            Action::BendDown => PlayerState::BEND_DOWN,
+            Action::RollForward => PlayerState::ROLL_FORWARD,
+            Action::RollBack => PlayerState::ROLL_BACK,
+            Action::JumpUp => PlayerState::JUMP_UP,
+            Action::JumpForward => PlayerState::JUMP_FORWARD,
+            Action::JumpBackward => PlayerState::JUMP_BACKWARD,
+            Action::JumpKick | Action::Kick => PlayerState::KICKING,
+            Action::Punch => PlayerState::PUNCHING,
+            Action::RangedAttack => PlayerState::RANGED_ATTACK,
+            Action::BackKick => PlayerState::BACK_KICKING,
+            Action::Skill => PlayerState::SKILL,
+            Action::Special => PlayerState::SPECIAL,
+            Action::Stun => PlayerState::STUN,
+            Action::Dizzy => PlayerState::DIZZY,
+            Action::KnockedDown => PlayerState::KNOCKDOWN,
+            Action::Guard => PlayerState::GUARD,
+            Action::Throw => PlayerState::THROWING,
+            Action::Thrown => PlayerState::THROWN,
+        }
+    }
+    /// The melee move of the action, its hitbox lives as long as the action.
+    /// The move of a special depends on the character, see `start_special`.
+    pub fn move_kind(&self) -> Option<MoveKind> {
+        match self {
+            Action::Kick => Some(MoveKind::Kick),
+            Action::JumpKick => Some(MoveKind::JumpKick),
+            Action::Punch => Some(MoveKind::Punch),
+            Action::BackKick => Some(MoveKind::BackKick),
+            Action::Throw => Some(MoveKind::Throw),
+            _ => None,
+        }
+    }
+    // the first key of the pose in `poses`, None keeps the current pose
+    fn first_key(&self, poses: &PoseSet) -> Option<Pose> {
+        match self {
+            Action::Idle => Some(poses.get(PoseName::Idle)),
+            Action::Walk => Some(WALKING_POSE1),
+            Action::BendDown => Some(BEND_DOWN_POSE1),
+            Action::RollForward => Some(poses.get(PoseName::RollForward1)),
+            Action::RollBack => Some(poses.get(PoseName::RollForward6)),
+            Action::JumpUp => Some(poses.get(PoseName::JumpUp1)),
+            Action::JumpKick => Some(poses.get(PoseName::JumpKick)),
+            // the first pose for punch similar to that of kick
+            Action::Kick | Action::Punch | Action::RangedAttack | Action::Throw => Some(poses.get(PoseName::Kick1)),
+            Action::BackKick => Some(poses.get(PoseName::BackKick1)),
+            Action::Stun | Action::Dizzy | Action::Thrown => Some(STUN_POSE1),
+            Action::Guard => Some(GUARD_POSE),
+            // the fall to the floor starts from the pose of the hit
+            Action::JumpForward | Action::JumpBackward | Action::Skill | Action::KnockedDown => None,
+            // the pose of a special depends on the character
+            Action::Special => None,
+        }
+    }
+    // the sprite sheet of the action, None keeps the current one
+    fn sprite(&self) -> Option<SpriteKind> {
+        match self {
+            Action::Idle | Action::Guard => Some(SpriteKind::Idle),
+            Action::Walk => Some(SpriteKind::Walk),
+            Action::BendDown => Some(SpriteKind::BendDown),
+            Action::RollForward | Action::RollBack => Some(SpriteKind::Roll),
+            Action::JumpUp | Action::JumpForward | Action::JumpBackward => Some(SpriteKind::Jump),
+            Action::JumpKick => Some(SpriteKind::JumpKick),
+            Action::Kick => Some(SpriteKind::Kick),
+            Action::Punch | Action::RangedAttack | Action::Throw => Some(SpriteKind::Punch),
+            Action::BackKick => Some(SpriteKind::BackKick),
+            Action::Stun | Action::Dizzy | Action::Thrown | Action::KnockedDown => Some(SpriteKind::Attacked),
+            Action::Skill | Action::Special => None,
+        }
+    }
+}
+
+impl Window {
+    fn is_open(&self, animation: &PlayerColliderAnimation) -> bool {
+        match self {
+            Window::Always => true,
+            Window::Phases(first, last) => (*first..=*last).contains(&animation.phase),
+            Window::End => animation.count == 0,
+        }
+    }
+}
+
+/// The transition from `from` to `to`, if there is one
+pub fn find_transition(from: Action, to: Action) -> Option<&'static Transition> {
+    TRANSITIONS
+        .iter()
+        .find(|transition| transition.from == from && transition.to == to)
+}
+
+impl Player {
+    /// The current main action
+    pub fn action(&self) -> Action {
+        Action::of(self.state)
+    }
+    /// true if the fighter may switch to `to` right now
+    pub fn can_enter(&self, to: Action) -> bool {
+        if matches!(to, Action::Stun | Action::Dizzy | Action::KnockedDown) {
+            return true;
+        }
+        find_transition(self.action(), to).is_some_and(|transition| transition.window.is_open(&self.animation))
+    }
+    /// Switches the fighter to the action `to`.
+    /// Leaves the current action, sets the flags and does the on-enter work of `to`:
+    /// the sprite sheet, the first key of the pose and the velocity.
+    /// The caller starts the first segment of the animation.
+    /// The caller checked `can_enter` or knows the transition is open, so a refused
+    /// transition is a bug: it fails a debug assertion and is logged in release builds.
+    ///
+    /// Returns false if the transition was refused.
+    pub fn enter(&mut self, to: Action) -> bool {
+        let from = self.action();
+        let entered = self.try_enter(to);
+        if !entered {
+            error!("illegal transition of a fighter: {:?} -> {:?} ({:#?}, phase {}, count {})", from, to, self.state, self.animation.phase, self.animation.count);
+            debug_assert!(false, "illegal transition of a fighter: {:?} -> {:?}", from, to);
+        }
+        entered
+    }
+    /// Switches the fighter to the action `to` like `enter`, for the callers to which a refusal is expected.
+    ///
+    /// Returns false and leaves the fighter as it is if `to` can't be entered now.
+    pub fn try_enter(&mut self, to: Action) -> bool {
+        let from = self.action();
+        if !self.can_enter(to) {
+            return false;
+        }
+        self.exit(from, to);
+        self.state = match to {
+            // starting over clears the direction and the cooldowns
+            Action::Idle | Action::Stun | Action::Dizzy | Action::Thrown | Action::KnockedDown => to.flag(),
+            Action::JumpKick => self.state | to.flag(),
+            _ => modifiers(self.state) | to.flag(),
+        };
+        self.on_enter(to);
+        true
+    }
+    fn exit(&mut self, from: Action, to: Action) {
+        if from.move_kind().is_some() {
+            // the move is over when the fighter leaves it
+            self.attack = None;
+        }
+        match from {
+            // the thrower lets go of the opponent
+            Action::Throw => self.grab = 0,
+            Action::Thrown => self.throw_tech = false,
+            // getting up or rolling away, nothing can touch the fighter for a while
+            Action::KnockedDown => self.wakeup = WAKE_UP_FRAMES,
+            Action::Special => self.attack = None,
+            _ => {}
+        }
+        let hit = |action: Action| matches!(action, Action::Stun | Action::Dizzy);
+        if hit(from) && !hit(to) {
+            // out of the stun, the combo of the opponent is over,
+            // and after a dizzy nothing stuns the fighter for a while
+            if self.combo.dizzy {
+                self.dizzy = 0;
+                self.invincible = self.invincible_frames;
+            }
+            self.combo = Combo::default();
+        }
+    }
+    fn on_enter(&mut self, to: Action) {
+        if let Some(sprite) = to.sprite() {
+            // walking away and rolling back play the sprite sheet backwards
+            let backward = to == Action::RollBack
+                || (to == Action::Walk && self.pose.facing != self.state.is_forward());
+            let frame = if backward { self.profile().frames(sprite) - 1 } else { 0 };
+            self.set_sprite(sprite, frame);
+        }
+        if let Some(key) = to.first_key(&self.poses) {
+            self.start_pose(key);
+        }
+        if let Some(kind) = to.move_kind() {
+            self.attack = Some(ActiveMove::new(kind));
+        }
+        match to {
+            Action::RollForward | Action::RollBack => {
+                let x_vel = if self.state.is_forward() { 1.0 } else { -1.0 } * self.agility * 2.0;
+                self.velocity = Vec2::new(x_vel, 0.0);
+            }
+            Action::Skill => {
+                self.animation.phase = 0;
+                self.animation.count = 0;
+            }
+            // keep the momentum of the walk and of the jump
+            Action::Walk | Action::JumpKick => {}
+            _ => self.velocity = Vec2::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character_def::character;
+    use std::sync::Arc;
+
+    const ALL: [Action; 21] = [
+        Action::Idle,
+        Action::Walk,
+        Action::BendDown,
+        Action::RollForward,
+        Action::RollBack,
+        Action::JumpUp,
+        Action::JumpForward,
+        Action::JumpBackward,
+        Action::JumpKick,
+        Action::Kick,
+        Action::Punch,
+        Action::RangedAttack,
+        Action::BackKick,
+        Action::Skill,
+        Action::Special,
+        Action::Stun,
+        Action::Dizzy,
+        Action::KnockedDown,
+        Action::Guard,
+        Action::Throw,
+        Action::Thrown,
+    ];
+
+    // the actions reachable from `from` through `TRANSITIONS`
+    fn reachable(from: Action) -> Vec<Action> {
+        let mut found = vec![from];
+        let mut next = 0;
+        while next < found.len() {
+            let current = found[next];
+            for transition in TRANSITIONS.iter().filter(|transition| transition.from == current) {
+                if !found.contains(&transition.to) {
+                    found.push(transition.to);
+                }
+            }
+            next += 1;
+        }
+        found
+    }
+
+    fn fighter(action: Action) -> Player {
+        let mut player = Player::new(Arc::new(character(0).clone()), PoseSet::default());
+        player.state = match action {
+            Action::JumpKick => PlayerState::JUMP_UP | PlayerState::KICKING,
+            _ => action.flag(),
+        };
+        player
+    }
+
+    #[test]
+    fn transitions_are_unique() {
+        for (i, a) in TRANSITIONS.iter().enumerate() {
+            assert!(a.from != a.to, "{:?} to itself", a.from);
+            for b in &TRANSITIONS[i + 1..] {
+                assert!(!(a.from == b.from && a.to == b.to), "{:?} -> {:?} listed twice", a.from, a.to);
+            }
+        }
+    }
+
+    #[test]
+    fn every_action_is_reachable_and_returns_to_idle() {
+        let from_idle = reachable(Action::Idle);
+        for action in ALL {
+            // getting hit is allowed from anywhere and is not listed
+            if !matches!(action, Action::Stun | Action::Dizzy | Action::KnockedDown) {
+                assert!(from_idle.contains(&action), "{:?} is not reachable from idle", action);
+            }
+            assert!(reachable(action).contains(&Action::Idle), "{:?} never gets back to idle", action);
+        }
+    }
+
+    #[test]
+    fn flags_give_back_the_action() {
+        for action in ALL {
+            assert_eq!(Action::of(fighter(action).state), action);
+        }
+    }
+
+    #[test]
+    fn end_window_waits_for_the_animation() {
+        let mut player = fighter(Action::Kick);
+        player.animation.count = 5;
+        assert!(!player.can_enter(Action::Idle));
+        player.animation.count = 0;
+        assert!(player.can_enter(Action::Idle));
+    }
+
+    #[test]
+    fn jump_kick_only_in_the_air() {
+        let mut player = fighter(Action::JumpUp);
+        for (phase, open) in [(0, false), (1, true), (3, true), (4, false)] {
+            player.animation.phase = phase;
+            assert_eq!(player.can_enter(Action::JumpKick), open, "phase {}", phase);
+        }
+    }
+
+    #[test]
+    fn getting_hit_is_always_allowed() {
+        for action in ALL {
+            let player = fighter(action);
+            for hit in [Action::Stun, Action::Dizzy, Action::KnockedDown] {
+                assert!(player.can_enter(hit), "{:?} -> {:?}", action, hit);
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_transition_is_refused() {
+        let mut player = fighter(Action::Kick);
+        player.animation.count = 5;
+        let attack = player.attack;
+        assert!(!player.try_enter(Action::Punch));
+        assert_eq!(player.action(), Action::Kick);
+        assert_eq!(player.attack, attack);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "illegal transition")]
+    fn illegal_enter_fails_in_debug_builds() {
+        let mut player = fighter(Action::Kick);
+        player.animation.count = 5;
+        player.enter(Action::Punch);
+    }
+
+    #[test]
+    fn enter_starts_and_ends_the_move() {
+        let mut player = fighter(Action::Idle);
+        assert!(player.enter(Action::Kick));
+        assert_eq!(player.action(), Action::Kick);
+        assert_eq!(player.attack.map(|attack| attack.kind), Some(MoveKind::Kick));
+        player.animation.count = 0;
+        assert!(player.enter(Action::Idle));
+        assert_eq!(player.attack, None);
+    }
+
+    #[test]
+    fn modifiers_survive_the_change() {
+        let mut player = fighter(Action::Idle);
+        player.state |= PlayerState::DIRECTION | PlayerState::COOLDOWN;
+        assert!(player.enter(Action::Walk));
+        assert!(player.state.check(PlayerState::DIRECTION));
+        assert!(player.state.check(PlayerState::COOLDOWN));
+        // starting over clears them
+        assert!(player.enter(Action::Idle));
+        assert!(!player.state.check(PlayerState::DIRECTION));
+    }
+
+    #[test]
+    fn invincible_after_a_dizzy() {
+        let mut player = fighter(Action::Dizzy);
+        player.combo.dizzy = true;
+        player.combo.hits = 4;
+        player.dizzy = 50;
+        assert!(player.enter(Action::Idle));
+        assert_eq!(player.invincible, player.invincible_frames);
+        assert_eq!(player.dizzy, 0);
+        assert_eq!(player.combo.hits, 0);
+    }
+}
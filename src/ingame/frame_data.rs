@@ -0,0 +1,488 @@
+// Frame data of the melee moves, with their hitboxes and the hurtboxes of the body.
+//
+// A move is split into startup, active and recovery frames counted from the logic step
+// the button was pressed on. Only during the active frames the hitbox of the move is
+// tested against the hurtboxes of the opponent, which are computed from the current pose
+// in the same way `update_pose` places the body parts, so a hit never depends on the
+// order of collision events or on the frame rate.
+use super::pose::Pose;
+use super::sim::BodyParts;
+use crate::layout::BodyLayout;
+use bevy::math::Vec2;
+
+/// Melee moves which have a hitbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    Punch,
+    Kick,
+    JumpKick,
+    BackKick,
+    // grabs instead of striking, see throw.rs
+    Throw,
+    // the specials of the characters, see `SpecialKind`
+    Rush,
+    Quake,
+}
+
+/// Box of an attack, relative to the fighter facing right
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hitbox {
+    pub center: Vec2,
+    pub half_size: Vec2,
+}
+
+/// Timing, reach and impact of a move.
+/// The move lasts `startup + active + recovery` frames, the hitbox is out on the
+/// frames `startup + 1 ..= startup + active`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameData {
+    pub startup: u16,
+    pub active: u16,
+    pub recovery: u16,
+    pub hitbox: Hitbox,
+    // frames both fighters freeze when the move connects
+    pub hitstop: u8,
+    // speed in px per frame the victim is sent away with, x points away from the attacker
+    pub knockback: Vec2,
+    // a clean hit knocks the victim down, see `knock_down`
+    pub knockdown: bool,
+}
+
+// the frames match the animations started in `apply_input`
+const PUNCH: FrameData = FrameData {
+    startup: 13,
+    active: 5,
+    recovery: 13,
+    hitbox: Hitbox {
+        center: Vec2::new(120.0, 30.0),
+        half_size: Vec2::new(40.0, 25.0),
+    },
+    hitstop: 6,
+    knockback: Vec2::new(4.0, 0.0),
+    knockdown: false,
+};
+const KICK: FrameData = FrameData {
+    startup: 15,
+    active: 5,
+    recovery: 25,
+    hitbox: Hitbox {
+        center: Vec2::new(130.0, -30.0),
+        half_size: Vec2::new(45.0, 35.0),
+    },
+    hitstop: 8,
+    knockback: Vec2::new(7.0, 0.0),
+    knockdown: false,
+};
+// the recovery of a jump kick is the landing
+const JUMP_KICK: FrameData = FrameData {
+    startup: 3,
+    active: 24,
+    recovery: 0,
+    hitbox: Hitbox {
+        center: Vec2::new(100.0, -80.0),
+        half_size: Vec2::new(50.0, 40.0),
+    },
+    hitstop: 10,
+    knockback: Vec2::new(10.0, 6.0),
+    knockdown: true,
+};
+const BACK_KICK: FrameData = FrameData {
+    startup: 15,
+    active: 3,
+    recovery: 30,
+    hitbox: Hitbox {
+        center: Vec2::new(140.0, -10.0),
+        half_size: Vec2::new(50.0, 30.0),
+    },
+    hitstop: 12,
+    knockback: Vec2::new(12.0, 5.0),
+    knockdown: true,
+};
+// the hitbox of a throw only reaches an opponent standing right in front
+// the impact is applied when the victim hits the floor, see throw.rs
+const THROW: FrameData = FrameData {
+    startup: 5,
+    active: 3,
+    recovery: 25,
+    hitbox: Hitbox {
+        center: Vec2::new(70.0, 0.0),
+        half_size: Vec2::new(30.0, 60.0),
+    },
+    hitstop: 8,
+    knockback: Vec2::new(6.0, 0.0),
+    knockdown: false,
+};
+
+// the fighter dashes forward during the active frames, see `step_fighter`
+const RUSH: FrameData = FrameData {
+    startup: 8,
+    active: 16,
+    recovery: 22,
+    hitbox: Hitbox {
+        center: Vec2::new(120.0, 30.0),
+        half_size: Vec2::new(50.0, 30.0),
+    },
+    hitstop: 10,
+    knockback: Vec2::new(10.0, 0.0),
+    knockdown: false,
+};
+// a wide box along the floor, a jump goes over it
+const QUAKE: FrameData = FrameData {
+    startup: 18,
+    active: 4,
+    recovery: 28,
+    hitbox: Hitbox {
+        center: Vec2::new(100.0, -170.0),
+        half_size: Vec2::new(200.0, 40.0),
+    },
+    hitstop: 10,
+    knockback: Vec2::new(4.0, 6.0),
+    knockdown: true,
+};
+
+impl MoveKind {
+    pub fn frame_data(&self) -> &'static FrameData {
+        match self {
+            MoveKind::Punch => &PUNCH,
+            MoveKind::Kick => &KICK,
+            MoveKind::JumpKick => &JUMP_KICK,
+            MoveKind::BackKick => &BACK_KICK,
+            MoveKind::Throw => &THROW,
+            MoveKind::Rush => &RUSH,
+            MoveKind::Quake => &QUAKE,
+        }
+    }
+}
+
+/// A move in progress
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveMove {
+    pub kind: MoveKind,
+    // logic frames since the move started, 1 on the first step
+    pub frame: u16,
+    // a move hits only once
+    pub hit: bool,
+}
+
+impl ActiveMove {
+    pub fn new(kind: MoveKind) -> Self {
+        Self {
+            kind,
+            frame: 0,
+            hit: false,
+        }
+    }
+    /// true while the hitbox is out and nothing has been hit yet
+    pub fn is_active(&self) -> bool {
+        let data = self.kind.frame_data();
+        !self.hit && self.frame > data.startup && self.frame <= data.startup + data.active
+    }
+    /// The hitbox in world coordinates as (min, max).
+    ///
+    /// # Arguments
+    /// * `position` - Position of the attacker
+    /// * `facing` - true when the attacker faces right
+    /// * `scale` - Scale of the arena
+    pub fn hitbox(&self, position: Vec2, facing: bool, scale: f32) -> (Vec2, Vec2) {
+        let hitbox = self.kind.frame_data().hitbox;
+        let sign = if facing { 1.0 } else { -1.0 };
+        let center = position + Vec2::new(hitbox.center.x * sign, hitbox.center.y) * scale;
+        let half_size = hitbox.half_size * scale;
+        (center - half_size, center + half_size)
+    }
+}
+
+/// A body part which can be hit, a segment with a radius
+#[derive(Debug, Clone, Copy)]
+pub struct Hurtbox {
+    pub part: BodyParts,
+    pub start: Vec2,
+    pub end: Vec2,
+    pub radius: f32,
+}
+
+// position and angle of a body part, angles are counterclockwise in radians
+#[derive(Clone, Copy)]
+struct Joint {
+    position: Vec2,
+    angle: f32,
+}
+
+impl Joint {
+    fn child(&self, local: Vec2, angle: f32) -> Self {
+        Self {
+            position: self.position + Vec2::from_angle(self.angle).rotate(local),
+            angle: self.angle + angle,
+        }
+    }
+    fn hurtbox(&self, part: BodyParts, half_length: f32, radius: f32) -> Hurtbox {
+        let axis = Vec2::from_angle(self.angle).rotate(Vec2::new(0.0, half_length));
+        Hurtbox {
+            part,
+            start: self.position - axis,
+            end: self.position + axis,
+            radius,
+        }
+    }
+}
+
+// same as `rotate_parts` in player.rs
+fn limb(parent: &Joint, x_offset: f32, y_offset: f32, degree: f32, length: f32) -> Joint {
+    let rad = degree.to_radians();
+    parent.child(
+        Vec2::new(x_offset + length * rad.sin(), y_offset + length * (1.0 - rad.cos())),
+        rad,
+    )
+}
+
+/// The hurtboxes of a fighter in world coordinates, in the order of the body parts
+/// spawned by `spawn_player`.
+///
+/// # Arguments
+/// * `pose` - Current pose of the fighter
+/// * `position` - Position of the fighter
+/// * `body` - Sizes of the body parts
+/// * `scale` - Scale of the arena, applied to the offset of the pose
+pub fn hurtboxes(pose: &Pose, position: Vec2, body: &BodyLayout, scale: f32) -> [Hurtbox; 10] {
+    let flip = if pose.facing { 1.0 } else { -1.0 };
+    let root = Joint { position, angle: 0.0 };
+    let mut torso = limb(&root, 0.0, body.body_offset, flip * pose.body, body.body_length);
+    torso.position += Vec2::new(
+        (pose.offset[0] - pose.old_offset[0]) * scale * flip,
+        (pose.offset[1] - pose.old_offset[1]) * scale,
+    );
+    // same as `rotate_neck` in player.rs
+    let neck_rad = (flip * pose.head).to_radians();
+    let neck = torso.child(
+        Vec2::new(
+            body.neck_length / 2.0 * neck_rad.sin(),
+            body.head_offset - body.neck_length / 2.0 * (1.0 - neck_rad.cos()),
+        ),
+        -neck_rad,
+    );
+    let head = neck.child(body.head_position.truncate(), 0.0);
+
+    let right_upper_arm = limb(&torso, -flip * body.body_thickness, body.upper_arm_offset, flip * pose.right_upper_arm, body.upper_arm_length);
+    let right_lower_arm = limb(&right_upper_arm, 0.0, body.lower_arm_offset, flip * pose.right_lower_arm, body.limb_length);
+    let left_upper_arm = limb(&torso, 2.0 * flip * body.body_thickness, body.upper_arm_offset, flip * pose.left_upper_arm, body.upper_arm_length);
+    let left_lower_arm = limb(&left_upper_arm, 0.0, body.lower_arm_offset, flip * pose.left_lower_arm, body.limb_length);
+    let right_upper_leg = limb(&torso, -flip * body.body_thickness, body.upper_leg_offset, flip * pose.right_upper_leg, body.upper_leg_length);
+    let right_lower_leg = limb(&right_upper_leg, 0.0, body.lower_leg_offset, flip * pose.right_lower_leg, body.limb_length);
+    let left_upper_leg = limb(&torso, flip * body.body_thickness, body.upper_leg_offset, flip * pose.left_upper_leg, body.upper_leg_length);
+    let left_lower_leg = limb(&left_upper_leg, 0.0, body.lower_leg_offset, flip * pose.left_lower_leg, body.limb_length);
+
+    [
+        // the box of the body is rounded off
+        torso.hurtbox(BodyParts::BODY, body.body_collider.y - body.body_collider.x, body.body_collider.x),
+        head.hurtbox(BodyParts::HEAD, 0.0, body.head_radius),
+        right_upper_arm.hurtbox(BodyParts::new(false, false, true, true, true), body.upper_arm_length, body.limb_radius),
+        right_lower_arm.hurtbox(BodyParts::new(false, false, true, true, false), body.limb_length, body.limb_radius),
+        left_upper_arm.hurtbox(BodyParts::new(false, false, true, false, true), body.upper_arm_length, body.limb_radius),
+        left_lower_arm.hurtbox(BodyParts::new(false, false, true, false, false), body.limb_length, body.limb_radius),
+        right_upper_leg.hurtbox(BodyParts::new(false, false, false, true, true), body.upper_leg_length, body.limb_radius),
+        right_lower_leg.hurtbox(BodyParts::new(false, false, false, true, false), body.limb_length, body.limb_radius),
+        left_upper_leg.hurtbox(BodyParts::new(false, false, false, false, true), body.upper_leg_length, body.limb_radius),
+        left_lower_leg.hurtbox(BodyParts::new(false, false, false, false, false), body.limb_length, body.limb_radius),
+    ]
+}
+
+fn point_segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let t = if segment.length_squared() == 0.0 {
+        0.0
+    } else {
+        ((point - start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+    };
+    point.distance(start + segment * t)
+}
+
+fn point_box_distance(point: Vec2, min: Vec2, max: Vec2) -> f32 {
+    point.distance(point.clamp(min, max))
+}
+
+// slab test of the segment against the box
+fn segment_crosses_box(start: Vec2, end: Vec2, min: Vec2, max: Vec2) -> bool {
+    let direction = end - start;
+    let mut t_min: f32 = 0.0;
+    let mut t_max: f32 = 1.0;
+    for axis in 0..2 {
+        if direction[axis] == 0.0 {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - start[axis]) / direction[axis];
+        let t2 = (max[axis] - start[axis]) / direction[axis];
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return false;
+        }
+    }
+    true
+}
+
+impl Hurtbox {
+    /// true if the hurtbox overlaps the box from `min` to `max`
+    pub fn overlaps(&self, min: Vec2, max: Vec2) -> bool {
+        if segment_crosses_box(self.start, self.end, min, max) {
+            return true;
+        }
+        // otherwise the closest points are an end of the segment or a corner of the box
+        let corners = [min, Vec2::new(min.x, max.y), max, Vec2::new(max.x, min.y)];
+        let distance = corners
+            .iter()
+            .map(|corner| point_segment_distance(*corner, self.start, self.end))
+            .fold(
+                point_box_distance(self.start, min, max).min(point_box_distance(self.end, min, max)),
+                f32::min,
+            );
+        distance <= self.radius
+    }
+}
+
+/// The body part hit by a hitbox, the one closest to the center of the hitbox wins
+/// when several parts overlap it.
+pub fn find_hit(hitbox: (Vec2, Vec2), hurtboxes: &[Hurtbox]) -> Option<BodyParts> {
+    let center = (hitbox.0 + hitbox.1) / 2.0;
+    hurtboxes
+        .iter()
+        .filter(|hurtbox| hurtbox.overlaps(hitbox.0, hitbox.1))
+        .map(|hurtbox| (hurtbox.part, point_segment_distance(center, hurtbox.start, hurtbox.end)))
+        .fold(None, |closest: Option<(BodyParts, f32)>, (part, distance)| match closest {
+            Some((_, closest_distance)) if closest_distance <= distance => closest,
+            _ => Some((part, distance)),
+        })
+        .map(|(part, _)| part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingame::{
+        pose::IDLE_POSE1,
+        rules::Handicap,
+        sim::{calculate_damage, PlayerState},
+    };
+    use crate::{character_def::character, layout::DESKTOP_BODY};
+
+    const KINDS: [MoveKind; 7] = [
+        MoveKind::Punch,
+        MoveKind::Kick,
+        MoveKind::JumpKick,
+        MoveKind::BackKick,
+        MoveKind::Throw,
+        MoveKind::Rush,
+        MoveKind::Quake,
+    ];
+
+    fn center(hurtbox: &Hurtbox) -> Vec2 {
+        (hurtbox.start + hurtbox.end) / 2.0
+    }
+
+    #[test]
+    fn hitbox_is_out_on_the_active_frames_only() {
+        for kind in KINDS {
+            let data = kind.frame_data();
+            let mut attack = ActiveMove::new(kind);
+            for frame in 0..=data.startup + data.active + data.recovery {
+                attack.frame = frame;
+                let active = frame > data.startup && frame <= data.startup + data.active;
+                assert_eq!(attack.is_active(), active, "{:?} on frame {}", kind, frame);
+            }
+            // a move hits only once
+            attack.frame = data.startup + 1;
+            attack.hit = true;
+            assert!(!attack.is_active());
+        }
+    }
+
+    #[test]
+    fn hitbox_turns_with_the_attacker() {
+        let attack = ActiveMove::new(MoveKind::Kick);
+        let position = Vec2::new(100.0, 50.0);
+        let (right_min, right_max) = attack.hitbox(position, true, 1.0);
+        let (left_min, left_max) = attack.hitbox(position, false, 1.0);
+        assert!(right_min.x > position.x);
+        assert!(left_max.x < position.x);
+        assert_eq!(right_min.x - position.x, position.x - left_max.x);
+        assert_eq!(right_min.y, left_min.y);
+        assert_eq!(right_max - right_min, left_max - left_min);
+        let (small_min, small_max) = attack.hitbox(position, true, 0.5);
+        assert_eq!(small_max - small_min, (right_max - right_min) * 0.5);
+    }
+
+    #[test]
+    fn hurtboxes_follow_the_body() {
+        let boxes = hurtboxes(&IDLE_POSE1, Vec2::ZERO, &DESKTOP_BODY, 1.0);
+        assert!(boxes[0].part.is_body());
+        assert!(boxes[1].part.is_head());
+        assert!(boxes[2..6].iter().all(|hurtbox| hurtbox.part.is_arm()));
+        assert!(boxes[6..].iter().all(|hurtbox| hurtbox.part.is_leg()));
+        // the head is on top and the lower legs are at the bottom
+        let body = center(&boxes[0]);
+        assert!(center(&boxes[1]).y > body.y);
+        assert!(center(&boxes[7]).y < body.y && center(&boxes[9]).y < body.y);
+        // the hurtboxes move with the fighter
+        let moved = hurtboxes(&IDLE_POSE1, Vec2::new(300.0, 20.0), &DESKTOP_BODY, 1.0);
+        for (hurtbox, moved) in boxes.iter().zip(moved) {
+            assert!((center(&moved) - center(hurtbox) - Vec2::new(300.0, 20.0)).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn hurtboxes_are_mirrored_when_facing_left() {
+        let left = Pose {
+            facing: false,
+            ..IDLE_POSE1
+        };
+        let right = hurtboxes(&IDLE_POSE1, Vec2::ZERO, &DESKTOP_BODY, 1.0);
+        let left = hurtboxes(&left, Vec2::ZERO, &DESKTOP_BODY, 1.0);
+        for (right, left) in right.iter().zip(left) {
+            let mirrored = center(right) * Vec2::new(-1.0, 1.0);
+            assert!((center(&left) - mirrored).length() < 1e-3, "{:?}", right.part.flags());
+        }
+    }
+
+    #[test]
+    fn closest_part_is_hit() {
+        let boxes = hurtboxes(&IDLE_POSE1, Vec2::ZERO, &DESKTOP_BODY, 1.0);
+        let at = |point: Vec2, half_size: f32| (point - Vec2::splat(half_size), point + Vec2::splat(half_size));
+        let head = center(&boxes[1]);
+        assert!(find_hit(at(head, 5.0), &boxes).is_some_and(|part| part.is_head()));
+        // a box over the whole fighter hits the part closest to its center
+        let body = center(&boxes[0]);
+        assert!(find_hit(at(body, 300.0), &boxes).is_some_and(|part| part.is_body()));
+        assert!(find_hit(at(Vec2::new(1000.0, 0.0), 50.0), &boxes).is_none());
+    }
+
+    #[test]
+    fn segment_through_a_box_overlaps() {
+        let hurtbox = Hurtbox {
+            part: BodyParts::BODY,
+            start: Vec2::new(-100.0, 0.0),
+            end: Vec2::new(100.0, 0.0),
+            radius: 1.0,
+        };
+        assert!(hurtbox.overlaps(Vec2::new(-10.0, -10.0), Vec2::new(10.0, 10.0)));
+        // within the radius of the segment
+        assert!(hurtbox.overlaps(Vec2::new(-10.0, 0.5), Vec2::new(10.0, 10.0)));
+        assert!(!hurtbox.overlaps(Vec2::new(-10.0, 2.0), Vec2::new(10.0, 10.0)));
+        assert!(!hurtbox.overlaps(Vec2::new(102.0, -10.0), Vec2::new(110.0, 10.0)));
+    }
+
+    #[test]
+    fn head_hits_hardest_and_legs_softest() {
+        let boxes = hurtboxes(&IDLE_POSE1, Vec2::ZERO, &DESKTOP_BODY, 1.0);
+        let damage = |index: usize| {
+            let profile = character(0);
+            calculate_damage((profile, PlayerState::PUNCHING), (profile, PlayerState::IDLE), &boxes[index].part, &Handicap::default())
+        };
+        let (body, head, arm, leg) = (damage(0), damage(1), damage(2), damage(6));
+        assert!(head > body && body > arm && arm > leg, "{} {} {} {}", head, body, arm, leg);
+        // every arm and every leg counts the same
+        assert!((2..6).all(|index| damage(index) == arm));
+        assert!((6..10).all(|index| damage(index) == leg));
+    }
+}
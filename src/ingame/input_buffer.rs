@@ -0,0 +1,475 @@
+// Input history of a fighter.
+//
+// Every logic step the buttons of the fighter are pushed into a short history, with the
+// directions turned relative to the side the fighter faces. Motions like a quarter circle,
+// a charge or a double tap are recognised from that history using the bindings of the
+// character (see `CharacterProfile::motions`). A press which cannot be used yet, because
+// the fighter is still recovering from a move, stays buffered for a few frames so that it
+// comes out on the first frame the fighter can act.
+use super::sim::FighterInput;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+// number of logic frames kept in the history
+pub const HISTORY_FRAMES: usize = 30;
+// frames a press stays buffered until it is used
+const BUFFER_FRAMES: u8 = 8;
+// a quarter circle has to be done within this many frames
+const MOTION_FRAMES: usize = 15;
+// the last direction of a motion has to be reached within this many frames before the button,
+// so a motion followed by a long walk is not a special anymore
+const MOTION_END_FRAMES: usize = 4;
+// both taps of a double tap have to be done within this many frames
+const DOUBLE_TAP_FRAMES: usize = 12;
+// frames after the back direction is let go in which forward + button still uses the charge
+const CHARGE_RELEASE_FRAMES: u16 = 10;
+
+/// Direction held by a fighter, relative to the side it faces.
+/// The jump button is pressed, not held, so there is no up direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Neutral,
+    Forward,
+    Back,
+    Down,
+    DownForward,
+    DownBack,
+}
+
+impl Direction {
+    /// The direction held in `input` by a fighter facing right when `facing` is true
+    pub fn of(input: &FighterInput, facing: bool) -> Self {
+        let (forward, back) = if facing { (input.right, input.left) } else { (input.left, input.right) };
+        match (input.down, forward && !back, back && !forward) {
+            (false, false, false) => Direction::Neutral,
+            (false, true, _) => Direction::Forward,
+            (false, _, true) => Direction::Back,
+            (true, false, false) => Direction::Down,
+            (true, true, _) => Direction::DownForward,
+            (true, _, true) => Direction::DownBack,
+        }
+    }
+    fn is_forward(&self) -> bool {
+        matches!(self, Direction::Forward | Direction::DownForward)
+    }
+    fn is_back(&self) -> bool {
+        matches!(self, Direction::Back | Direction::DownBack)
+    }
+}
+
+/// A pressed button, or a move recognised from a motion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Button {
+    Jump,
+    Kick,
+    Punch,
+    Fire,
+    BackKick,
+    Skill,
+    Special,
+    Throw,
+    // only recognised from a double tap
+    DashForward,
+    DashBack,
+}
+
+const BUTTON_COUNT: usize = 10;
+
+impl Button {
+    pub const ALL: [Button; BUTTON_COUNT] = [
+        Button::Jump,
+        Button::Kick,
+        Button::Punch,
+        Button::Fire,
+        Button::BackKick,
+        Button::Skill,
+        Button::Special,
+        Button::Throw,
+        Button::DashForward,
+        Button::DashBack,
+    ];
+
+    /// true if the button is pressed in `input`
+    pub fn pressed(&self, input: &FighterInput) -> bool {
+        match self {
+            Button::Jump => input.jump,
+            Button::Kick => input.kick,
+            Button::Punch => input.punch,
+            Button::Fire => input.fire,
+            Button::BackKick => input.back_kick,
+            Button::Skill => input.skill,
+            Button::Special => input.special,
+            Button::Throw => input.throw,
+            Button::DashForward => input.dash_forward,
+            Button::DashBack => input.dash_back,
+        }
+    }
+    fn press(&self, input: &mut FighterInput) {
+        match self {
+            Button::Jump => input.jump = true,
+            Button::Kick => input.kick = true,
+            Button::Punch => input.punch = true,
+            Button::Fire => input.fire = true,
+            Button::BackKick => input.back_kick = true,
+            Button::Skill => input.skill = true,
+            Button::Special => input.special = true,
+            Button::Throw => input.throw = true,
+            Button::DashForward => input.dash_forward = true,
+            Button::DashBack => input.dash_back = true,
+        }
+    }
+}
+
+/// Motions which can be bound to a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Motion {
+    // down, down-forward, forward
+    QuarterCircleForward,
+    // down, down-back, back
+    QuarterCircleBack,
+    // back held for the given number of frames, then forward
+    Charge(u16),
+    // forward, neutral, forward
+    DoubleTapForward,
+    // back, neutral, back
+    DoubleTapBack,
+}
+
+/// A motion of a character and the move it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct MotionBinding {
+    pub motion: Motion,
+    // button pressed at the end of the motion, None for a motion of the directions only
+    #[serde(default)]
+    pub button: Option<Button>,
+    // the move done instead of the button
+    #[serde(rename = "move")]
+    pub special: Button,
+}
+
+/// The recent input of a fighter
+#[derive(Debug, Clone, Default)]
+pub struct InputBuffer {
+    // directions of the last `HISTORY_FRAMES` frames, the newest last
+    history: VecDeque<Direction>,
+    // frames each button stays buffered, indexed by `Button`
+    pending: [u8; BUTTON_COUNT],
+    // frames the back direction has been held so far
+    charge: u16,
+    // the charge when the back direction was let go and the frames since then
+    released_charge: u16,
+    since_release: u16,
+}
+
+impl InputBuffer {
+    /// Pushes the buttons of a logic step and returns the input the fighter acts on:
+    /// the held directions as they are, and every button pressed or move recognised
+    /// in the last few frames which has not been used yet.
+    /// The button at the end of a recognised motion is replaced by the move only if the
+    /// fighter can afford the move, otherwise the button is done as usual.
+    ///
+    /// # Arguments
+    /// * `input` - Buttons of this frame
+    /// * `facing` - true when the fighter faces right
+    /// * `motions` - Motions of the character
+    /// * `affordable` - true for a move the fighter has the meter or the charge for
+    pub fn push(
+        &mut self,
+        input: &FighterInput,
+        facing: bool,
+        motions: &[MotionBinding],
+        affordable: impl Fn(Button) -> bool,
+    ) -> FighterInput {
+        let direction = Direction::of(input, facing);
+        if direction.is_back() {
+            self.charge = self.charge.saturating_add(1);
+        } else if self.charge > 0 {
+            self.released_charge = self.charge;
+            self.since_release = 0;
+            self.charge = 0;
+        } else {
+            self.since_release = self.since_release.saturating_add(1);
+        }
+        self.history.push_back(direction);
+        if self.history.len() > HISTORY_FRAMES {
+            self.history.pop_front();
+        }
+
+        for button in Button::ALL {
+            if button.pressed(input) {
+                self.pending[button as usize] = BUFFER_FRAMES;
+            }
+        }
+        for binding in motions {
+            if binding.button.is_none_or(|button| button.pressed(input))
+                && affordable(binding.special)
+                && self.recognise(binding.motion)
+            {
+                if let Some(button) = binding.button {
+                    self.pending[button as usize] = 0;
+                }
+                self.pending[binding.special as usize] = BUFFER_FRAMES;
+            }
+        }
+
+        let mut buffered = FighterInput {
+            left: input.left,
+            right: input.right,
+            down: input.down,
+            guard: input.guard,
+            ex: input.ex,
+            ..Default::default()
+        };
+        for button in Button::ALL {
+            if self.pending[button as usize] > 0 {
+                button.press(&mut buffered);
+                self.pending[button as usize] -= 1;
+            }
+        }
+        buffered
+    }
+
+    /// Drops the buffered press of `button` once the fighter used it
+    pub fn consume(&mut self, button: Button) {
+        self.pending[button as usize] = 0;
+    }
+
+    // true if the motion ends on the newest frame of the history
+    fn recognise(&mut self, motion: Motion) -> bool {
+        match motion {
+            Motion::QuarterCircleForward => {
+                self.sequence(&[Direction::Down, Direction::DownForward, Direction::Forward], MOTION_FRAMES)
+            }
+            Motion::QuarterCircleBack => {
+                self.sequence(&[Direction::Down, Direction::DownBack, Direction::Back], MOTION_FRAMES)
+            }
+            Motion::Charge(frames) => {
+                let charged = self.history.back().is_some_and(|direction| direction.is_forward())
+                    && self.released_charge >= frames
+                    && self.since_release <= CHARGE_RELEASE_FRAMES;
+                if charged {
+                    // a charge is used only once
+                    self.released_charge = 0;
+                }
+                charged
+            }
+            Motion::DoubleTapForward => self.double_tap(Direction::Forward),
+            Motion::DoubleTapBack => self.double_tap(Direction::Back),
+        }
+    }
+
+    // true if `steps` were held in this order in the last `frames` frames and the last step
+    // was reached in the last `MOTION_END_FRAMES` frames, other directions in between are ignored
+    fn sequence(&self, steps: &[Direction], frames: usize) -> bool {
+        // direction `age` frames before the newest one
+        let held = |age: usize| self.history.len().checked_sub(age + 1).map(|index| self.history[index]);
+        let Some((last, steps)) = steps.split_last() else {
+            return false;
+        };
+        let Some(reached) =
+            (0..MOTION_END_FRAMES).find(|age| held(*age) == Some(*last) && held(age + 1) != Some(*last))
+        else {
+            return false;
+        };
+        let mut remaining = steps.len();
+        if remaining == 0 {
+            return true;
+        }
+        for age in reached + 1..frames {
+            if held(age) == Some(steps[remaining - 1]) {
+                remaining -= 1;
+                if remaining == 0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // true on the frame of the second tap only
+    fn double_tap(&self, direction: Direction) -> bool {
+        let mut newest = self.history.iter().rev();
+        newest.next() == Some(&direction)
+            && newest.next() != Some(&direction)
+            && self.sequence(&[direction, Direction::Neutral, direction], DOUBLE_TAP_FRAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRE: MotionBinding = MotionBinding {
+        motion: Motion::QuarterCircleForward,
+        button: Some(Button::Punch),
+        special: Button::Fire,
+    };
+    const CHARGE: MotionBinding = MotionBinding {
+        motion: Motion::Charge(40),
+        button: Some(Button::Punch),
+        special: Button::Fire,
+    };
+    const DASH: MotionBinding = MotionBinding {
+        motion: Motion::DoubleTapForward,
+        button: None,
+        special: Button::DashForward,
+    };
+
+    fn held(direction: Direction) -> FighterInput {
+        // a fighter facing right
+        let (down, right, left) = match direction {
+            Direction::Neutral => (false, false, false),
+            Direction::Forward => (false, true, false),
+            Direction::Back => (false, false, true),
+            Direction::Down => (true, false, false),
+            Direction::DownForward => (true, true, false),
+            Direction::DownBack => (true, false, true),
+        };
+        FighterInput {
+            down,
+            right,
+            left,
+            ..Default::default()
+        }
+    }
+
+    fn punch(direction: Direction) -> FighterInput {
+        FighterInput {
+            punch: true,
+            ..held(direction)
+        }
+    }
+
+    // pushes `frames` frames holding each direction, the result of the last frame
+    fn hold(buffer: &mut InputBuffer, steps: &[(Direction, usize)], motions: &[MotionBinding]) -> FighterInput {
+        let mut result = FighterInput::default();
+        for (direction, frames) in steps {
+            for _ in 0..*frames {
+                result = buffer.push(&held(*direction), true, motions, |_| true);
+            }
+        }
+        result
+    }
+
+    const QCF: [(Direction, usize); 3] = [(Direction::Down, 2), (Direction::DownForward, 2), (Direction::Forward, 1)];
+
+    #[test]
+    fn quarter_circle_replaces_the_button() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &QCF, &[FIRE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[FIRE], |_| true);
+        assert!(input.fire);
+        assert!(!input.punch);
+    }
+
+    #[test]
+    fn quarter_circle_depends_on_the_side() {
+        let mut buffer = InputBuffer::default();
+        // the buttons of a quarter circle forward facing right are a quarter circle back facing left
+        for (direction, frames) in QCF {
+            for _ in 0..frames {
+                buffer.push(&held(direction), false, &[FIRE], |_| true);
+            }
+        }
+        let input = buffer.push(&punch(Direction::Forward), false, &[FIRE], |_| true);
+        assert!(!input.fire);
+        assert!(input.punch);
+    }
+
+    #[test]
+    fn unaffordable_motion_keeps_the_button() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &QCF, &[FIRE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[FIRE], |button| button != Button::Fire);
+        assert!(!input.fire);
+        assert!(input.punch);
+    }
+
+    #[test]
+    fn late_button_is_still_a_motion() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &QCF, &[FIRE]);
+        hold(&mut buffer, &[(Direction::Forward, MOTION_END_FRAMES - 2)], &[FIRE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[FIRE], |_| true);
+        assert!(input.fire);
+    }
+
+    #[test]
+    fn walking_after_the_motion_is_not_a_motion() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &QCF, &[FIRE]);
+        hold(&mut buffer, &[(Direction::Forward, 6)], &[FIRE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[FIRE], |_| true);
+        assert!(!input.fire);
+        assert!(input.punch);
+    }
+
+    #[test]
+    fn slow_motion_is_not_a_motion() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &[(Direction::Down, 1), (Direction::DownForward, MOTION_FRAMES), (Direction::Forward, 1)], &[FIRE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[FIRE], |_| true);
+        assert!(!input.fire);
+    }
+
+    #[test]
+    fn charge_needs_the_full_time() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &[(Direction::Back, 39), (Direction::Forward, 1)], &[CHARGE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[CHARGE], |_| true);
+        assert!(!input.fire);
+
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &[(Direction::Back, 40), (Direction::Forward, 1)], &[CHARGE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[CHARGE], |_| true);
+        assert!(input.fire);
+        assert!(!input.punch);
+    }
+
+    #[test]
+    fn charge_is_lost_after_the_release_window() {
+        let mut buffer = InputBuffer::default();
+        hold(&mut buffer, &[(Direction::Back, 40), (Direction::Forward, CHARGE_RELEASE_FRAMES as usize + 2)], &[CHARGE]);
+        let input = buffer.push(&punch(Direction::Forward), true, &[CHARGE], |_| true);
+        assert!(!input.fire);
+    }
+
+    #[test]
+    fn double_tap_dashes_once() {
+        let mut buffer = InputBuffer::default();
+        let input = hold(&mut buffer, &[(Direction::Forward, 2), (Direction::Neutral, 2), (Direction::Forward, 1)], &[DASH]);
+        assert!(input.dash_forward);
+        buffer.consume(Button::DashForward);
+        // holding forward does not dash again
+        let input = hold(&mut buffer, &[(Direction::Forward, 1)], &[DASH]);
+        assert!(!input.dash_forward);
+    }
+
+    #[test]
+    fn slow_double_tap_is_a_walk() {
+        let mut buffer = InputBuffer::default();
+        let input = hold(&mut buffer, &[(Direction::Forward, 2), (Direction::Neutral, DOUBLE_TAP_FRAMES), (Direction::Forward, 1)], &[DASH]);
+        assert!(!input.dash_forward);
+    }
+
+    #[test]
+    fn press_stays_buffered() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(&punch(Direction::Neutral), true, &[], |_| true);
+        for _ in 1..BUFFER_FRAMES {
+            assert!(buffer.push(&held(Direction::Neutral), true, &[], |_| true).punch);
+        }
+        assert!(!buffer.push(&held(Direction::Neutral), true, &[], |_| true).punch);
+    }
+
+    #[test]
+    fn used_press_is_dropped() {
+        let mut buffer = InputBuffer::default();
+        assert!(buffer.push(&punch(Direction::Neutral), true, &[], |_| true).punch);
+        buffer.consume(Button::Punch);
+        assert!(!buffer.push(&held(Direction::Neutral), true, &[], |_| true).punch);
+    }
+}
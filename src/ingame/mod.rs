@@ -3,25 +3,33 @@ use bevy::{
     prelude::*,
     render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
 };
-use bevy_rapier2d::prelude::*;
-use crate::{CharacterTextures, BGM, GameMode, ingame::player::PlayerCollision};
-#[cfg(not(target_arch = "wasm32"))]
-use crate::ingame::pose::{FRAMES_VICTORY, FRAMES_DEFEATED};
+use crate::{BGM, GameMode};
 
 pub mod agent;
 #[cfg(not(target_arch = "wasm32"))]
 mod controller;
+mod fighter_state;
+pub mod frame_data;
+pub mod input_buffer;
 #[cfg(feature="pause")]
 mod pause;
 mod player;
 mod pose;
-#[cfg(feature="phone")]
+#[cfg(not(target_arch = "wasm32"))]
+mod pose_loader;
+pub mod projectile;
+pub mod round;
+pub mod rules;
+pub mod sim;
+pub mod stage;
+pub mod survival;
 mod phone;
+mod throw;
 
 #[cfg(feature="pause")]
 use crate::PATH_BOLD_FONT;
 use crate::{
-    AppState, GameConfig, SoundEffect, PATH_BOLD_MONOSPACE_FONT,
+    character_def::{character, characters, ENERGY_STOCKS}, layout::Layout, CharacterTextures, AppState, GameConfig, SoundEffect, PATH_BOLD_MONOSPACE_FONT,
     PATH_EXTRA_BOLD_FONT, PATH_IMAGE_PREFIX, PATH_SOUND_PREFIX, TITLE_FONT_SIZE, DEFAULT_FONT_SIZE
 };
 
@@ -31,7 +39,11 @@ use controller::*;
 #[cfg(feature="pause")]
 use pause::*;
 use player::*;
-#[cfg(feature="phone")]
+use pose::PoseSet;
+use round::*;
+use rules::{team_positions, MatchRules, ROUND_VOICES};
+use sim::Match;
+use stage::stage;
 use phone::*;
 
 const FPS: f32 = 60.0;
@@ -39,41 +51,75 @@ const FPS: f32 = 60.0;
 const BARS_OFFSET_Y: f32 = 60.0;
 #[cfg(target_arch = "wasm32")]
 const BARS_OFFSET_Y: f32 = 30.0;
+// horizontal slant of the end of the HUD bars (desktop layout)
+pub const BAR_SLANT: f32 = 50.0;
 
-#[derive(Resource, Default)]
-pub struct GameState {
-    pub winners: [u8; 3],
-    pub win_types: [bool; 3],
-    pub round: u8,
-    pub phase: u8,
-    pub count: u8,
-    pub timer: Timer,
+// text of the status bar at the beginning of a round, a survival counts its fights
+fn round_title(game: &Match, mode: GameMode) -> String {
+    if game.is_sudden_death() {
+        "SUDDEN DEATH".to_string()
+    } else if mode == GameMode::Survival {
+        format!("BATTLE {}", game.round)
+    } else {
+        format!("ROUND {}", game.round)
+    }
 }
 
-impl GameState {
-    // return the total winner
-    pub fn get_winner(&self) -> u8 {
-        let mut player1 = 0;
-        let mut player2 = 0;
-        for winner in self.winners.iter() {
-            if *winner == 1 {
-                player1 += 1;
-            } else if *winner == 2 {
-                player2 += 1;
-            }
-        }
-        if player1 > player2 {
-            return 1;
-        } else if player1 < player2 {
-            return 2;
-        } else {
-            return 0;
-        }
+// seconds on the timer for the frames left in the round, infinite without time limit
+fn timer_seconds(time_left: Option<u32>) -> f32 {
+    time_left.map_or(f32::INFINITY, |frames| frames as f32 / FPS)
+}
+
+// names of the fighters a team has left from `position` on, the one fighting first
+fn team_text(team: &[isize], position: usize) -> String {
+    team.iter()
+        .skip(position)
+        .map(|character_id| character(*character_id).name.clone())
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+// text under the bars of side `id` in a survival
+fn survival_text(id: u8, winners: &[u8], level: Level) -> String {
+    if id == 0 {
+        format!("DEFEATED {}", survival::defeated(winners))
+    } else {
+        format!("LEVEL {:?}", level)
+    }
+}
+
+fn timer_text(seconds: f32) -> String {
+    if seconds.is_infinite() {
+        "--.--".to_string()
+    } else {
+        format!("{:.2}", seconds)
     }
 }
 
+/// The `Match` being fought, the entities of the fight only show it
+#[derive(Resource, Deref, DerefMut)]
+pub struct Game(pub Match);
+
+// the fight is going on, the `Match` is stepped while it exists
 #[derive(Resource)]
-pub struct Fighting(u8);
+pub struct Fighting;
+
+/// Steps of one logic frame on `FixedUpdate`, chained in this order
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LogicStep {
+    /// the bot decides its action, the devices apply their buttons on `Update`
+    Input,
+    /// `Match` is stepped
+    Step,
+    /// the entities follow the `Match`
+    Sync,
+    /// the hits and the other events of the step are shown
+    Show,
+    Timer,
+    RoundFlow,
+    /// what happens on the change of the round phase
+    Hooks,
+}
 
 #[derive(Component)]
 struct InGame;
@@ -91,7 +137,12 @@ struct StatusBar;
 struct Curtain;
 
 #[derive(Component)]
-struct GameTimer(f32);
+struct GameTimer;
+
+// text under the bars of a side: the fighters it has left in a team battle,
+// or the opponents defeated and the level of the bot in a survival
+#[derive(Component)]
+struct SideDisplay;
 
 #[derive(Component)]
 pub struct SkillName(u8);
@@ -107,6 +158,31 @@ struct DamageDisplay {
     pub alpha: f32,
 }
 
+// combo counter under the damage display, `hits` is the combo shown
+#[derive(Component)]
+struct ComboDisplay {
+    pub hits: u16,
+    pub alpha: f32,
+}
+
+/// Position of an entity moved by the logic on the fixed schedule.
+/// The logic always works on `current`, while the displayed `Transform`
+/// is interpolated between the last two logic steps.
+#[derive(Component)]
+struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl Interpolated {
+    fn new(translation: Vec3) -> Self {
+        Self {
+            previous: translation,
+            current: translation,
+        }
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn rand() -> f32 {
     rand::random::<f32>()
@@ -116,18 +192,61 @@ pub fn rand() -> f32 {
     web_sys::js_sys::Math::random() as f32
 }
 
+/// Mesh of a bar of the HUD.
+/// The bar grows from x = 0 toward `length` and its end is slanted by `slant`.
+///
+/// # Arguments
+/// * `ratio` - how much of the bar is filled
+/// * `colors` - colors of the bottom left, top left, bottom right and top right corners
+fn bar_mesh(length: f32, ratio: f32, height: f32, slant: f32, colors: [[f32; 4]; 4]) -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 1.0],
+                [0.0, height, 1.0],
+                [length * ratio + slant, 0.0, 1.0],
+                [length * ratio, height, 1.0],
+            ],
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors.to_vec())
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3]))
+}
+
+/// Builds the mesh of a thin line across a bar, slanted like the end of the bar
+fn divider_mesh(width: f32, height: f32, slant: f32, color: [f32; 4]) -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [slant, 0.0, 1.0],
+                [0.0, height, 1.0],
+                [width + slant, 0.0, 1.0],
+                [width, height, 1.0],
+            ],
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![color; 4])
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3]))
+}
+
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut game_state: ResMut<GameState>,
+    mut round_flow: ResMut<RoundFlow>,
     config: Res<GameConfig>,
+    layout: Res<Layout>,
+    rules: Res<MatchRules>,
+    character_textures: Res<CharacterTextures>,
+    poses: Res<PoseSet>,
     audio_query: Query<Entity, With<BGM>>,
 ) {
     info!("setup");
 
+    // the bot starts at the level of the settings
+    commands.insert_resource(Agent::new(config.level));
+
     for entity in audio_query.iter() {
         commands.entity(entity).despawn();
     }
@@ -146,10 +265,7 @@ fn setup(
             Node {
                 justify_self: JustifySelf::Start,
                 align_self: AlignSelf::Start,
-                #[cfg(not(feature="phone"))]
                 border: UiRect::all(Val::Px(5.0)),
-                #[cfg(feature="phone")]
-                border: UiRect::all(Val::Px(2.0)),
                 ..default()
             },
             BorderRadius::MAX,
@@ -202,7 +318,7 @@ fn setup(
                 ))
                 .with_children(|spawner| {
                     spawner.spawn((
-                        if config.mode == GameMode::SinglePlayer {
+                        if config.mode.has_bot() {
                             Text::new("You")
                         } else {
                             Text::new("Player 1")
@@ -226,7 +342,7 @@ fn setup(
                 ))
                 .with_children(|spawner| {
                     spawner.spawn((
-                        if config.mode == GameMode::SinglePlayer {
+                        if config.mode.has_bot() {
                             Text::new("Bot")
                         } else {
                             Text::new("Player 2")
@@ -243,14 +359,8 @@ fn setup(
             });
             spawner.spawn((
                 Node {
-                    #[cfg(not(feature="phone"))]
                     width: Val::Px(300.0),
-                    #[cfg(feature="phone")]
-                    width: Val::Px(150.0),
-                    #[cfg(not(feature="phone"))]
                     height: Val::Px(100.0),
-                    #[cfg(feature="phone")]
-                    height: Val::Px(50.0),
                     justify_self: JustifySelf::Center,
                     align_self: AlignSelf::Center,
                     justify_content: JustifyContent::Center,
@@ -262,8 +372,8 @@ fn setup(
             ))
             .with_children(|spawner| {
                 spawner.spawn((
-                    GameTimer(60.0),
-                    Text::new("60.00"),
+                    GameTimer,
+                    Text::new(timer_text(timer_seconds(rules.round_frames(false)))),
                     TextFont {
                         font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
                         font_size: DEFAULT_FONT_SIZE,
@@ -309,6 +419,8 @@ fn setup(
                     justify_self: JustifySelf::Start,
                     align_self: AlignSelf::End,
                     justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
                     width: Val::Percent(20.0),
                     height: Val::Px(100.0),
                     margin: UiRect::horizontal(Val::Px(100.0)),
@@ -331,12 +443,29 @@ fn setup(
                         },
                         PlayerID(0),
                     ));
+                    spawner.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
+                            font_size: DEFAULT_FONT_SIZE,
+                            ..default()
+                        },
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextColor(Color::srgba(5.0, 4.0, 0.0, 0.0)),
+                        ComboDisplay {
+                            hits: 0,
+                            alpha: 0.0,
+                        },
+                        PlayerID(0),
+                    ));
                 });
             spawner.spawn((
                 Node {
                     justify_self: JustifySelf::End,
                     align_self: AlignSelf::End,
                     justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
                     width: Val::Percent(20.0),
                     height: Val::Px(100.0),
                     margin: UiRect::horizontal(Val::Px(100.0)),
@@ -359,46 +488,208 @@ fn setup(
                         },
                         PlayerID(1),
                     ));
+                    spawner.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
+                            font_size: DEFAULT_FONT_SIZE,
+                            ..default()
+                        },
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextColor(Color::srgba(5.0, 4.0, 0.0, 0.0)),
+                        ComboDisplay {
+                            hits: 0,
+                            alpha: 0.0,
+                        },
+                        PlayerID(1),
+                    ));
                 });
         });
 
-    // health bar for player 1
+    // bars of the HUD, the ones of player 1 grow to the left and the ones of player 2 to the right
+    let bar_height = 20.0;
+    let row_height = bar_height + 10.0 * layout.scale;
+    let glow = 10.0 * layout.glow;
+    for id in 0..2 {
+        let sign = if id == 0 { -1.0 } else { 1.0 };
+        // the bars end `margin` away from the edge of the screen
+        let length = |margin: f32| sign * (config.window_size.x / 2.0 - margin * layout.scale);
+        let slant = -sign * BAR_SLANT * layout.scale;
+        let row = |row: f32, z: f32| {
+            Transform::from_translation(Vec3::new(
+                sign * 150.0 * layout.scale,
+                config.window_size.y / 2.0 - row_height * row - BARS_OFFSET_Y,
+                z,
+            ))
+        };
+        // health bar
+        commands.spawn((
+            InGame,
+            PlayerID(id),
+            HealthBar(1.0, length(250.0)),
+            Mesh2d(meshes.add(bar_mesh(
+                length(250.0),
+                1.0,
+                bar_height,
+                slant,
+                [
+                    [0.0, glow, 0.0, 1.0],
+                    [0.0, glow, 0.0, 1.0],
+                    [0.0, glow, 0.0, 0.5],
+                    [0.0, glow, 0.0, 0.5],
+                ],
+            ))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(1.0, 1.0),
+        ));
+        // marker of a handicapped player, between the end of the health bar and the edge of the screen
+        if !config.handicaps[id as usize].is_none() {
+            let mut transform = row(1.0, 2.0);
+            transform.translation.x = sign * (config.window_size.x / 2.0 - 130.0 * layout.scale);
+            transform.translation.y += bar_height / 2.0;
+            commands.spawn((
+                InGame,
+                Text2d::new("HANDICAP"),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
+                    font_size: DEFAULT_FONT_SIZE * 0.5 * layout.scale,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextColor(Color::srgba(5.0, 4.0, 0.0, 1.0)),
+                transform,
+            ));
+        }
+        // lineup of a team battle under the bars
+        if config.is_team_battle(&rules) {
+            let mut transform = row(6.0, 2.0);
+            transform.translation.x = sign * config.window_size.x / 4.0;
+            transform.translation.y += bar_height / 2.0;
+            commands.spawn((
+                InGame,
+                PlayerID(id),
+                SideDisplay,
+                Text2d::new(team_text(&config.teams[id as usize], 0)),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
+                    font_size: DEFAULT_FONT_SIZE * 0.5 * layout.scale,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextColor(Color::WHITE),
+                transform,
+            ));
+        }
+        // progress of a survival at the same place
+        if config.mode == GameMode::Survival {
+            let mut transform = row(6.0, 2.0);
+            transform.translation.x = sign * config.window_size.x / 4.0;
+            transform.translation.y += bar_height / 2.0;
+            commands.spawn((
+                InGame,
+                PlayerID(id),
+                SideDisplay,
+                Text2d::new(survival_text(id, &[], config.level)),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_MONOSPACE_FONT),
+                    font_size: DEFAULT_FONT_SIZE * 0.5 * layout.scale,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextColor(Color::WHITE),
+                transform,
+            ));
+        }
+        // energy bar
+        commands.spawn((
+            InGame,
+            Mesh2d(meshes.add(bar_mesh(length(350.0), 1.0, bar_height, slant, [[0.0, 0.0, glow, 0.5]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(2.0, 1.0),
+        ));
+        commands.spawn((
+            InGame,
+            PlayerID(id),
+            EnergyBar(0.0, length(350.0)),
+            Mesh2d(meshes.add(bar_mesh(length(350.0), 0.0, bar_height, slant, [[0.0, 0.0, glow, 1.0]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(2.0, 2.0),
+        ));
+        // lines between the stocks of the energy bar
+        for stock in 1..ENERGY_STOCKS {
+            let mut transform = row(2.0, 3.0);
+            transform.translation.x += length(350.0) * stock as f32 / ENERGY_STOCKS as f32;
+            commands.spawn((
+                InGame,
+                Mesh2d(meshes.add(divider_mesh(sign * 3.0 * layout.scale, bar_height, slant, [1.0, 1.0, 1.0, 0.8]))),
+                MeshMaterial2d(materials.add(ColorMaterial::default())),
+                transform,
+            ));
+        }
+        // fire charge bar
+        commands.spawn((
+            InGame,
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 1.0, bar_height, slant, [[0.0, 0.0, 1.0, 0.5]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(3.0, 1.0),
+        ));
+        commands.spawn((
+            InGame,
+            PlayerID(id),
+            FireBar(1.0, length(450.0)),
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 1.0, bar_height, slant, [[2.0 * glow, 0.0, 0.0, 1.0]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(3.0, 2.0),
+        ));
+        // guard bar
+        commands.spawn((
+            InGame,
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 1.0, bar_height, slant, [[1.0, 1.0, 1.0, 0.3]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(4.0, 1.0),
+        ));
+        commands.spawn((
+            InGame,
+            PlayerID(id),
+            GuardBar(1.0, length(450.0)),
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 1.0, bar_height, slant, [[glow, glow, 0.0, 1.0]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(4.0, 2.0),
+        ));
+        // dizzy bar, it fills up with the hits taken
+        commands.spawn((
+            InGame,
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 1.0, bar_height, slant, [[1.0, 1.0, 1.0, 0.3]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(5.0, 1.0),
+        ));
+        commands.spawn((
+            InGame,
+            PlayerID(id),
+            DizzyBar(0.0, length(450.0)),
+            Mesh2d(meshes.add(bar_mesh(length(450.0), 0.0, bar_height, slant, [[glow, 0.0, glow, 1.0]; 4]))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            row(5.0, 2.0),
+        ));
+    }
+
+    // skill name display
+    for (character_id, profile) in characters().iter().enumerate() {
+        commands.spawn((
+            InGame,
+            Visibility::Hidden,
+            Node {
+                justify_self: JustifySelf::Center,
+                align_self: AlignSelf::Center,
+                ..default()
+            },
+            SkillName(character_id as u8),
+            ImageNode::new(asset_server.load(format!("{}{}", layout.image_prefix, profile.skill_name_image))),
+        ));
+    }
+
+    // curtain for skill
     commands.spawn((
-        InGame,
-        PlayerID(0),
-        #[cfg(not(feature="phone"))]
-        HealthBar(1.0, 250.0 - config.window_size.x / 2.0),
-        #[cfg(feature="phone")]
-        HealthBar(1.0, 125.0 - config.window_size.x / 2.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [300.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [250.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 10.0, 0.0, 1.0],
-                        [0.0, 10.0, 0.0, 1.0],
-                        [0.0, 10.0, 0.0, 0.5],
-                        [0.0, 10.0, 0.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
         Mesh2d(
             meshes.add(
                 Mesh::new(
@@ -408,1243 +699,493 @@ fn setup(
                 .with_inserted_attribute(
                     Mesh::ATTRIBUTE_POSITION,
                     vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [150.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [125.0 - config.window_size.x / 2.0, 20.0, 1.0],
+                        [
+                            -config.window_size.x / 2.0,
+                            config.window_size.y / 2.0,
+                            10.0,
+                        ],
+                        [config.window_size.x / 2.0, config.window_size.y / 2.0, 10.0],
+                        [
+                            -config.window_size.x / 2.0,
+                            -config.window_size.y / 2.0,
+                            10.0,
+                        ],
+                        [
+                            config.window_size.x / 2.0,
+                            -config.window_size.y / 2.0,
+                            10.0,
+                        ],
                     ],
                 )
                 .with_inserted_attribute(
                     Mesh::ATTRIBUTE_COLOR,
                     vec![
-                        [0.0, 1.0, 0.0, 1.0],
-                        [0.0, 1.0, 0.0, 1.0],
-                        [0.0, 1.0, 0.0, 0.5],
-                        [0.0, 1.0, 0.0, 0.5],
+                        [0.0, 0.0, 0.0, 0.0],
+                        [0.0, 0.0, 0.0, 0.0],
+                        [0.0, 0.0, 0.0, 0.0],
+                        [0.0, 0.0, 0.0, 0.0],
                     ],
                 )
                 .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
             ),
         ),
         MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(-150.0, config.window_size.y / 2.0 - 30.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(-75.0, config.window_size.y / 2.0 - 25.0 - BARS_OFFSET_Y, 1.0)),
+        SkillEntity { id: 1 },
+        Transform::from_translation(Vec3::new(0.0, 0.0, 19.0)),
     ));
-    // health bar for player 2
+
+    // thunder for skill of character 0
     commands.spawn((
         InGame,
-        PlayerID(1),
-        #[cfg(not(feature="phone"))]
-        HealthBar(1.0, config.window_size.x / 2.0 - 250.0),
-        #[cfg(feature="phone")]
-        HealthBar(1.0, config.window_size.x / 2.0 - 125.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 300.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 250.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 10.0, 0.0, 1.0],
-                        [0.0, 10.0, 0.0, 1.0],
-                        [0.0, 10.0, 0.0, 0.5],
-                        [0.0, 10.0, 0.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 150.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 125.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 1.0, 0.0, 1.0],
-                        [0.0, 1.0, 0.0, 1.0],
-                        [0.0, 1.0, 0.0, 0.5],
-                        [0.0, 1.0, 0.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(150.0, config.window_size.y / 2.0 - 30.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(75.0, config.window_size.y / 2.0 - 25.0 - BARS_OFFSET_Y, 1.0)),
+        SkillEntity { id: 0 },
+        Visibility::Hidden,
+        Sprite {
+            image: asset_server.load(format!("{}thunder.png", layout.image_prefix)),
+            custom_size: Some(Vec2::new(250.0, 1000.0)),
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(0.0, 100.0, 20.0)),
     ));
 
-    // energy bar for player 1
+    // sky background
     commands.spawn((
+        Sprite {
+            image: asset_server.load(format!("{}{}", layout.image_prefix, layout.sky_image)),
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(0.0, 100.0 * layout.scale, -2.0)),
+        SkyBackground,
         InGame,
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [400.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [350.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 10.0, 0.5],
-                        [0.0, 0.0, 10.0, 0.5],
-                        [0.0, 0.0, 10.0, 0.5],
-                        [0.0, 0.0, 10.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [200.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [175.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(-150.0, config.window_size.y / 2.0 - 60.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(-75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 1.0)),
     ));
     commands.spawn((
-        InGame,
-        PlayerID(0),
-        #[cfg(not(feature="phone"))]
-        EnergyBar(0.0, 350.0 - config.window_size.x / 2.0),
-        #[cfg(feature="phone")]
-        EnergyBar(0.0, 175.0 - config.window_size.x / 2.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [10.0, 0.0, 1.0],
-                        [10.0, 20.0, 1.0],
-                        [50.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [-25.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(-150.0, config.window_size.y / 2.0 - 60.0 - BARS_OFFSET_Y, 2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(-75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 2.0)),
-    ));
-    // energy bar for player 2
-    commands.spawn((
-        InGame,
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 400.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 350.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 200.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 175.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(150.0, config.window_size.y / 2.0 - 60.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 1.0)),
-    ));
-    commands.spawn((
-        InGame,
-        PlayerID(1),
-        #[cfg(not(feature="phone"))]
-        EnergyBar(0.0, config.window_size.x / 2.0 - 350.0),
-        #[cfg(feature="phone")]
-        EnergyBar(0.0, config.window_size.x / 2.0 - 175.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [-10.0, 0.0, 1.0],
-                        [-10.0, 20.0, 1.0],
-                        [-50.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                        [0.0, 0.0, 10.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [-25.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                        [0.0, 0.0, 1.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(150.0, config.window_size.y / 2.0 - 60.0 - BARS_OFFSET_Y, 2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 2.0)),
-    ));
-
-    // fire charge bar for player 1
-    commands.spawn((
-        InGame,
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [500.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [450.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [200.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [175.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(-150.0, config.window_size.y / 2.0 - 90.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(-75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 1.0)),
-    ));
-    commands.spawn((
-        InGame,
-        PlayerID(0),
-        #[cfg(not(feature="phone"))]
-        FireBar(1.0, 450.0 - config.window_size.x / 2.0),
-        #[cfg(feature="phone")]
-        FireBar(1.0, 175.0 - config.window_size.x / 2.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [10.0, 0.0, 1.0],
-                        [10.0, 20.0, 1.0],
-                        [500.0 - config.window_size.x / 2.0, 0.0, 1.0],
-                        [450.0 - config.window_size.x / 2.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [-25.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(-150.0, config.window_size.y / 2.0 - 90.0 - BARS_OFFSET_Y, 2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(-75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 2.0)),
-    ));
-    
-    // fire charge bar for player 2
-    commands.spawn((
-        InGame,
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 500.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 450.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 200.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 175.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                        [0.0, 0.0, 1.0, 0.5],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(150.0, config.window_size.y / 2.0 - 90.0 - BARS_OFFSET_Y, 1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 1.0)),
-    ));
-    commands.spawn((
-        InGame,
-        PlayerID(1),
-        #[cfg(not(feature="phone"))]
-        FireBar(1.0, config.window_size.x / 2.0 - 450.0),
-        #[cfg(feature="phone")]
-        FireBar(1.0, config.window_size.x / 2.0 - 175.0),
-        #[cfg(not(feature="phone"))]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [-10.0, 0.0, 1.0],
-                        [-10.0, 20.0, 1.0],
-                        [config.window_size.x / 2.0 - 500.0, 0.0, 1.0],
-                        [config.window_size.x / 2.0 - 450.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                        [20.0, 0.0, 0.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        #[cfg(feature="phone")]
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [0.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                        [-25.0, 0.0, 1.0],
-                        [0.0, 20.0, 1.0],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                        [1.0, 0.0, 0.0, 1.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(150.0, config.window_size.y / 2.0 - 90.0 - BARS_OFFSET_Y, 2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(75.0, config.window_size.y / 2.0 - 50.0 - BARS_OFFSET_Y, 2.0)),
-    ));
-
-    // skill name display
-    commands.spawn((
-        InGame,
-        Visibility::Hidden,
-        Node {
-            justify_self: JustifySelf::Center,
-            align_self: AlignSelf::Center,
-            ..default()
-        },
-        SkillName(0),
-        ImageNode::new(asset_server.load(format!("{}skill_name1.png", PATH_IMAGE_PREFIX))),
-    ));
-    commands.spawn((
-        InGame,
-        Visibility::Hidden,
-        Node {
-            justify_self: JustifySelf::Center,
-            align_self: AlignSelf::Center,
-            ..default()
-        },
-        SkillName(1),
-        ImageNode::new(asset_server.load(format!("{}skill_name2.png", PATH_IMAGE_PREFIX))),
-    ));
-    commands.spawn((
-        InGame,
-        Visibility::Hidden,
-        Node {
-            justify_self: JustifySelf::Center,
-            align_self: AlignSelf::Center,
-            ..default()
-        },
-        SkillName(2),
-        ImageNode::new(asset_server.load(format!("{}skill_name3.png", PATH_IMAGE_PREFIX))),
-    ));
-
-    // curtain for skill
-    commands.spawn((
-        Mesh2d(
-            meshes.add(
-                Mesh::new(
-                    PrimitiveTopology::TriangleList,
-                    RenderAssetUsages::default(),
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_POSITION,
-                    vec![
-                        [
-                            -config.window_size.x / 2.0,
-                            config.window_size.y / 2.0,
-                            10.0,
-                        ],
-                        [config.window_size.x / 2.0, config.window_size.y / 2.0, 10.0],
-                        [
-                            -config.window_size.x / 2.0,
-                            -config.window_size.y / 2.0,
-                            10.0,
-                        ],
-                        [
-                            config.window_size.x / 2.0,
-                            -config.window_size.y / 2.0,
-                            10.0,
-                        ],
-                    ],
-                )
-                .with_inserted_attribute(
-                    Mesh::ATTRIBUTE_COLOR,
-                    vec![
-                        [0.0, 0.0, 0.0, 0.0],
-                        [0.0, 0.0, 0.0, 0.0],
-                        [0.0, 0.0, 0.0, 0.0],
-                        [0.0, 0.0, 0.0, 0.0],
-                    ],
-                )
-                .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 2, 3])),
-            ),
-        ),
-        MeshMaterial2d(materials.add(ColorMaterial::default())),
-        SkillEntity { id: 1 },
-        Transform::from_translation(Vec3::new(0.0, 0.0, 19.0)),
-    ));
-
-    // thunder for skill of character 0
-    commands.spawn((
-        InGame,
-        SkillEntity { id: 0 },
-        Visibility::Hidden,
-        Sprite {
-            image: asset_server.load(format!("{}thunder.png", PATH_IMAGE_PREFIX)),
-            custom_size: Some(Vec2::new(250.0, 1000.0)),
-            ..default()
-        },
-        Transform::from_translation(Vec3::new(0.0, 100.0, 20.0)),
-    ));
-
-    // sky background
-    commands.spawn((
-        #[cfg(not(feature="phone"))]
-        Sprite {
-            image: asset_server.load(format!("{}sky_upscaled.png", PATH_IMAGE_PREFIX)),
-            ..default()
-        },
-        #[cfg(feature="phone")]
-        Sprite {
-            image: asset_server.load(format!("{}sky_original.png", PATH_IMAGE_PREFIX)),
-            ..default()
-        },
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(0.0, 100.0, -2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(0.0, 50.0, -2.0)),
-        SkyBackground,
-        InGame,
-    ));
-    commands.spawn((
-        #[cfg(not(feature="phone"))]
-        Sprite {
-            image: asset_server.load(format!("{}sky_upscaled.png", PATH_IMAGE_PREFIX)),
-            flip_x: true,
-            ..default()
-        },
-        #[cfg(feature="phone")]
-        Sprite {
-            image: asset_server.load(format!("{}sky_original.png", PATH_IMAGE_PREFIX)),
-            flip_x: true,
-            ..default()
-        },
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(4800.0, 100.0, -2.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(1200.0, 50.0, -2.0)),
-        SkyBackground,
+        Sprite {
+            image: asset_server.load(format!("{}{}", layout.image_prefix, layout.sky_image)),
+            flip_x: true,
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(layout.sky_width, 100.0 * layout.scale, -2.0)),
+        SkyBackground,
         InGame,
     ));
 
     // background
+    let stage = stage();
     commands.spawn((
         Sprite {
-            image: asset_server.load(format!("{}background.png", PATH_IMAGE_PREFIX)),
+            image: asset_server.load(format!("{}{}", PATH_IMAGE_PREFIX, stage.image)),
             ..default()
         },
         BackGround,
-        #[cfg(not(feature="phone"))]
-        Transform::from_translation(Vec3::new(0.0, 0.0, -1.0)),
-        #[cfg(feature="phone")]
-        Transform::from_translation(Vec3::new(0.0, 70.0, -1.0)),
+        Transform::from_translation(Vec3::new(0.0, layout.background_y, -1.0)),
+        Interpolated::new(Vec3::new(0.0, layout.background_y, -1.0)),
         InGame,
     ));
-    if cfg!(feature="phone") {
-        spawn_player(
-            0,
-            config.characters_id[0],
-            true,
-            &mut commands,
-            &mut texture_atlas_layouts,
-            &asset_server,
-            135.0 - config.window_size.y / 2.0,
-        );
-        spawn_player(
-            1,
-            config.characters_id[1],
-            true,
-            &mut commands,
-            &mut texture_atlas_layouts,
-            &asset_server,
-            135.0 - config.window_size.y / 2.0,
-        );
-    } else {
-        spawn_player(
-            0,
-            config.characters_id[0],
-            config.mode == GameMode::SinglePlayer,
-            &mut commands,
-            &mut texture_atlas_layouts,
-            &asset_server,
-            270.0 - config.window_size.y / 2.0,
-        );
+    // the match is decided by the simulation, the entities only show it
+    let arena = stage.arena(&layout, config.window_size.x / 2.0);
+    let survival = config.mode == GameMode::Survival;
+    let teams = [0, 1].map(|id| {
+        let team = if config.is_team_battle(&rules) {
+            config.teams[id].clone()
+        } else if survival && id == 1 {
+            survival::opponents(config.characters_id[1], config.characters_id[0])
+        } else {
+            vec![config.characters_id[id]]
+        };
+        team.iter().map(|character_id| character(*character_id).clone()).collect::<Vec<_>>()
+    });
+    let seed = (rand() * u32::MAX as f32) as u32;
+    let game = Game(Match::new(teams, config.handicaps, *poses, arena, *rules, survival, seed));
+    for id in 0..2 {
         spawn_player(
-            1,
-            config.characters_id[1],
-            config.mode == GameMode::SinglePlayer,
+            id,
+            &game,
+            config.mode.has_bot(),
             &mut commands,
-            &mut texture_atlas_layouts,
+            &character_textures,
+            &layout,
             &asset_server,
-            270.0 - config.window_size.y / 2.0,
         );
     }
+    commands.insert_resource(game);
 
     // create controller circle
-    #[cfg(feature="phone")]
-    commands
-        .spawn((
-            InGame,
-            Mesh2d(meshes.add(Circle::new(CONTROLLER_CIRCLE_RADIUS))),
-            MeshMaterial2d(materials.add(Color::srgba(1.0, 1.0, 1.0, 0.4))),
-            Transform::from_translation(Vec3::new(
-                -config.window_size.x / 2.0 + 100.0,
-                -config.window_size.y / 4.0,
-                20.0,
-            )),
-        ))
-        .with_children(|spawner| {
-            spawner.spawn((
-                ControllerCircle,
-                Mesh2d(meshes.add(Circle::new(CONTROLLER_CIRCLE_RADIUS / 3.0))),
-                MeshMaterial2d(materials.add(Color::srgba(1.0, 1.0, 1.0, 1.0))),
-                Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
-            ));
-        });
+    if layout.touch_controller {
+        commands
+            .spawn((
+                InGame,
+                Mesh2d(meshes.add(Circle::new(CONTROLLER_CIRCLE_RADIUS))),
+                MeshMaterial2d(materials.add(Color::srgba(1.0, 1.0, 1.0, 0.4))),
+                Transform::from_translation(Vec3::new(
+                    -config.window_size.x / 2.0 + 100.0,
+                    -config.window_size.y / 4.0,
+                    20.0,
+                )),
+            ))
+            .with_children(|spawner| {
+                spawner.spawn((
+                    ControllerCircle,
+                    Mesh2d(meshes.add(Circle::new(CONTROLLER_CIRCLE_RADIUS / 3.0))),
+                    MeshMaterial2d(materials.add(Color::srgba(1.0, 1.0, 1.0, 1.0))),
+                    Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
+                ));
+            });
+    }
 
-    game_state.phase = 0;
-    game_state.count = 0;
-    game_state.round = 1;
-    game_state.timer = Timer::from_seconds(1.0 / FPS, TimerMode::Repeating);
+    round_flow.restart();
 }
 
+// shows the time left of the round, the `Match` decides when it is up
 fn update_timer(
-    time: Res<Time>,
-    fighting: ResMut<Fighting>,
-    mut gamestate: ResMut<GameState>,
-    mut timer_query: Query<(&mut Text, &mut TextColor, &mut GameTimer)>,
-    health_bar_query: Query<(&HealthBar, &PlayerID)>,
+    game: Res<Game>,
+    mut timer_query: Query<(&mut Text, &mut TextColor), With<GameTimer>>,
+) {
+    let (mut text, mut color) = timer_query.single_mut().unwrap();
+    let seconds = timer_seconds(game.time_left);
+    text.0 = timer_text(seconds);
+    color.0 = if seconds < 5.0 { Color::srgb(1.0, 0.0, 0.0) } else { Color::WHITE };
+}
+
+// The hooks of the round phases, each one reacts to the `RoundPhaseChanged` of its phase.
+// The exit hooks run before the entry hooks of the same logic step.
+
+// the fighters can move once "FIGHT!" has faded out
+fn start_fight(mut commands: Commands, mut phase_changed: EventReader<RoundPhaseChanged>) {
+    if phase_changed.read().any(|event| event.from == Some(RoundPhase::FightFade)) {
+        commands.insert_resource(Fighting);
+    }
+}
+
+// the fighters stop at the end of the KO or the time up
+fn stop_fight(mut commands: Commands, mut phase_changed: EventReader<RoundPhaseChanged>) {
+    if phase_changed
+        .read()
+        .any(|event| matches!(event.from, Some(RoundPhase::Ko) | Some(RoundPhase::TimeUp)))
+    {
+        commands.remove_resource::<Fighting>();
+    }
+}
+
+// behind the closed curtain, the match goes on with the next round or shows the result
+fn prepare_next_round(
+    mut game: ResMut<Game>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
-    if fighting.0 != 0 || gamestate.phase != 5 {
+    if phase_changed.read().any(|event| event.from == Some(RoundPhase::CurtainOut)) && !game.next_round() {
+        next_state.set(AppState::Result);
+    }
+}
+
+fn hide_status_bar(
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<(&mut BackgroundColor, &mut TextColor), With<StatusBar>>,
+) {
+    if !phase_changed
+        .read()
+        .any(|event| matches!(event.from, Some(RoundPhase::FightFade) | Some(RoundPhase::CurtainOut)))
+    {
         return;
     }
-    let (mut text, mut color, mut timer) = timer_query.single_mut().unwrap();
-    if timer.0 == 0.0 {
+    if let Ok((mut bar, mut text_color)) = status_bar_query.single_mut() {
+        bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.0);
+        text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.0);
+    }
+}
+
+// the texts under the bars follow the next round,
+// and the bot of a survival gets stronger with the opponents defeated
+fn update_side_displays(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    game: Res<Game>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut display_query: Query<(&mut Text2d, &PlayerID), With<SideDisplay>>,
+) {
+    // nothing to follow when the match is over
+    if !phase_changed.read().any(|event| event.from == Some(RoundPhase::CurtainOut)) || game.is_round_over() {
         return;
     }
-    timer.0 -= time.delta_secs();
-    if timer.0 < 0.0 {
-        timer.0 = 0.0;
-        let bars: Vec<_> = health_bar_query.iter().collect();
-        let winner_id = if bars[0].0 .0 < bars[1].0 .0 {
-            bars[1].1 .0 + 1
-        } else if bars[0].0 .0 == bars[1].0 .0 {
-            0
+    let survival = config.mode == GameMode::Survival;
+    let positions = team_positions(&game.winners);
+    let level = survival::opponent_level(config.level, survival::defeated(&game.winners));
+    if survival {
+        commands.insert_resource(Agent::new(level));
+    }
+    for (mut text, id) in display_query.iter_mut() {
+        text.0 = if survival {
+            survival_text(id.0, &game.winners, level)
         } else {
-            bars[0].1 .0 + 1
+            let team = &config.teams[id.0 as usize];
+            team_text(team, positions[id.0 as usize].min(team.len() - 1))
         };
-        let round = gamestate.round as usize - 1;
-        gamestate.winners[round] = winner_id;
-        gamestate.win_types[round] = false;
-        gamestate.phase = 6;
-    } else if timer.0 < 5.0 {
-        color.0 = Color::srgb(1.0, 0.0, 0.0);
     }
-    text.0 = format!("{:.2}", timer.0);
 }
 
-fn check_gameset(
-    mut gamestate: ResMut<GameState>,
-    fighting: ResMut<Fighting>,
-    player_query: Query<(&Player, &PlayerID)>
+// the health and fire bars start the next round from the health of the fighters and full
+fn reset_bars(
+    game: Res<Game>,
+    layout: Res<Layout>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut health_query: Query<(&mut HealthBar, &mut Mesh2d, &PlayerID), Without<FireBar>>,
+    mut fire_query: Query<(&mut FireBar, &mut Mesh2d, &PlayerID), Without<HealthBar>>,
 ) {
-    if fighting.0 != 0 {
+    // nothing to reset when the match is over
+    if !phase_changed.read().any(|event| event.from == Some(RoundPhase::CurtainOut)) || game.is_round_over() {
         return;
     }
-    for (player, player_id) in player_query.iter() {
-        if player.health == 0 {
-            let round = gamestate.round as usize - 1;
-            gamestate.winners[round] = if player_id.0 == 0 { 2 } else { 1 };
-            gamestate.win_types[round] = true;
-            if gamestate.phase == 5 {
-                gamestate.count = 0;
-                gamestate.phase = 6;
+    for (mut health_bar, mesh_handler, health_id) in health_query.iter_mut() {
+        health_bar.0 = sim::health_ratio(&game.players[health_id.0 as usize]);
+        if let Some(mesh) = meshes.get_mut(mesh_handler.id()) {
+            if let Some(VertexAttributeValues::Float32x3(ref mut positions)) =
+                mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+            {
+                positions[3][0] = health_bar.1 * health_bar.0;
+                positions[2][0] = health_bar.1 * health_bar.0
+                    + BAR_SLANT * layout.scale * if health_id.0 == 0 { 1.0 } else { -1.0 };
+            }
+        }
+    }
+    for (mut fire_bar, mesh_handler, fire_id) in fire_query.iter_mut() {
+        fire_bar.0 = 1.0;
+        if let Some(mesh) = meshes.get_mut(mesh_handler.id()) {
+            if let Some(VertexAttributeValues::Float32x3(ref mut positions)) =
+                mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+            {
+                positions[3][0] = fire_bar.1 * fire_bar.0;
+                positions[2][0] = fire_bar.1 * fire_bar.0
+                    + BAR_SLANT * layout.scale * if fire_id.0 == 0 { 1.0 } else { -1.0 };
+            }
+            if let Some(VertexAttributeValues::Float32x4(ref mut colors)) =
+                mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+            {
+                for i in 0..4 {
+                    colors[i][0] = 20.0 * layout.glow;
+                }
             }
-            break;
         }
     }
 }
 
-fn main_game_system(
+// remove the audio players of the round (unused sound effect entities)
+fn clear_sound_effects(
+    mut commands: Commands,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    sound_query: Query<Entity, With<SoundEffect>>,
+) {
+    if !phase_changed.read().any(|event| event.from == Some(RoundPhase::CurtainOut)) {
+        return;
+    }
+    for entity in sound_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// "ROUND n" with its voice, the curtain and the status bar are spawned for the first round
+fn show_round_title(
     mut commands: Commands,
-    time: Res<Time>,
-    mut config: (Res<GameConfig>, ResMut<PlayerCollision>),
     asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut gamestate: ResMut<GameState>,
-    mut next_state: ResMut<NextState<AppState>>,
-    character_textures: Res<CharacterTextures>,
+    config: Res<GameConfig>,
+    game: Res<Game>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<(&mut BackgroundColor, &mut Text, &mut TextColor), With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::RoundIntro) {
+        return;
+    }
+    if game.round == 1 {
+        commands.spawn((
+            InGame,
+            Curtain,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_self: JustifySelf::Center,
+                align_self: AlignSelf::Center,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.0)),
+        ));
+        commands.spawn((
+            InGame,
+            StatusBar,
+            Node {
+                width: Val::Percent(100.0),
+                justify_self: JustifySelf::Center,
+                align_self: AlignSelf::Center,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            Text::new(round_title(&game, config.mode)),
+            TextFont {
+                font: asset_server.load(PATH_EXTRA_BOLD_FONT),
+                font_size: TITLE_FONT_SIZE,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            TextColor(Color::srgba(1.0, 1.0, 1.0, 0.8)),
+        ));
+    } else if let Ok((mut bar, mut text, mut text_color)) = status_bar_query.single_mut() {
+        bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8);
+        text.0 = round_title(&game, config.mode);
+        text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8);
+    }
+    // there is no voice for the rounds after the third nor for a sudden death
+    if game.round <= ROUND_VOICES && !game.is_sudden_death() {
+        commands.spawn((
+            AudioPlayer::new(asset_server.load(format!(
+                "{}round{}.ogg",
+                PATH_SOUND_PREFIX, game.round
+            ))),
+            SoundEffect,
+        ));
+    }
+}
+
+fn show_ready(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<&mut Text, With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::Ready) {
+        return;
+    }
+    if let Ok(mut text) = status_bar_query.single_mut() {
+        text.0 = "READY?".to_string();
+    }
+    // TODO: I have to think about how to handle spawned Audio Player entity
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(format!("{}ready.ogg", PATH_SOUND_PREFIX))),
+        SoundEffect,
+    ));
+}
+
+fn show_fight(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<&mut Text, With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::Fight) {
+        return;
+    }
+    if let Ok(mut text) = status_bar_query.single_mut() {
+        text.0 = "FIGHT!".to_string();
+    }
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(format!("{}fight.ogg", PATH_SOUND_PREFIX))),
+        SoundEffect,
+    ));
+}
+
+// "KO!" starts the winner and loser animation, step_match moves on to the winner pose once it is over
+fn show_ko(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))] mut game: ResMut<Game>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<(&mut BackgroundColor, &mut Text, &mut TextColor), With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::Ko) {
+        return;
+    }
+    if let Ok((mut bar, mut text, mut text_color)) = status_bar_query.single_mut() {
+        bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8);
+        text.0 = "KO!".to_string();
+        text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8);
+    }
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(format!("{}KO.ogg", PATH_SOUND_PREFIX))),
+        SoundEffect,
+    ));
+    #[cfg(not(target_arch = "wasm32"))]
+    game.start_final_poses();
+}
+
+fn show_time_up(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
     mut status_bar_query: Query<(&mut BackgroundColor, &mut Text, &mut TextColor), With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::TimeUp) {
+        return;
+    }
+    if let Ok((mut bar, mut text, mut text_color)) = status_bar_query.single_mut() {
+        bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8);
+        text.0 = "TIME UP!".to_string();
+        text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8);
+    }
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(format!("{}timeup.ogg", PATH_SOUND_PREFIX))),
+        SoundEffect,
+    ));
+}
+
+fn announce_winner(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game: Res<Game>,
+    mut phase_changed: EventReader<RoundPhaseChanged>,
+    mut status_bar_query: Query<&mut Text, With<StatusBar>>,
+) {
+    if !phase_changed.read().any(|event| event.to == RoundPhase::Announce) {
+        return;
+    }
+    let winner_id = game.round_winner();
+    let (status, voice) = if winner_id == 0 {
+        ("DRAW".to_string(), "draw.ogg".to_string())
+    } else {
+        (format!("Player {} WIN", winner_id), format!("player{}_win.ogg", winner_id))
+    };
+    if let Ok(mut text) = status_bar_query.single_mut() {
+        text.0 = status;
+    }
+    commands.spawn((
+        AudioPlayer::new(asset_server.load(format!("{}{}", PATH_SOUND_PREFIX, voice))),
+        SoundEffect,
+    ));
+}
+
+/// Fade the status bar and the curtain during the timed phases
+fn animate_round_phase(
+    flow: Res<RoundFlow>,
+    mut status_bar_query: Query<(&mut BackgroundColor, &mut TextColor), With<StatusBar>>,
     mut curtain_query: Query<&mut BackgroundColor, (With<Curtain>, Without<StatusBar>)>,
-    mut background_query: Query<&mut Transform, (With<BackGround>, Without<Player>)>,
-    mut player_query: Query<
-        (&PlayerID, &mut Player, &mut Sprite, &mut Transform),
-        Without<BackGround>,
-    >,
-    mut health_query: Query<(&mut HealthBar, &mut Mesh2d, &PlayerID), Without<FireBar>>,
-    mut fire_query: (Query<(&mut FireBar, &mut Mesh2d, &PlayerID), Without<HealthBar>>, Query<Entity, (With<FireAnimation>, Without<SoundEffect>)>),
-    mut timer_query: Query<(&mut Text, &mut TextColor, &mut GameTimer), Without<StatusBar>>,
-    sound_query: Query<Entity, (With<SoundEffect>, Without<FireAnimation>)>,
 ) {
-    gamestate.timer.tick(time.delta());
-    if gamestate.timer.just_finished() {
-        if gamestate.phase == 0 {
-            if gamestate.round == 1 {
-                commands.spawn((
-                    InGame,
-                    Curtain,
-                    Node {
-                        width: Val::Percent(100.0),
-                        height: Val::Percent(100.0),
-                        justify_self: JustifySelf::Center,
-                        align_self: AlignSelf::Center,
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.0)),
-                ));
-                commands.spawn((
-                    InGame,
-                    StatusBar,
-                    Node {
-                        width: Val::Percent(100.0),
-                        justify_self: JustifySelf::Center,
-                        align_self: AlignSelf::Center,
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
-                    Text::new("ROUND 1"),
-                    TextFont {
-                        font: asset_server.load(PATH_EXTRA_BOLD_FONT),
-                        font_size: TITLE_FONT_SIZE,
-                        ..default()
-                    },
-                    TextLayout::new_with_justify(JustifyText::Center),
-                    TextColor(Color::srgba(1.0, 1.0, 1.0, 0.8)),
-                ));
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load(format!(
-                        "{}round{}.ogg",
-                        PATH_SOUND_PREFIX, gamestate.round
-                    ))),
-                    SoundEffect,
-                ));
-            } else {
-                let (mut bar, mut text, mut text_color) = status_bar_query.single_mut().unwrap();
-                bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8);
-                text.0 = format!("ROUND {}", gamestate.round);
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load(format!(
-                        "{}round{}.ogg",
-                        PATH_SOUND_PREFIX, gamestate.round
-                    ))),
-                    SoundEffect,
-                ));
-                text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8);
-            }
-            gamestate.phase = 1;
-            gamestate.count = 0;
-        } else if gamestate.phase == 1 {
-            gamestate.count += 1;
-            if gamestate.count == 60 {
-                let (_, mut text, _) = status_bar_query.single_mut().unwrap();
-                text.0 = "READY?".to_string();
-                // TODO: I have to think about how to handle spawned Audio Player entity
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load(format!("{}ready.ogg", PATH_SOUND_PREFIX))),
-                    SoundEffect,
-                ));
-                gamestate.phase = 2;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 2 {
-            gamestate.count += 1;
-            if gamestate.count == 90 {
-                let (_, mut text, _) = status_bar_query.single_mut().unwrap();
-                text.0 = "FIGHT!".to_string();
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load(format!("{}fight.ogg", PATH_SOUND_PREFIX))),
-                    SoundEffect,
-                ));
-                gamestate.phase = 3;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 3 {
-            gamestate.count += 1;
-            if gamestate.count == 30 {
-                gamestate.phase = 4;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 4 {
-            gamestate.count += 1;
-            let (mut bar, _, mut text_color) = status_bar_query.single_mut().unwrap();
-            bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8 - gamestate.count as f32 / 60.0);
-            text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8 - gamestate.count as f32 / 60.0);
-            if gamestate.count == 48 {
-                commands.insert_resource(Fighting(0));
-                gamestate.phase = 5;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 6 {
-            if gamestate.count == 0 {
-                let (mut bar, mut text, mut text_color) = status_bar_query.single_mut().unwrap();
-                bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.8);
-                text.0 = if gamestate.win_types[gamestate.round as usize - 1] {
-                    "KO!".to_string()
-                } else {
-                    "TIME UP!".to_string()
-                };
-                if gamestate.win_types[gamestate.round as usize - 1] {
-                    commands.spawn((
-                        AudioPlayer::new(
-                            asset_server.load(format!("{}KO.ogg", PATH_SOUND_PREFIX)),
-                        ),
-                        SoundEffect,
-                    ));
-                } else {
-                    commands.spawn((
-                        AudioPlayer::new(
-                            asset_server.load(format!("{}timeup.ogg", PATH_SOUND_PREFIX)),
-                        ),
-                        SoundEffect,
-                    ));
-                }
-                text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.8);
-                // winner and loser animation
-                if cfg!(target_arch = "wasm32") {
-                    commands.remove_resource::<Fighting>();
-                    gamestate.phase = 7;
-                    gamestate.count = 0;   
-                }
-                #[cfg(not(target_arch = "wasm32"))]
-                if !gamestate.win_types[gamestate.round as usize - 1] {
-                    commands.remove_resource::<Fighting>();
-                    gamestate.phase = 7;
-                    gamestate.count = 0;
-                } else {
-                    for (id, mut player, mut sprite, _) in player_query.iter_mut() {
-                        if gamestate.winners[gamestate.round as usize - 1] == id.0 + 1 {
-                            sprite.image = character_textures.textures[player.character_id as usize].victory.clone();
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                            player.animation_frame_max = FRAMES_VICTORY;
-                            player.animation.count = FRAMES_VICTORY as u8;
-                        } else {
-                            sprite.image = character_textures.textures[player.character_id as usize].defeated.clone();
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                            player.animation_frame_max = FRAMES_DEFEATED;
-                            player.animation.count = FRAMES_DEFEATED as u8;
-                        }
-                    }
-                    gamestate.count = 1;
-                }
+    match flow.phase {
+        RoundPhase::FightFade => {
+            if let Ok((mut bar, mut text_color)) = status_bar_query.single_mut() {
+                let alpha = 0.8 * (1.0 - flow.progress());
+                bar.0 = Color::srgba(0.0, 0.0, 0.0, alpha);
+                text_color.0 = Color::srgba(1.0, 1.0, 1.0, alpha);
             }
-        } else if gamestate.phase == 7 {
-            gamestate.count += 1;
-            if gamestate.count == 60 {
-                gamestate.phase = 8;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 8 {
-            let (_, mut text, _) = status_bar_query.single_mut().unwrap();
-            let winner_id = gamestate.winners[gamestate.round as usize - 1];
-            if winner_id == 0 {
-                text.0 = "DRAW".to_string();
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load(format!("{}draw.ogg", PATH_SOUND_PREFIX))),
-                    SoundEffect,
-                ));
-            } else {
-                text.0 = format!("Player {} WIN", winner_id);
-                commands.spawn((
-                    AudioPlayer::new(
-                        asset_server
-                            .load(format!("{}player{}_win.ogg", PATH_SOUND_PREFIX, winner_id)),
-                    ),
-                    SoundEffect,
-                ));
-            }
-            gamestate.phase = 9;
-            gamestate.count = 0;
-        } else if gamestate.phase == 9 {
-            gamestate.count += 1;
-            if gamestate.count == 60 {
-                gamestate.phase = 10;
-                gamestate.count = 0;
-            }
-        } else if gamestate.phase == 10 {
-            gamestate.count += 1;
-            let mut curtain = curtain_query.single_mut().unwrap();
-            curtain.0 = Color::srgba(0.0, 0.0, 0.0, gamestate.count as f32 / 60.0);
-            if gamestate.count == 60 {
-                gamestate.round += 1;
-                if gamestate.round == 4 {
-                    // change app state to show result
-                    next_state.set(AppState::Result);
-                } else {
-                    gamestate.phase = 11;
-                    gamestate.count = 0;
-
-                    // remove status bar
-                    let (mut bar, _, mut text_color) = status_bar_query.single_mut().unwrap();
-                    bar.0 = Color::srgba(0.0, 0.0, 0.0, 0.0);
-                    text_color.0 = Color::srgba(1.0, 1.0, 1.0, 0.0);
-                    // reset background
-                    background_query.single_mut().unwrap().translation.x = 0.0;
-                    // reset player
-                    for (id, mut player, mut sprite, mut transform) in player_query.iter_mut() {
-                        sprite.image = character_textures.textures[player.character_id as usize].idle.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        if id.0 == 0 {
-                            sprite.flip_x = false;
-                        } else {
-                            sprite.flip_x = true;
-                        }
-                        player.reset(id);
-                        if cfg!(feature="phone") {
-                            transform.translation.x = if id.0 == 0 { -250.0 } else { 250.0 };
-                            transform.translation.y = 135.0 - config.0.window_size.y / 2.0;
-                        } else {
-                            transform.translation.x = if id.0 == 0 { -500.0 } else { 500.0 };
-                            transform.translation.y = 270.0 - config.0.window_size.y / 2.0;
-                        }
-                    }
-                    //reset player collision state
-                    config.1.0 = 2;
-
-                    // reset health bar
-                    for (mut health_bar, mesh_handler, health_id) in health_query.iter_mut() {
-                        health_bar.0 = 1.0;
-                        if let Some(mesh) = meshes.get_mut(mesh_handler.id()) {
-                            if let Some(VertexAttributeValues::Float32x3(ref mut positions)) =
-                                mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
-                            {
-                                positions[3][0] = health_bar.1 * health_bar.0;
-                                if cfg!(feature="phone") {
-                                    positions[2][0] = health_bar.1 * health_bar.0
-                                        + if health_id.0 == 0 { 25.0 } else { -25.0 };
-                                } else {
-                                    positions[2][0] = health_bar.1 * health_bar.0
-                                        + if health_id.0 == 0 { 50.0 } else { -50.0 };
-                                }
-                            }
-                        }
-                    }
-                    // reset fire bar
-                    for (mut fire_bar, mesh_handler, fire_id) in fire_query.0.iter_mut() {
-                        fire_bar.0 = 1.0;
-                        if let Some(mesh) = meshes.get_mut(mesh_handler.id()) {
-                            if let Some(VertexAttributeValues::Float32x3(ref mut positions)) =
-                                mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
-                            {
-                                positions[3][0] = fire_bar.1 * fire_bar.0;
-                                if cfg!(feature="phone") {
-                                    positions[2][0] = fire_bar.1 * fire_bar.0
-                                        + if fire_id.0 == 0 { 25.0 } else { -25.0 };
-                                } else {
-                                    positions[2][0] = fire_bar.1 * fire_bar.0
-                                        + if fire_id.0 == 0 { 50.0 } else { -50.0 };
-                                }
-                            }
-                            if let Some(VertexAttributeValues::Float32x4(ref mut colors)) =
-                                mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
-                            {
-                                for i in 0..4 {
-                                    colors[i][0] = 20.0;
-                                }
-                            }
-                        }
-                    }
-                    // despawn fire animation entities
-                    for entity in fire_query.1.iter() {
-                        commands.entity(entity).despawn();
-                    }
-                    // reset timer
-                    let (mut text, mut color, mut timer) = timer_query.single_mut().unwrap();
-                    timer.0 = 60.0;
-                    text.0 = "60.00".to_string();
-                    color.0 = Color::WHITE;
-                    // reset audio player(unused sound effect entity)
-                    for entity in sound_query.iter() {
-                        commands.entity(entity).despawn();
-                    }
-                }
+        }
+        RoundPhase::CurtainOut => {
+            if let Ok(mut curtain) = curtain_query.single_mut() {
+                curtain.0 = Color::srgba(0.0, 0.0, 0.0, flow.progress());
             }
-        } else if gamestate.phase == 11 {
-            gamestate.count += 1;
-            let mut curtain = curtain_query.single_mut().unwrap();
-            curtain.0 = Color::srgba(0.0, 0.0, 0.0, 1.0 - gamestate.count as f32 / 60.0);
-            if gamestate.count == 60 {
-                gamestate.phase = 0;
-                gamestate.count = 0;
+        }
+        RoundPhase::CurtainIn => {
+            if let Ok(mut curtain) = curtain_query.single_mut() {
+                curtain.0 = Color::srgba(0.0, 0.0, 0.0, 1.0 - flow.progress());
             }
         }
+        _ => {}
     }
 }
 
-fn move_background(mut query: Query<&mut Transform, With<SkyBackground>>) {
+fn move_background(layout: Res<Layout>, mut query: Query<&mut Transform, With<SkyBackground>>) {
     // move sky background
     for mut transform in query.iter_mut() {
         transform.translation.x -= 0.25;
-        if cfg!(feature="phone") {
-            if transform.translation.x < -1150.0 {
-                transform.translation.x = 1150.0;
-            }
-        } else {
-            if transform.translation.x < -4750.0 {
-                transform.translation.x = 4750.0;
-            }
+        if transform.translation.x < 50.0 - layout.sky_width {
+            transform.translation.x = layout.sky_width - 50.0;
         }
     }
 }
@@ -1676,6 +1217,29 @@ fn check_pause(
     }
 }
 
+// put back the logical position before each logic step
+fn restore_logic_position(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        transform.translation = interpolated.current;
+        interpolated.previous = interpolated.current;
+    }
+}
+
+// remember the logical position after each logic step
+fn record_logic_position(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = transform.translation;
+    }
+}
+
+// display the position between the last two logic steps
+fn interpolate_position(fixed_time: Res<Time<Fixed>>, mut query: Query<(&mut Transform, &Interpolated)>) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in query.iter_mut() {
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+    }
+}
+
 fn exit(mut commands: Commands, query: Query<Entity, With<InGame>>) {
     info!("exit");
     for entity in query.iter() {
@@ -1688,42 +1252,84 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        #[cfg(debug_assertions)]
-        // add debug plugin for rapier2d
-        app.add_plugins(RapierDebugRenderPlugin::default());
         #[cfg(feature="pause")]
         app
             .add_plugins(PausePlugin)
             .add_systems(Update, check_pause);
         
         #[cfg(not(target_arch = "wasm32"))]
-        app.add_plugins(ControllerPlugin);
+        app.add_plugins(ControllerPlugin)
+            .add_plugins(pose_loader::PoseLoaderPlugin);
 
-        #[cfg(feature="phone")]
+        // the fallback poses until the pose files are loaded, and on wasm
+        app.init_resource::<PoseSet>();
         app.insert_resource(TouchState {
             start_position: Vec2::ZERO,
             id: u64::MAX,
         })
         .add_systems(
             Update,
-            touch_input.run_if(in_state(AppState::Ingame).and(resource_exists::<Fighting>)),
+            touch_input.run_if(
+                in_state(AppState::Ingame)
+                    .and(resource_exists::<Fighting>)
+                    .and(|layout: Res<Layout>| layout.touch_controller),
+            ),
         );
 
         app.add_plugins(PlayerPlugin)
             .add_plugins(AgentPlugin)
-            .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(300.0))
-            .insert_resource(GameState::default())
+            .insert_resource(MatchRules::default())
+            .insert_resource(RoundFlow::default())
+            .add_event::<RoundPhaseChanged>()
+            // all the match logic runs at FPS steps per second, whatever the frame rate is
+            .insert_resource(Time::<Fixed>::from_hz(FPS as f64))
             .add_systems(OnEnter(AppState::Ingame), setup)
             .add_systems(OnExit(AppState::Ingame), exit)
+            .add_systems(FixedFirst, restore_logic_position)
+            .add_systems(FixedLast, record_logic_position)
             .add_systems(
-                Update,
-                update_timer.run_if(in_state(AppState::Ingame).and(resource_exists::<Fighting>)),
+                RunFixedMainLoop,
+                interpolate_position.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
             )
-            .add_systems(
-                Update,
-                check_gameset.run_if(in_state(AppState::Ingame).and(resource_exists::<Fighting>)),
+            .configure_sets(
+                FixedUpdate,
+                (
+                    LogicStep::Input,
+                    LogicStep::Step,
+                    LogicStep::Sync,
+                    LogicStep::Show,
+                    LogicStep::Timer,
+                    LogicStep::RoundFlow,
+                    LogicStep::Hooks,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Ingame)),
             )
-            .add_systems(Update, move_background.run_if(in_state(AppState::Ingame)))
-            .add_systems(Update, main_game_system.run_if(in_state(AppState::Ingame)));
+            .add_systems(FixedUpdate, update_timer.in_set(LogicStep::Timer))
+            .add_systems(FixedUpdate, move_background.in_set(LogicStep::Sync))
+            .add_systems(FixedUpdate, advance_round_flow.in_set(LogicStep::RoundFlow))
+            .add_systems(
+                FixedUpdate,
+                (
+                    // exit hooks
+                    start_fight,
+                    stop_fight,
+                    prepare_next_round,
+                    hide_status_bar,
+                    update_side_displays,
+                    reset_bars,
+                    clear_sound_effects,
+                    // entry hooks
+                    show_round_title,
+                    show_ready,
+                    show_fight,
+                    show_ko,
+                    show_time_up,
+                    announce_winner,
+                    animate_round_phase,
+                )
+                    .chain()
+                    .in_set(LogicStep::Hooks),
+            );
     }
 }
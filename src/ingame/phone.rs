@@ -1,7 +1,6 @@
 use crate::{
-    character_def::*,
-    ingame::{player::*, pose::*},
-    GameConfig, CharacterTextures
+    ingame::{player::*, sim::*},
+    GameConfig,
 };
 use bevy::{input::touch::TouchPhase, prelude::*};
 use std::f32::consts::PI;
@@ -40,11 +39,10 @@ fn convert_touch_to_world(touch_position: Vec2, window_size: Vec2) -> Vec2 {
 
 pub fn touch_input(
     config: Res<GameConfig>,
-    character_textures: Res<CharacterTextures>,
     mut touch_state: ResMut<TouchState>,
     mut touch_evr: EventReader<TouchInput>,
     mut circle_query: Query<&mut Transform, With<ControllerCircle>>,
-    mut player_query: Query<(&mut Player, &PlayerID, &mut Sprite)>,
+    mut player_query: Query<(&mut FighterCommand, &PlayerID)>,
 ) {
     for event in touch_evr.read() {
         match event.phase {
@@ -116,183 +114,43 @@ pub fn touch_input(
         if circle_transform.translation == Vec3::new(0.0, 0.0, 1.0) {
             // none
             circle_state = CircleState::None;
-        } else if circle_radian >= -PI / 8.0 && circle_radian < PI / 8.0 {
+        } else if (-PI / 8.0..PI / 8.0).contains(&circle_radian) {
             // right
             circle_state = CircleState::Right;
-        } else if circle_radian >= PI / 8.0 && circle_radian < 3.0 * PI / 8.0 {
+        } else if (PI / 8.0..3.0 * PI / 8.0).contains(&circle_radian) {
             // down right
             circle_state = CircleState::DownRight;
-        } else if circle_radian >= 3.0 * PI / 8.0 && circle_radian < 5.0 * PI / 8.0 {
+        } else if (3.0 * PI / 8.0..5.0 * PI / 8.0).contains(&circle_radian) {
             // down
             circle_state = CircleState::Down;
-        } else if circle_radian >= 5.0 * PI / 8.0 && circle_radian < 7.0 * PI / 8.0 {
+        } else if (5.0 * PI / 8.0..7.0 * PI / 8.0).contains(&circle_radian) {
             // down left
             circle_state = CircleState::DownLeft;
-        } else if circle_radian >= 7.0 * PI / 8.0 || circle_radian < -7.0 * PI / 8.0 {
+        } else if !(-7.0 * PI / 8.0..7.0 * PI / 8.0).contains(&circle_radian) {
             // left
             circle_state = CircleState::Left;
-        } else if circle_radian >= -7.0 * PI / 8.0 && circle_radian < -5.0 * PI / 8.0 {
+        } else if (-7.0 * PI / 8.0..-5.0 * PI / 8.0).contains(&circle_radian) {
             // up left
             circle_state = CircleState::UpLeft;
-        } else if circle_radian >= -5.0 * PI / 8.0 && circle_radian < -3.0 * PI / 8.0 {
+        } else if (-5.0 * PI / 8.0..-3.0 * PI / 8.0).contains(&circle_radian) {
             // up
             circle_state = CircleState::Up;
-        } else if circle_radian >= -3.0 * PI / 8.0 && circle_radian < -PI / 8.0 {
+        } else if (-3.0 * PI / 8.0..-PI / 8.0).contains(&circle_radian) {
             // up right
             circle_state = CircleState::UpRight;
         }
-        // change state of player 1
-        if let Some((mut player, _, mut sprite)) = player_query
+        // write the command of player 1
+        if let Some((mut command, _)) = player_query
             .iter_mut()
-            .find(|(_, player_id, _)| player_id.0 == 0)
+            .find(|(_, player_id)| player_id.0 == 0)
         {
-            match circle_state {
-                CircleState::Right => {
-                    if player.state.is_idle() {
-                        // player is just walking
-                        sprite.image = character_textures.textures[player.character_id as usize].walk.clone();
-                        if player.pose.facing {
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        } else {
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = FRAMES_WALK - 1);
-                        }
-                        player.animation_frame_max = FRAMES_WALK;
-                        player.state |= PlayerState::WALKING;
-                        player.pose.set(WALKING_POSE1);
-                        player.set_animation(WALKING_POSE2, 1, 15);
-                    }
-                    // direction is right
-                    player.state |= PlayerState::DIRECTION;
-                }
-                CircleState::Left => {
-                    if player.state.is_idle() {
-                        // player is just walking
-                        sprite.image = character_textures.textures[player.character_id as usize].walk.clone();
-                        if !player.pose.facing {
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        } else {
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = FRAMES_WALK - 1);
-                        }
-                        player.animation_frame_max = FRAMES_WALK;
-                        player.state |= PlayerState::WALKING;
-                        player.pose.set(WALKING_POSE1);
-                        player.set_animation(WALKING_POSE2, 1, 15);
-                    }
-                    // direction is left
-                    player.state &= !PlayerState::DIRECTION;
-                }
-                CircleState::Up => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_JUMP;
-                        player.state |= PlayerState::JUMP_UP;
-                        player.pose.set(JUMP_POSE1);
-                        player.set_animation(JUMP_POSE2, 0, 11);
-                        player.velocity = Vec2::new(0.0, 12.0);
-                        player.energy += 1;
-                    } else if !player.state.check(
-                        PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD | PlayerState::JUMP_BACKWARD,
-                    ) && player.state.check(PlayerState::WALKING) {
-                        if player.state.check(PlayerState::DIRECTION) {
-                            sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                            player.animation_frame_max = FRAMES_JUMP;
-                            player.state |= PlayerState::JUMP_FORWARD;
-                            player.pose.set(JUMP_POSE1);
-                            player.set_animation(JUMP_POSE2, 0, 11);
-                            let x_vel = CHARACTER_PROFILES[player.character_id as usize].agility;
-                            player.velocity = Vec2::new(x_vel, 12.0);
-                        } else {
-                            sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                            player.animation_frame_max = FRAMES_JUMP;
-                            player.state |= PlayerState::JUMP_BACKWARD;
-                            player.pose.set(JUMP_POSE1);
-                            player.set_animation(JUMP_POSE2, 0, 11);
-                            let x_vel = CHARACTER_PROFILES[player.character_id as usize].agility;
-                            player.velocity = Vec2::new(-x_vel, 12.0);
-                        }
-                        player.energy += 1;
-                    }
-                }
-                CircleState::UpRight => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_JUMP;
-                        player.state |= PlayerState::DIRECTION;
-                        player.state |= PlayerState::JUMP_FORWARD;
-                        player.pose.set(JUMP_POSE1);
-                        player.set_animation(JUMP_POSE2, 0, 11);
-                        player.velocity = Vec2::ZERO;
-                        player.energy += 1;
-                    }
-                }
-                CircleState::UpLeft => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].jump.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_JUMP;
-                        player.state &= !PlayerState::DIRECTION;
-                        player.state |= PlayerState::JUMP_BACKWARD;
-                        player.pose.set(JUMP_POSE1);
-                        player.set_animation(JUMP_POSE2, 0, 11);
-                        player.velocity = Vec2::ZERO;
-                        player.energy += 1;
-                    }
-                }
-                CircleState::Down => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].bend_down.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_BEND_DOWN;
-                        player.state |= PlayerState::BEND_DOWN;
-                        player.pose.set(BEND_DOWN_POSE1);
-                        player.set_animation(BEND_DOWN_POSE2, 0, 27);
-                    }
-                }
-                CircleState::DownRight => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].roll.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_ROLL;
-                        player.state |= PlayerState::ROLL_FORWARD;
-                        player.pose.set(ROLL_FORWARD_POSE1);
-                        player.set_animation(ROLL_FORWARD_POSE2, 0, 11);
-                        player.state |= PlayerState::DIRECTION;
-                        let x_vel = if player.state.is_forward() { 1.0 } else { -1.0 }
-                            * CHARACTER_PROFILES[player.character_id as usize].agility * 2.0;
-                        player.velocity = Vec2::new(x_vel, 0.0);
-                    }
-                }
-                CircleState::DownLeft => {
-                    if player.state.is_idle() {
-                        sprite.image = character_textures.textures[player.character_id as usize].roll.clone();
-                        sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                        player.animation_frame_max = FRAMES_ROLL;
-                        player.state |= PlayerState::ROLL_FORWARD;
-                        player.pose.set(ROLL_FORWARD_POSE1);
-                        player.set_animation(ROLL_FORWARD_POSE2, 0, 11);
-                        player.state &= !PlayerState::DIRECTION;
-                        let x_vel = if player.state.is_forward() { 1.0 } else { -1.0 }
-                            * CHARACTER_PROFILES[player.character_id as usize].agility * 2.0;
-                        player.velocity = Vec2::new(x_vel, 0.0);
-                    }
-                }
-                CircleState::None => {
-                    if player.state.check(PlayerState::WALKING) {
-                        player.state &= !PlayerState::WALKING;
-                        if player.state.is_idle() {
-                            sprite.image = character_textures.textures[player.character_id as usize].idle.clone();
-                            sprite.texture_atlas.as_mut().map(|atlas| atlas.index = 0);
-                            player.animation_frame_max = FRAMES_IDLE;
-                            player.pose.set(IDLE_POSE1);
-                            player.set_animation(IDLE_POSE2, 0, 15);
-                        }
-                    }
-                }
-            }
+            command.write(&FighterInput {
+                left: matches!(circle_state, CircleState::Left | CircleState::UpLeft | CircleState::DownLeft),
+                right: matches!(circle_state, CircleState::Right | CircleState::UpRight | CircleState::DownRight),
+                down: matches!(circle_state, CircleState::Down | CircleState::DownLeft | CircleState::DownRight),
+                jump: matches!(circle_state, CircleState::Up | CircleState::UpLeft | CircleState::UpRight),
+                ..Default::default()
+            });
         }
     }
 }
@@ -1,4 +1,5 @@
-use std::ops::{Add, AddAssign, Div, Sub};
+use bevy::prelude::Resource;
+use std::ops::{Add, AddAssign, Mul, Sub};
 
 // number of frames for each action
 pub const FRAMES_IDLE: usize = 57;
@@ -19,7 +20,7 @@ pub const FRAMES_ATTACKED: usize = 25;
 // facing: true means right facing, false means left facing
 // offset: the offset of the character from the center of the body([x, y])
 // old_offset: the offset of the character from the center of the body in the previous frame
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Pose {
     // true means right facing, false means left facing
     pub facing: bool,
@@ -110,43 +111,99 @@ impl Sub for Pose {
     }
 }
 
-impl Div<f32> for Pose {
+impl Mul<f32> for Pose {
     type Output = Self;
 
-    fn div(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: f32) -> Self::Output {
         Self {
             facing: self.facing,
-            offset: [self.offset[0] / rhs, self.offset[1] / rhs],
+            offset: [self.offset[0] * rhs, self.offset[1] * rhs],
             old_offset: self.old_offset,
-            head: self.head / rhs,
-            body: self.body / rhs,
-            right_upper_arm: self.right_upper_arm / rhs,
-            right_lower_arm: self.right_lower_arm / rhs,
-            right_upper_leg: self.right_upper_leg / rhs,
-            right_lower_leg: self.right_lower_leg / rhs,
-            left_upper_arm: self.left_upper_arm / rhs,
-            left_lower_arm: self.left_lower_arm / rhs,
-            left_upper_leg: self.left_upper_leg / rhs,
-            left_lower_leg: self.left_lower_leg / rhs,
+            head: self.head * rhs,
+            body: self.body * rhs,
+            right_upper_arm: self.right_upper_arm * rhs,
+            right_lower_arm: self.right_lower_arm * rhs,
+            right_upper_leg: self.right_upper_leg * rhs,
+            right_lower_leg: self.right_lower_leg * rhs,
+            left_upper_arm: self.left_upper_arm * rhs,
+            left_lower_arm: self.left_lower_arm * rhs,
+            left_upper_leg: self.left_upper_leg * rhs,
+            left_lower_leg: self.left_lower_leg * rhs,
         }
     }
 }
 
-pub const OPPOSITE_DEFAULT_POSE: Pose = Pose {
-    facing: false,
-    offset: [0.0, 0.0],
-    old_offset: [0.0, 0.0],
-    head: 30.0,
-    body: -5.0,
-    right_upper_arm: 0.0,
-    right_lower_arm: 120.0,
-    right_upper_leg: -40.0,
-    right_lower_leg: 40.0,
-    left_upper_arm: 30.0,
-    left_lower_arm: 90.0,
-    left_upper_leg: 60.0,
-    left_lower_leg: -50.0,
-};
+/// Difference of two angles in degrees going the shorter way around, in [-180, 180)
+pub fn shortest_arc(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+impl Pose {
+    /// The change from `self` to `other` like `other - self`, but every angle turns
+    /// the shorter way around so a joint never spins across ±180°.
+    pub fn arc_to(&self, other: &Pose) -> Pose {
+        Pose {
+            facing: self.facing,
+            offset: [
+                other.offset[0] - self.offset[0],
+                other.offset[1] - self.offset[1],
+            ],
+            old_offset: self.old_offset,
+            head: shortest_arc(self.head, other.head),
+            body: shortest_arc(self.body, other.body),
+            right_upper_arm: shortest_arc(self.right_upper_arm, other.right_upper_arm),
+            right_lower_arm: shortest_arc(self.right_lower_arm, other.right_lower_arm),
+            right_upper_leg: shortest_arc(self.right_upper_leg, other.right_upper_leg),
+            right_lower_leg: shortest_arc(self.right_lower_leg, other.right_lower_leg),
+            left_upper_arm: shortest_arc(self.left_upper_arm, other.left_upper_arm),
+            left_lower_arm: shortest_arc(self.left_lower_arm, other.left_lower_arm),
+            left_upper_leg: shortest_arc(self.left_upper_leg, other.left_upper_leg),
+            left_lower_leg: shortest_arc(self.left_lower_leg, other.left_lower_leg),
+        }
+    }
+}
+
+// Timing curve of one segment of an animation
+// Linear: constant speed
+// EaseIn: starts slow and speeds up, for strikes
+// EaseOut: starts fast and slows down, for recoveries
+// EaseInOut: slow at both ends
+// Cubic: like EaseInOut with a sharper middle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+    Cubic,
+}
+
+impl Easing {
+    /// Progress of the segment at `t` (0.0 to 1.0), 0.0 at the start and 1.0 at the end
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(2) / 2.0
+                }
+            }
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
 
 pub const IDLE_POSE1: Pose = Pose {
     facing: true,
@@ -180,6 +237,23 @@ pub const IDLE_POSE2: Pose = Pose {
     left_lower_leg: -50.0,
 };
 
+// both arms up in front of the head and the body
+pub const GUARD_POSE: Pose = Pose {
+    facing: true,
+    offset: [0.0, 0.0],
+    old_offset: [0.0, 0.0],
+    head: 10.0,
+    body: -10.0,
+    right_upper_arm: 30.0,
+    right_lower_arm: 140.0,
+    right_upper_leg: -40.0,
+    right_lower_leg: 40.0,
+    left_upper_arm: 60.0,
+    left_lower_arm: 120.0,
+    left_upper_leg: 60.0,
+    left_lower_leg: -60.0,
+};
+
 pub const WALKING_POSE1: Pose = Pose {
     facing: true,
     offset: [10.0, 0.0],
@@ -340,14 +414,6 @@ pub const ROLL_FORWARD_POSE6: Pose = Pose {
     left_lower_leg: -110.0,
 };
 
-// roll back movement is just playing the forward movement in reverse
-pub const ROLL_BACK_POSE1: Pose = ROLL_FORWARD_POSE6;
-pub const ROLL_BACK_POSE2: Pose = ROLL_FORWARD_POSE5;
-pub const ROLL_BACK_POSE3: Pose = ROLL_FORWARD_POSE4;
-pub const ROLL_BACK_POSE4: Pose = ROLL_FORWARD_POSE3;
-pub const ROLL_BACK_POSE5: Pose = ROLL_FORWARD_POSE2;
-pub const ROLL_BACK_POSE6: Pose = ROLL_FORWARD_POSE1;
-
 pub const JUMP_POSE1: Pose = Pose {
     facing: true,
     offset: [0.0, 50.0],
@@ -508,6 +574,40 @@ pub const PUNCH_POSE: Pose = Pose {
     left_lower_leg: -60.0,
 };
 
+// reaching for the opponent's collar
+pub const THROW_POSE1: Pose = Pose {
+    facing: true,
+    offset: [10.0, 0.0],
+    old_offset: [0.0, 0.0],
+    head: 10.0,
+    body: 10.0,
+    right_upper_arm: 80.0,
+    right_lower_arm: 20.0,
+    right_upper_leg: -40.0,
+    right_lower_leg: 40.0,
+    left_upper_arm: 90.0,
+    left_lower_arm: 10.0,
+    left_upper_leg: 60.0,
+    left_lower_leg: -60.0,
+};
+
+// lifting the opponent over the head
+pub const THROW_POSE2: Pose = Pose {
+    facing: true,
+    offset: [0.0, 0.0],
+    old_offset: [0.0, 0.0],
+    head: -20.0,
+    body: -20.0,
+    right_upper_arm: 160.0,
+    right_lower_arm: 10.0,
+    right_upper_leg: -20.0,
+    right_lower_leg: 20.0,
+    left_upper_arm: 170.0,
+    left_lower_arm: 0.0,
+    left_upper_leg: 40.0,
+    left_lower_leg: -40.0,
+};
+
 pub const STUN_POSE1: Pose = Pose {
     facing: true,
     offset: [10.0, 0.0],
@@ -538,4 +638,170 @@ pub const STUN_POSE2: Pose = Pose {
     left_lower_arm: 90.0,
     left_upper_leg: 30.0,
     left_lower_leg: -50.0,
-};
\ No newline at end of file
+};
+
+// lying on the back after a knockdown
+pub const KNOCKDOWN_POSE: Pose = Pose {
+    facing: true,
+    offset: [-80.0, -150.0],
+    old_offset: [0.0, 0.0],
+    head: 10.0,
+    body: 90.0,
+    right_upper_arm: 60.0,
+    right_lower_arm: 30.0,
+    right_upper_leg: 70.0,
+    right_lower_leg: -20.0,
+    left_upper_arm: 90.0,
+    left_lower_arm: 20.0,
+    left_upper_leg: 80.0,
+    left_lower_leg: -40.0,
+};
+/// Poses which can be edited with pose-editor.py.
+/// Each one is read from a file in the pose directory into the `PoseSet` of the fighters
+/// once it is loaded, the const above is the pose while the file is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoseName {
+    Idle,
+    BendDown,
+    JumpUp1,
+    JumpUp2,
+    JumpKick,
+    Kick1,
+    Kick2,
+    BackKick1,
+    BackKick2,
+    Punch,
+    // the roll is a sequence of 6 poses, the back roll plays it backward
+    RollForward1,
+    RollForward2,
+    RollForward3,
+    RollForward4,
+    RollForward5,
+    RollForward6,
+}
+
+const POSE_NAME_COUNT: usize = 16;
+
+impl PoseName {
+    pub const ALL: [PoseName; POSE_NAME_COUNT] = [
+        PoseName::Idle,
+        PoseName::BendDown,
+        PoseName::JumpUp1,
+        PoseName::JumpUp2,
+        PoseName::JumpKick,
+        PoseName::Kick1,
+        PoseName::Kick2,
+        PoseName::BackKick1,
+        PoseName::BackKick2,
+        PoseName::Punch,
+        PoseName::RollForward1,
+        PoseName::RollForward2,
+        PoseName::RollForward3,
+        PoseName::RollForward4,
+        PoseName::RollForward5,
+        PoseName::RollForward6,
+    ];
+
+    /// The file in the pose directory and the index of the pose in it
+    pub fn source(self) -> (&'static str, usize) {
+        match self {
+            PoseName::Idle => ("idle.json", 0),
+            PoseName::BendDown => ("bend_down.json", 0),
+            PoseName::JumpUp1 => ("jump_up1.json", 0),
+            PoseName::JumpUp2 => ("jump_up2.json", 0),
+            PoseName::JumpKick => ("jumping_kick.json", 0),
+            PoseName::Kick1 => ("kick1.json", 0),
+            PoseName::Kick2 => ("kick2.json", 0),
+            PoseName::BackKick1 => ("back_kick1.json", 0),
+            PoseName::BackKick2 => ("back_kick2.json", 0),
+            PoseName::Punch => ("punch.json", 0),
+            PoseName::RollForward1 => ("roll.json", 0),
+            PoseName::RollForward2 => ("roll.json", 1),
+            PoseName::RollForward3 => ("roll.json", 2),
+            PoseName::RollForward4 => ("roll.json", 3),
+            PoseName::RollForward5 => ("roll.json", 4),
+            PoseName::RollForward6 => ("roll.json", 5),
+        }
+    }
+
+    /// The pose used while the file is not loaded
+    pub fn fallback(self) -> Pose {
+        match self {
+            PoseName::Idle => IDLE_POSE1,
+            PoseName::BendDown => BEND_DOWN_POSE2,
+            PoseName::JumpUp1 => JUMP_POSE1,
+            PoseName::JumpUp2 => JUMP_POSE2,
+            PoseName::JumpKick => JUMP_KICK_POSE,
+            PoseName::Kick1 => KICK_POSE1,
+            PoseName::Kick2 => KICK_POSE2,
+            PoseName::BackKick1 => BACK_KICK_POSE1,
+            PoseName::BackKick2 => BACK_KICK_POSE2,
+            PoseName::Punch => PUNCH_POSE,
+            PoseName::RollForward1 => ROLL_FORWARD_POSE1,
+            PoseName::RollForward2 => ROLL_FORWARD_POSE2,
+            PoseName::RollForward3 => ROLL_FORWARD_POSE3,
+            PoseName::RollForward4 => ROLL_FORWARD_POSE4,
+            PoseName::RollForward5 => ROLL_FORWARD_POSE5,
+            PoseName::RollForward6 => ROLL_FORWARD_POSE6,
+        }
+    }
+
+}
+
+/// The poses of `PoseName` a fighter moves with.
+/// Each fighter carries its own copy, so the simulation never reads a pose from outside
+/// and the same inputs always give the same match. The resource holds the poses loaded
+/// from the pose directory, see pose_loader.rs, and is copied to the fighters.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PoseSet([Pose; POSE_NAME_COUNT]);
+
+impl Default for PoseSet {
+    // the fallback poses, the same on every platform
+    fn default() -> Self {
+        Self(PoseName::ALL.map(PoseName::fallback))
+    }
+}
+
+impl PoseSet {
+    pub fn get(&self, name: PoseName) -> Pose {
+        self.0[name as usize]
+    }
+
+    /// Replace the pose with the one read from the file
+    ///
+    /// # Arguments
+    /// * `name` - The pose to replace
+    /// * `pose` - The loaded pose, None to go back to the fallback
+    pub fn set(&mut self, name: PoseName, pose: Option<Pose>) {
+        self.0[name as usize] = pose.unwrap_or(name.fallback());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortest_arc_turns_across_180() {
+        assert_eq!(shortest_arc(170.0, -170.0), 20.0);
+        assert_eq!(shortest_arc(-170.0, 170.0), -20.0);
+        assert_eq!(shortest_arc(10.0, 50.0), 40.0);
+        assert_eq!(shortest_arc(30.0, 30.0), 0.0);
+    }
+
+    #[test]
+    fn every_easing_goes_from_0_to_1() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut, Easing::Cubic] {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+            // outside of the segment it stays at the ends
+            assert_eq!(easing.apply(-1.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(2.0), 1.0, "{:?}", easing);
+        }
+        assert_eq!(Easing::Linear.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Cubic.apply(0.5), 0.5);
+    }
+}
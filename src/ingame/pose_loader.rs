@@ -0,0 +1,194 @@
+// Loads the poses written by pose-editor.py from the pose directory.
+//
+// A file holds a single pose or an array of poses for the steps of a move.
+// When a file is loaded or modified (with the `hot_reload` feature) the poses of
+// `PoseName` read from it are replaced in the `PoseSet` resource and in the fighters,
+// so the next animation uses the new values.
+use super::{pose::*, Game};
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use serde::Deserialize;
+
+/// A pose as saved by pose-editor.py
+/// The editor does not save the offset, the one of the fallback pose is used then.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct PoseData {
+    facing: bool,
+    #[serde(default)]
+    offset: Option<[f32; 2]>,
+    head: f32,
+    body: f32,
+    right_upper_arm: f32,
+    right_lower_arm: f32,
+    right_upper_leg: f32,
+    right_lower_leg: f32,
+    left_upper_arm: f32,
+    left_lower_arm: f32,
+    left_upper_leg: f32,
+    left_lower_leg: f32,
+}
+
+impl PoseData {
+    fn to_pose(self, fallback: Pose) -> Pose {
+        Pose {
+            facing: self.facing,
+            offset: self.offset.unwrap_or(fallback.offset),
+            old_offset: [0.0, 0.0],
+            head: self.head,
+            body: self.body,
+            right_upper_arm: self.right_upper_arm,
+            right_lower_arm: self.right_lower_arm,
+            right_upper_leg: self.right_upper_leg,
+            right_lower_leg: self.right_lower_leg,
+            left_upper_arm: self.left_upper_arm,
+            left_lower_arm: self.left_lower_arm,
+            left_upper_leg: self.left_upper_leg,
+            left_lower_leg: self.left_lower_leg,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PoseFile {
+    Single(PoseData),
+    Sequence(Vec<PoseData>),
+}
+
+/// Poses of one file in the order they are played
+#[derive(Asset, TypePath, Debug)]
+pub struct PoseSequence(Vec<PoseData>);
+
+#[derive(Default)]
+struct PoseLoader;
+
+impl AssetLoader for PoseLoader {
+    type Asset = PoseSequence;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: PoseFile = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(match file {
+            PoseFile::Single(pose) => PoseSequence(vec![pose]),
+            PoseFile::Sequence(poses) => PoseSequence(poses),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+// handles of the pose files, kept so they are not unloaded
+#[derive(Resource)]
+struct PoseFiles(HashMap<&'static str, Handle<PoseSequence>>);
+
+fn load_pose_files(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut files = HashMap::new();
+    for name in PoseName::ALL {
+        let (file, _) = name.source();
+        files
+            .entry(file)
+            .or_insert_with(|| asset_server.load(format!("pose://{}", file)));
+    }
+    commands.insert_resource(PoseFiles(files));
+}
+
+/// Replace the poses when their file is loaded, modified or removed
+fn update_loaded_poses(
+    mut events: EventReader<AssetEvent<PoseSequence>>,
+    sequences: Res<Assets<PoseSequence>>,
+    files: Res<PoseFiles>,
+    mut poses: ResMut<PoseSet>,
+    mut game: Option<ResMut<Game>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id }
+        | AssetEvent::Modified { id }
+        | AssetEvent::Removed { id }) = event
+        else {
+            continue;
+        };
+        let Some((file, _)) = files.0.iter().find(|(_, handle)| handle.id() == *id) else {
+            continue;
+        };
+        let sequence = sequences.get(*id);
+        for name in PoseName::ALL {
+            let (source, index) = name.source();
+            if source != *file {
+                continue;
+            }
+            let pose = sequence
+                .and_then(|sequence| sequence.0.get(index))
+                .map(|data| data.to_pose(name.fallback()));
+            if pose.is_none() && sequence.is_some() {
+                warn!("{} has no pose at index {}", file, index);
+            }
+            poses.set(name, pose);
+        }
+        info!("poses of {} updated", file);
+        if let Some(game) = game.as_mut() {
+            for player in game.players.iter_mut() {
+                player.poses = *poses;
+            }
+        }
+    }
+}
+
+pub struct PoseLoaderPlugin;
+
+impl Plugin for PoseLoaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<PoseSequence>()
+            .init_asset_loader::<PoseLoader>()
+            .add_systems(Startup, load_pose_files)
+            .add_systems(Update, update_loaded_poses);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every editable pose is read from a file of the pose directory which the loader
+    // can parse and which has a pose at the index `PoseName::source` points at
+    #[test]
+    fn pose_files_hold_every_pose_name() {
+        for name in PoseName::ALL {
+            let (file, index) = name.source();
+            let path = format!("{}/pose/{}", env!("CARGO_MANIFEST_DIR"), file);
+            let bytes = std::fs::read(&path).unwrap();
+            let sequence = match serde_json::from_slice::<PoseFile>(&bytes).unwrap() {
+                PoseFile::Single(pose) => vec![pose],
+                PoseFile::Sequence(poses) => poses,
+            };
+            assert!(index < sequence.len(), "{} has no pose at index {}", file, index);
+        }
+    }
+
+    #[test]
+    fn pose_set_goes_back_to_the_fallback() {
+        let mut poses = PoseSet::default();
+        let moved = Pose {
+            head: 90.0,
+            ..PoseName::Idle.fallback()
+        };
+        poses.set(PoseName::Idle, Some(moved));
+        assert_eq!(poses.get(PoseName::Idle), moved);
+        assert_eq!(poses.get(PoseName::Punch), PoseName::Punch.fallback());
+        poses.set(PoseName::Idle, None);
+        assert_eq!(poses.get(PoseName::Idle), PoseName::Idle.fallback());
+    }
+}
@@ -0,0 +1,182 @@
+// Projectiles.
+//
+// The ranged attack launches the projectile of the character, described in the character
+// file with its speed, hitbox, damage and number of hits (see `CharacterProfile::projectile`),
+// so a character can throw a slow, an arcing or a multi-hit projectile instead of the
+// fire arrow. Projectiles of the two fighters cancel each other when they touch, and a
+// back kick on its active frames sends a projectile back toward the one who launched it.
+use super::{frame_data::MoveKind, sim::*};
+use bevy::math::Vec2;
+use serde::Deserialize;
+
+// half size of the body of a projectile, which touches other projectiles and kicks
+const BODY_HALF_SIZE: Vec2 = Vec2::new(60.0, 30.0);
+// the EX version flies faster and hits once more
+const EX_SPEED_RATIO: f32 = 1.5;
+
+/// Flight and damage of the projectile of a character, the fire arrow by default
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProjectileDef {
+    // px per frame toward the opponent
+    pub speed: f32,
+    // px per frame upward at the launch and how much of it is lost on every frame,
+    // an arcing projectile vanishes when it falls back under the floor
+    pub rise: f32,
+    pub gravity: f32,
+    // half of the width and height of the box around the projectile which the
+    // position of the opponent has to be in to get hit
+    pub half_size: [f32; 2],
+    pub damage: u32,
+    // hits before the projectile vanishes and the frames between two of them
+    pub hits: u8,
+    pub hit_interval: u8,
+}
+
+impl ProjectileDef {
+    /// The EX version of the projectile, launched by spending a stock of energy
+    pub fn ex(&self) -> Self {
+        Self {
+            speed: self.speed * EX_SPEED_RATIO,
+            hits: self.hits + 1,
+            ..*self
+        }
+    }
+}
+
+impl Default for ProjectileDef {
+    fn default() -> Self {
+        Self {
+            speed: FIRE_SPEED,
+            rise: 0.0,
+            gravity: 0.0,
+            half_size: [FIRE_HIT_RANGE; 2],
+            damage: FIRE_DAMAGE,
+            hits: 1,
+            hit_interval: 0,
+        }
+    }
+}
+
+/// A projectile in flight
+#[derive(Debug, Clone, Copy)]
+pub struct Projectile {
+    // given by `Match` at the launch, the view follows the projectile by it
+    pub id: u32,
+    // the fighter it flies for, it changes when the projectile is reflected
+    pub owner: usize,
+    // the fighter who launched it, whose ranged attack is charging meanwhile
+    pub launcher: usize,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub def: ProjectileDef,
+    pub hits_left: u8,
+    // frames until it can hit again
+    pub cooldown: u8,
+}
+
+impl Projectile {
+    /// The projectile of `def` launched by the fighter `owner` at `position`,
+    /// flying toward the right when `facing` is true
+    pub fn new(owner: usize, position: Vec2, facing: bool, def: ProjectileDef) -> Self {
+        let sign = if facing { 1.0 } else { -1.0 };
+        Self {
+            id: 0,
+            owner,
+            launcher: owner,
+            position,
+            velocity: Vec2::new(sign * def.speed, def.rise),
+            def,
+            hits_left: def.hits,
+            cooldown: 0,
+        }
+    }
+    /// true while it flies toward the right
+    pub fn facing(&self) -> bool {
+        self.velocity.x > 0.0
+    }
+    /// Moves the projectile by one frame
+    pub fn step(&mut self) {
+        self.position += self.velocity;
+        self.velocity.y -= self.def.gravity;
+        self.cooldown = self.cooldown.saturating_sub(1);
+    }
+    /// true when the projectile is used up, hit a wall of the stage or fell under the floor
+    ///
+    /// # Arguments
+    /// * `stage_x` - X position of the ground, see `scroll_stage`
+    /// * `arena` - The arena, gives the walls and the floor
+    pub fn is_gone(&self, stage_x: f32, arena: &Arena) -> bool {
+        self.hits_left == 0
+            || (self.position.x - stage_x).abs() > arena.wall_x
+            || (self.def.gravity > 0.0 && self.position.y < arena.ground_y)
+    }
+    /// true if the projectile can hit the fighter at `target` on this frame
+    pub fn reaches(&self, target: Vec2) -> bool {
+        let half_size = Vec2::from(self.def.half_size);
+        self.hits_left > 0 && self.cooldown == 0 && (target - self.position).abs().cmplt(half_size).all()
+    }
+    /// Counts a hit, the projectile vanishes after its last one
+    pub fn hit(&mut self) {
+        self.hits_left = self.hits_left.saturating_sub(1);
+        self.cooldown = self.def.hit_interval;
+    }
+    // the body of the projectile as (min, max)
+    fn body(&self) -> (Vec2, Vec2) {
+        (self.position - BODY_HALF_SIZE, self.position + BODY_HALF_SIZE)
+    }
+    /// Sends the projectile back if the back kick of the fighter `id` touches it.
+    /// Only a projectile of the opponent is reflected, and it flies for the kicker afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The fighter who may be kicking
+    /// * `player` - The fighter
+    /// * `position` - Position of the fighter
+    /// * `scale` - Scale of the arena
+    ///
+    /// Returns true when the projectile was reflected.
+    pub fn reflect(&mut self, id: usize, player: &Player, position: Vec2, scale: f32) -> bool {
+        if self.owner == id || self.hits_left == 0 {
+            return false;
+        }
+        let Some(attack) = player
+            .attack
+            .filter(|attack| attack.kind == MoveKind::BackKick && attack.is_active())
+        else {
+            return false;
+        };
+        let (min, max) = attack.hitbox(position, player.pose.facing, scale);
+        let (body_min, body_max) = self.body();
+        if min.cmpgt(body_max).any() || max.cmplt(body_min).any() {
+            return false;
+        }
+        self.owner = id;
+        self.velocity.x = -self.velocity.x;
+        self.cooldown = 0;
+        true
+    }
+}
+
+/// Cancels two projectiles of different fighters which touch each other.
+/// Each one loses a hit, so a multi-hit projectile can go through a weaker one.
+///
+/// Returns true when they clashed.
+pub fn clash(first: &mut Projectile, second: &mut Projectile) -> bool {
+    if first.owner == second.owner
+        || first.hits_left == 0
+        || second.hits_left == 0
+        || first.cooldown > 0
+        || second.cooldown > 0
+    {
+        return false;
+    }
+    let (first_min, first_max) = first.body();
+    let (second_min, second_max) = second.body();
+    if first_min.cmpgt(second_max).any() || first_max.cmplt(second_min).any() {
+        return false;
+    }
+    first.hit();
+    second.hit();
+    true
+}
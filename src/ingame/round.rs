@@ -0,0 +1,156 @@
+// Flow of a round, from the "ROUND n" announce to the curtain before the next round.
+//
+// `RoundFlow` only knows in which phase the round is and how long each phase lasts.
+// Systems request a phase with `RoundFlow::go`, `advance_round_flow` applies it once per
+// logic step and sends `RoundPhaseChanged`, and the hooks in mod.rs react to that event.
+// A new phase is added by giving it a place in `RoundPhase::next` and, if it is timed,
+// an entry in `ROUND_PHASE_FRAMES`.
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// Phases of a round in the order they are played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RoundPhase {
+    // "ROUND n" is displayed
+    #[default]
+    RoundIntro,
+    // "READY?" is displayed
+    Ready,
+    // "FIGHT!" is displayed
+    Fight,
+    // the status bar fades out
+    FightFade,
+    // players can move
+    Fighting,
+    // a player has no health left, winner and loser poses are played
+    Ko,
+    // the timer reached zero
+    TimeUp,
+    // players stay in their final pose
+    WinnerPose,
+    // the winner of the round is displayed
+    Announce,
+    // the curtain closes
+    CurtainOut,
+    // the curtain opens on the next round
+    CurtainIn,
+}
+
+impl RoundPhase {
+    /// The phase played when this one is over
+    pub fn next(self) -> Self {
+        match self {
+            RoundPhase::RoundIntro => RoundPhase::Ready,
+            RoundPhase::Ready => RoundPhase::Fight,
+            RoundPhase::Fight => RoundPhase::FightFade,
+            RoundPhase::FightFade => RoundPhase::Fighting,
+            RoundPhase::Fighting => RoundPhase::TimeUp,
+            RoundPhase::Ko | RoundPhase::TimeUp => RoundPhase::WinnerPose,
+            RoundPhase::WinnerPose => RoundPhase::Announce,
+            RoundPhase::Announce => RoundPhase::CurtainOut,
+            RoundPhase::CurtainOut => RoundPhase::CurtainIn,
+            RoundPhase::CurtainIn => RoundPhase::RoundIntro,
+        }
+    }
+}
+
+// length of the timed phases in logic frames
+// phases which are not listed here last until another system calls `RoundFlow::go`
+const ROUND_PHASE_FRAMES: [(RoundPhase, u32); 9] = [
+    (RoundPhase::RoundIntro, 60),
+    (RoundPhase::Ready, 90),
+    (RoundPhase::Fight, 30),
+    (RoundPhase::FightFade, 48),
+    (RoundPhase::TimeUp, 1),
+    (RoundPhase::WinnerPose, 60),
+    (RoundPhase::Announce, 60),
+    (RoundPhase::CurtainOut, 60),
+    (RoundPhase::CurtainIn, 60),
+];
+
+/// Sent on the logic step a phase begins.
+/// `from` is None for the first phase of the match.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoundPhaseChanged {
+    pub from: Option<RoundPhase>,
+    pub to: RoundPhase,
+}
+
+#[derive(Resource)]
+pub struct RoundFlow {
+    pub phase: RoundPhase,
+    // logic frames spent in the current phase
+    pub count: u32,
+    pub durations: HashMap<RoundPhase, u32>,
+    // phase requested by `go`, applied on the next logic step
+    next: Option<RoundPhase>,
+    started: bool,
+}
+
+impl Default for RoundFlow {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut durations: HashMap<RoundPhase, u32> = ROUND_PHASE_FRAMES.into_iter().collect();
+        // there are no winner and loser sprites on the web, KO ends right away
+        #[cfg(target_arch = "wasm32")]
+        durations.insert(RoundPhase::Ko, 1);
+        Self {
+            phase: RoundPhase::default(),
+            count: 0,
+            durations,
+            next: None,
+            started: false,
+        }
+    }
+}
+
+impl RoundFlow {
+    /// Start the match again from the intro of the first round
+    pub fn restart(&mut self) {
+        self.phase = RoundPhase::RoundIntro;
+        self.count = 0;
+        self.next = Some(RoundPhase::RoundIntro);
+        self.started = false;
+    }
+
+    /// Request a phase, it begins on the next logic step
+    ///
+    /// # Arguments
+    /// * `phase` - The phase to move to
+    pub fn go(&mut self, phase: RoundPhase) {
+        self.next = Some(phase);
+    }
+
+    /// Progress of the current timed phase from 0.0 to 1.0
+    /// Untimed phases always return 0.0
+    pub fn progress(&self) -> f32 {
+        match self.durations.get(&self.phase) {
+            Some(&duration) if duration > 0 => (self.count as f32 / duration as f32).min(1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Move the round to the requested phase or to the next one when the current phase is over
+pub fn advance_round_flow(
+    mut flow: ResMut<RoundFlow>,
+    mut phase_changed: EventWriter<RoundPhaseChanged>,
+) {
+    let to = if let Some(phase) = flow.next.take() {
+        phase
+    } else if let Some(&duration) = flow.durations.get(&flow.phase) {
+        flow.count += 1;
+        if flow.count < duration {
+            return;
+        }
+        flow.phase.next()
+    } else {
+        flow.count += 1;
+        return;
+    };
+    let from = if flow.started { Some(flow.phase) } else { None };
+    debug!("round phase {:?} -> {:?}", from, to);
+    flow.started = true;
+    flow.phase = to;
+    flow.count = 0;
+    phase_changed.write(RoundPhaseChanged { from, to });
+}
@@ -0,0 +1,265 @@
+// Rules of a match.
+//
+// How many rounds are played, how long a round lasts and what the fighters start with
+// are chosen on the settings screen and kept in the `MatchRules` resource. `Match` and the
+// Bevy systems in mod.rs read the same rules, and `is_over` decides from the winners of the
+// rounds played so far whether another round is needed. When the match would end in a
+// draw, the optional sudden death adds short rounds in which the fighters start with
+// little health until one of them wins.
+// In a team battle each side lines up the whole roster, and `team_positions` tells from
+// the same winners which member of each team fights the next round.
+// A `Handicap` is chosen for each player on the confirm screen, so that a child can play
+// against a parent. Unlike the rules it only changes one fighter, see `Player::handicap`.
+use super::sim::{total_winner, FPS};
+use crate::character_def::{ENERGY_STOCK, FIRE_CHARGE_MAX};
+use bevy::prelude::Resource;
+
+// a first-to-N match full of draws still ends after this many rounds
+pub const MAX_ROUNDS: u8 = 9;
+// voices of the round announce, see sounds/round<n>.ogg
+pub const ROUND_VOICES: u8 = 3;
+
+/// How the winner of the match is decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundFormat {
+    // a fixed number of rounds, the one who won more of them wins
+    Rounds(u8),
+    // the first to win this many rounds wins
+    FirstTo(u8),
+    // each side fights with the whole roster in its chosen order, the winner of a round
+    // stays in and the side which loses all its fighters loses
+    Team,
+}
+
+// the formats in the order of the settings screen
+pub const ROUND_FORMATS: [RoundFormat; 6] = [
+    RoundFormat::Rounds(1),
+    RoundFormat::Rounds(3),
+    RoundFormat::Rounds(5),
+    RoundFormat::FirstTo(2),
+    RoundFormat::FirstTo(3),
+    RoundFormat::Team,
+];
+
+// length of a round in seconds in the order of the settings screen, None has no time limit
+pub const ROUND_TIMES: [Option<u16>; 4] = [Some(30), Some(60), Some(99), None];
+
+// seconds nothing stuns a fighter after a dizzy, in the order of the settings screen
+pub const INVINCIBLE_TIMES: [f32; 4] = [0.0, 1.0, 2.0, 4.0];
+
+// length of a sudden death round in seconds
+pub const SUDDEN_DEATH_SECONDS: u16 = 20;
+// part of the maximum health the fighters start a sudden death round with
+pub const SUDDEN_DEATH_HEALTH: f32 = 0.1;
+// the match ends in a draw after this many sudden death rounds ended in a draw
+pub const MAX_SUDDEN_DEATHS: u8 = 3;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct MatchRules {
+    pub format: RoundFormat,
+    // seconds, None for no time limit
+    pub round_time: Option<u16>,
+    // stocks of energy each fighter has at the beginning of the match
+    pub start_stocks: u16,
+    // part of the fire charge filled at the beginning of each round
+    pub start_fire_charge: f32,
+    // every damage is multiplied by this
+    pub damage_ratio: f32,
+    // the remaining rounds of a fixed number of rounds are not played once the match is decided
+    pub skip_decided: bool,
+    // seconds after a dizzy in which hits do damage but neither stun nor throw the fighter
+    pub invincible_time: f32,
+    // a match which would end in a draw goes on with sudden death rounds
+    pub sudden_death: bool,
+}
+
+impl Default for MatchRules {
+    // three rounds of 60 seconds, all of them played
+    fn default() -> Self {
+        Self {
+            format: RoundFormat::Rounds(3),
+            round_time: Some(60),
+            start_stocks: 0,
+            start_fire_charge: 1.0,
+            damage_ratio: 1.0,
+            skip_decided: false,
+            invincible_time: 2.0,
+            sudden_death: false,
+        }
+    }
+}
+
+impl MatchRules {
+    /// Length of a round in logic frames, None for no time limit
+    ///
+    /// # Arguments
+    ///
+    /// * `sudden_death` - true for a sudden death round, which always has the short time limit
+    pub fn round_frames(&self, sudden_death: bool) -> Option<u32> {
+        let round_time = if sudden_death { Some(SUDDEN_DEATH_SECONDS) } else { self.round_time };
+        round_time.map(|seconds| seconds as u32 * FPS as u32)
+    }
+
+    pub fn start_energy(&self) -> u16 {
+        self.start_stocks * ENERGY_STOCK
+    }
+
+    pub fn start_fire_charge(&self) -> u16 {
+        (self.start_fire_charge.clamp(0.0, 1.0) * FIRE_CHARGE_MAX as f32).round() as u16
+    }
+
+    /// Length of the invincibility after a dizzy in logic frames
+    pub fn invincible_frames(&self) -> u16 {
+        (self.invincible_time * FPS).round() as u16
+    }
+
+    /// true when no more round has to be played
+    ///
+    /// # Arguments
+    ///
+    /// * `winners` - Winner of each round played so far, 0 means draw
+    /// * `team_sizes` - Number of fighters on each side, only used by a team battle
+    pub fn is_over(&self, winners: &[u8], team_sizes: [usize; 2]) -> bool {
+        let wins = [1, 2].map(|id| winners.iter().filter(|winner| **winner == id).count() as u8);
+        let played = winners.len() as u8;
+        match self.format {
+            RoundFormat::Rounds(rounds) => {
+                if played >= rounds {
+                    return true;
+                }
+                // the one behind cannot catch up even by winning all the remaining rounds
+                let remaining = rounds - played;
+                self.skip_decided && wins[0].abs_diff(wins[1]) > remaining
+            }
+            RoundFormat::FirstTo(wins_needed) => wins.contains(&wins_needed) || played >= MAX_ROUNDS,
+            RoundFormat::Team => team_positions(winners)
+                .iter()
+                .zip(team_sizes)
+                .any(|(position, size)| *position >= size),
+        }
+    }
+
+    pub fn is_team_battle(&self) -> bool {
+        self.format == RoundFormat::Team
+    }
+
+    /// true when the match is over without a winner and a sudden death round is played next
+    ///
+    /// # Arguments
+    ///
+    /// * `winners` - Winner of each round played so far, sudden death rounds included
+    /// * `sudden_deaths` - Number of sudden death rounds played so far
+    /// * `team_sizes` - Number of fighters on each side, only used by a team battle
+    pub fn needs_sudden_death(&self, winners: &[u8], sudden_deaths: u8, team_sizes: [usize; 2]) -> bool {
+        self.sudden_death
+            && sudden_deaths < MAX_SUDDEN_DEATHS
+            && self.is_over(winners, team_sizes)
+            && total_winner(winners) == 0
+    }
+}
+
+/// Member of each team who fights the current round of a team battle, as an index in the team.
+/// A side sends in its next fighter after each round it did not win, so a draw takes out both.
+/// The index reaches the size of the team once all its fighters are out.
+///
+/// # Arguments
+///
+/// * `winners` - Winner of each round played so far, 0 means draw
+pub fn team_positions(winners: &[u8]) -> [usize; 2] {
+    [1, 2].map(|id| winners.iter().filter(|winner| **winner != id).count())
+}
+
+// multipliers of a handicap in the order of the confirm screen
+pub const HANDICAP_RATIOS: [f32; 6] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// Handicap of one fighter, the default one changes nothing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handicap {
+    // maximum health is multiplied by this
+    pub health: f32,
+    // damage dealt by the fighter is multiplied by this
+    pub damage: f32,
+    // energy gained by the fighter is multiplied by this
+    pub meter: f32,
+    // the fighter guards by itself while standing, walking or bending down
+    pub auto_block: bool,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Self {
+            health: 1.0,
+            damage: 1.0,
+            meter: 1.0,
+            auto_block: false,
+        }
+    }
+}
+
+impl Handicap {
+    /// true when the handicap changes nothing
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `damage` dealt by the fighter with this handicap
+    pub fn damage_of(&self, damage: u32) -> u32 {
+        (damage as f32 * self.damage).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(format: RoundFormat) -> MatchRules {
+        MatchRules {
+            format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rounds_are_all_played() {
+        let rules = rules(RoundFormat::Rounds(3));
+        assert!(!rules.is_over(&[1, 1], [1, 1]));
+        assert!(rules.is_over(&[1, 1, 2], [1, 1]));
+        let rules = MatchRules {
+            skip_decided: true,
+            ..rules
+        };
+        assert!(!rules.is_over(&[1], [1, 1]));
+        assert!(rules.is_over(&[1, 1], [1, 1]));
+    }
+
+    #[test]
+    fn first_to_ends_on_the_last_win() {
+        let rules = rules(RoundFormat::FirstTo(2));
+        assert!(!rules.is_over(&[1, 2, 0], [1, 1]));
+        assert!(rules.is_over(&[1, 2, 2], [1, 1]));
+        assert!(rules.is_over(&[0; MAX_ROUNDS as usize], [1, 1]));
+    }
+
+    #[test]
+    fn team_battle_ends_when_a_team_is_out() {
+        let rules = rules(RoundFormat::Team);
+        assert_eq!(team_positions(&[1, 1, 0]), [1, 3]);
+        assert!(!rules.is_over(&[1, 1], [3, 3]));
+        assert!(rules.is_over(&[1, 1, 1], [3, 3]));
+        // the sizes of the teams decide, not the roster
+        assert!(rules.is_over(&[1, 1], [3, 2]));
+        assert!(!rules.is_over(&[1, 1, 1], [3, 4]));
+    }
+
+    #[test]
+    fn sudden_death_only_after_a_draw() {
+        let rules = MatchRules {
+            sudden_death: true,
+            ..rules(RoundFormat::Rounds(2))
+        };
+        assert!(rules.needs_sudden_death(&[1, 2], 0, [1, 1]));
+        assert!(!rules.needs_sudden_death(&[1, 1], 0, [1, 1]));
+        assert!(!rules.needs_sudden_death(&[1], 0, [1, 1]));
+        assert!(!rules.needs_sudden_death(&[1, 2, 0, 0, 0], MAX_SUDDEN_DEATHS, [1, 1]));
+    }
+}
@@ -0,0 +1,3000 @@
+// Headless combat simulation.
+//
+// Everything in this module is plain Rust: no Sprite, no AssetServer, no meshes and no ECS
+// types, and the characters are the profiles the match was given, not the global roster.
+// `Match` owns the whole fight and advances it one logic frame at a time from the inputs
+// of both fighters. The Bevy systems in player.rs step the `Match` of the `Game` resource
+// and only translate its state and its `SimEvent`s into sprites, transforms and sounds,
+// so the same fights can be run headless (bots, tests, replays).
+use super::{fighter_state::Action, frame_data::*, input_buffer::*, pose::*, projectile::*, rules::{team_positions, Handicap, MatchRules, SUDDEN_DEATH_HEALTH}, survival, stage::stage, throw::*};
+use crate::{character_def::*, layout::{BodyLayout, LayoutProfile, DESKTOP_BODY}};
+use bevy::math::Vec2;
+use std::{
+    fmt::Debug,
+    ops::{BitAndAssign, BitOr, BitOrAssign, Not},
+    sync::Arc,
+};
+
+pub const PIXELS_PER_METER: f32 = 100.0;
+pub const GRAVITY_ACCEL: f32 = 9.80665;
+pub const FPS: f32 = 60.0;
+
+// fire arrow moves 20px per frame, other projectiles are given by the character
+pub const FIRE_SPEED: f32 = 20.0;
+// half size of the box around a fire arrow that hits the opponent
+pub const FIRE_HIT_RANGE: f32 = 150.0;
+pub const FIRE_DAMAGE: u32 = 50;
+// souls of 魂吸収 move 10px per frame
+pub const SOUL_SPEED: f32 = 10.0;
+// dizzy meter, every hit fills it and the fighter is dizzy when it is full
+pub const DIZZY_MAX: u16 = 1000;
+// meter filled per point of damage, multiplied by the part which was hit
+const DIZZY_PER_DAMAGE: f32 = 4.0;
+// a hit on the head shakes the fighter twice as much as one on the body
+const DIZZY_PARTS: [f32; 4] = [
+    2.0, // head
+    1.0, // body
+    0.7, // arm
+    0.7, // leg
+];
+// the meter goes down by this every frame the fighter is not being hit
+const DIZZY_RECOVERY: u16 = 2;
+// a full meter empties in this many frames of dizzy
+const DIZZY_FRAMES: u16 = 125;
+// frames of each sway of a dizzy fighter
+const DIZZY_SWAY_FRAMES: u8 = 20;
+// guard meter, a blocked hit drains GUARD_DRAIN per point of damage it would have done
+pub const GUARD_MAX: u16 = 300;
+const GUARD_DRAIN: u16 = 3;
+// a blocked punch or kick lets 1/10 of the damage through, a blocked arrow 1/4
+const MELEE_CHIP_DIVISOR: u32 = 10;
+const FIRE_CHIP_DIVISOR: u32 = 4;
+// the stagger of a guard break is longer than that of a hit
+const GUARD_BREAK_FRAMES: u8 = 40;
+// damage of the n-th hit of a combo, the last value is kept for longer combos
+const COMBO_SCALING: [f32; 6] = [1.0, 0.8, 0.7, 0.6, 0.5, 0.4];
+// fighters closer than this push each other away, scaled with the arena
+pub const PUSHBOX_WIDTH: f32 = 80.0;
+// fighters closer than this to the side of the screen scroll the stage
+const EDGE_MARGIN: f32 = 100.0;
+// a knocked down fighter flying into a wall faster than this bounces off it,
+// keeping this much of its speed and thrown up a little
+const WALL_BOUNCE_SPEED: f32 = 6.0;
+const WALL_BOUNCE_RATIO: f32 = 0.6;
+const WALL_BOUNCE_LIFT: f32 = 8.0;
+// impact of a fire arrow, the owner is far away and does not freeze
+const FIRE_HITSTOP: u8 = 6;
+const FIRE_KNOCKBACK: Vec2 = Vec2::new(6.0, 0.0);
+// a blocked hit pushes the victim this much of the knockback
+const BLOCK_KNOCKBACK_RATIO: f32 = 0.5;
+// the knockback slows down by this ratio every frame and is pulled down by the gravity
+const KNOCKBACK_FRICTION: f32 = 0.8;
+const KNOCKBACK_GRAVITY: f32 = 1.0;
+// frames of the fall to the floor after a knockdown
+const KNOCKDOWN_FALL_FRAMES: u8 = 12;
+// frames a knocked down fighter lies on the floor unless it gets up early
+const KNOCKDOWN_FRAMES: u8 = 40;
+// frames of getting up, and of getting up early with jump
+const GET_UP_FRAMES: u8 = 20;
+const QUICK_RISE_FRAMES: u8 = 8;
+// frames after getting up in which nothing can hit or throw the fighter
+pub const WAKE_UP_FRAMES: u16 = 30;
+// damage and extra hitstop of the EX version of a move
+const EX_DAMAGE_RATIO: f32 = 1.5;
+const EX_HITSTOP: u8 = 4;
+// health recovered by the 気功 special and its startup, active and recovery frames
+const HEAL_AMOUNT: u32 = 80;
+const HEAL_FRAMES: (u8, u8, u8) = (20, 10, 20);
+
+/// Which sprite sheet of the character is displayed.
+/// The simulation only decides the sheet and the frame, the view picks the actual image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpriteKind {
+    #[default]
+    Idle,
+    Walk,
+    Jump,
+    Kick,
+    JumpKick,
+    Punch,
+    BackKick,
+    BendDown,
+    Roll,
+    Attacked,
+    #[cfg(not(target_arch = "wasm32"))]
+    Victory,
+    #[cfg(not(target_arch = "wasm32"))]
+    Defeated,
+}
+
+impl SpriteKind {
+    /// default number of frames in the sprite sheet, see `CharacterProfile::frames`
+    pub fn frames(&self) -> usize {
+        match self {
+            SpriteKind::Idle => FRAMES_IDLE,
+            SpriteKind::Walk => FRAMES_WALK,
+            SpriteKind::Jump => FRAMES_JUMP,
+            SpriteKind::Kick => FRAMES_KICK,
+            // jump kick is a single picture
+            SpriteKind::JumpKick => 1,
+            SpriteKind::Punch => FRAMES_PUNCH,
+            SpriteKind::BackKick => FRAMES_BACK_KICK,
+            SpriteKind::BendDown => FRAMES_BEND_DOWN,
+            SpriteKind::Roll => FRAMES_ROLL,
+            SpriteKind::Attacked => FRAMES_ATTACKED,
+            #[cfg(not(target_arch = "wasm32"))]
+            SpriteKind::Victory => FRAMES_VICTORY,
+            #[cfg(not(target_arch = "wasm32"))]
+            SpriteKind::Defeated => FRAMES_DEFEATED,
+        }
+    }
+}
+
+/// Represents the current state of a player using bit flags.
+/// Multiple states can be active simultaneously by combining flags with bitwise OR.
+/// The flags of the main action only change through `Player::enter`, see fighter_state.rs.
+///
+/// | State           | Bit Pattern        | Description                         |
+/// |-----------------|--------------------|-------------------------------------|
+/// | IDLE            | 0b0000000000000000 | Default state, no action            |
+/// | WALKING         | 0b0000000000000001 | Player is moving horizontally       |
+/// | JUMP_UP         | 0b0000000000000010 | Player is in first jump             |
+/// | SKILL           | 0b0000000000000100 | Player is performing skill attack   |
+/// | KICKING         | 0b0000000000001000 | Player is performing kick           |
+/// | PUNCHING        | 0b0000000000010000 | Player is performing punch          |
+/// | FIRE_EMISSION   | 0b0000000000100000 | Player is performing ranged attack  |
+/// | BACK_KICKING    | 0b0000000001000000 | Player is performing back kick      |
+/// | COOLDOWN        | 0b0000000010000000 | Player is in cooldown state         |
+/// | DIRECTION       | 0b0000000100000000 | Player is moving right              |
+/// | JUMP_FORWARD    | 0b0000001000000000 | Player is jumping forward           |
+/// | JUMP_BACKWARD   | 0b0000010000000000 | Player is jumping backward          |
+/// | BEND_DOWN       | 0b0000100000000000 | Player is bending down              |
+/// | ROLL_BACK       | 0b0001000000000000 | Player is rolling back              |
+/// | ROLL_FORWARD    | 0b0010000000000000 | Player is rolling forward           |
+/// | ATTACK_DISABLED | 0b0100000000000000 | Player is in attack cooldown state  |
+/// | STUN            | 0b1000000000000000 | Player is stunned                   |
+/// | GUARD           | 0b10000000000000000 | Player is guarding                 |
+/// | THROWING        | 0b100000000000000000 | Player is throwing the opponent  |
+/// | THROWN          | 0b1000000000000000000 | Player is being thrown          |
+/// | KNOCKDOWN       | 0b10000000000000000000 | Player is lying on the floor   |
+/// | SPECIAL         | 0b100000000000000000000 | Player is performing special  |
+/// | EX              | 0b1000000000000000000000 | The move is the EX version   |
+/// | DIZZY           | 0b10000000000000000000000 | Player is dizzy             |
+#[derive(PartialEq, Eq, Copy, Clone, Default)]
+pub struct PlayerState(u32);
+
+impl BitOr for PlayerState {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for PlayerState {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAndAssign for PlayerState {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl Not for PlayerState {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl Debug for PlayerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let states = [
+            (0x0000, "IDLE"),
+            (0x0001, "WALKING"),
+            (0x0002, "JUMP_UP"),
+            (0x0004, "SKILL"),
+            (0x0008, "KICKING"),
+            (0x0010, "PUNCHING"),
+            (0x0020, "RANGED_ATTACK"),
+            (0x0040, "BACK_KICKING"),
+            (0x0080, "COOLDOWN"),
+            (0x0100, "DIRECTION"),
+            (0x0200, "JUMP_FORWARD"),
+            (0x0400, "JUMP_BACKWARD"),
+            (0x0800, "BEND_DOWN"),
+            (0x1000, "ROLL_BACK"),
+            (0x2000, "ROLL_FORWARD"),
+            (0x4000, "ATTACK_DISABLED"),
+            (0x8000, "STUN"),
+            (0x10000, "GUARD"),
+            (0x20000, "THROWING"),
+            (0x40000, "THROWN"),
+            (0x80000, "KNOCKDOWN"),
+            (0x100000, "SPECIAL"),
+            (0x200000, "EX"),
+            (0x400000, "DIZZY"),
+        ];
+
+        let active_states: Vec<&str> = states.iter()
+            .filter(|(flag, _)| flag > &0 && (self.0 & *flag as u32) != 0)
+            .map(|(_, name)| *name)
+            .collect();
+
+        if f.alternate() {
+            // detailed format (using #?)
+            write!(f, "{}", active_states.join(" | "))?;
+            write!(f, " (0b{:016b})", self.0)
+        } else {
+            // normal format
+            write!(f, "{}", active_states.join("|"))
+        }
+    }
+}
+
+impl PlayerState {
+    pub const IDLE: Self = Self(0b0000000000000000);
+    pub const WALKING: Self = Self(0b0000000000000001);
+    pub const JUMP_UP: Self = Self(0b0000000000000010);
+    pub const SKILL: Self = Self(0b0000000000000100);
+    pub const KICKING: Self = Self(0b0000000000001000);
+    pub const PUNCHING: Self = Self(0b0000000000010000);
+    pub const RANGED_ATTACK: Self = Self(0b0000000000100000);
+    pub const BACK_KICKING: Self = Self(0b0000000001000000);
+    pub const COOLDOWN: Self = Self(0b0000000010000000);
+    pub const DIRECTION: Self = Self(0b0000000100000000);
+    pub const JUMP_FORWARD: Self = Self(0b0000001000000000);
+    pub const JUMP_BACKWARD: Self = Self(0b0000010000000000);
+    pub const BEND_DOWN: Self = Self(0b0000100000000000);
+    pub const ROLL_BACK: Self = Self(0b0001000000000000);
+    pub const ROLL_FORWARD: Self = Self(0b0010000000000000);
+    pub const ATTACK_DISABLED: Self = Self(0b0100000000000000);
+    pub const STUN:Self = Self(0b1000000000000000);
+    pub const GUARD: Self = Self(0b10000000000000000);
+    pub const THROWING: Self = Self(0b100000000000000000);
+    pub const THROWN: Self = Self(0b1000000000000000000);
+    pub const KNOCKDOWN: Self = Self(0b10000000000000000000);
+    pub const SPECIAL: Self = Self(0b100000000000000000000);
+    pub const EX: Self = Self(0b1000000000000000000000);
+    pub const DIZZY: Self = Self(0b10000000000000000000000);
+
+    // ignore cooldown state
+    pub fn is_idle(&self) -> bool {
+        self.0 & !(Self::COOLDOWN.0 | Self::DIRECTION.0 | Self::ATTACK_DISABLED.0) == 0
+    }
+    pub fn is_just_walk(&self) -> bool {
+        self.0 & !(Self::COOLDOWN.0 | Self::DIRECTION.0 | Self::ATTACK_DISABLED.0 | Self::WALKING.0) == 0 && self.check(Self::WALKING)
+    }
+    pub fn check(&self, state: Self) -> bool {
+        self.0 & state.0 != 0
+    }
+    pub fn is_forward(&self) -> bool {
+        self.0 & Self::DIRECTION.0 != 0
+    }
+}
+
+// number of frames a new move takes to catch up with the pose it interrupted
+const BLEND_FRAMES: u8 = 6;
+
+// one segment of an animation, from the pose at the start to the next key
+#[derive(Clone)]
+pub struct PlayerColliderAnimation {
+    // change of the pose over the whole segment
+    diff_pose: Pose,
+    easing: Easing,
+    // length of the segment in frames
+    frames: u8,
+    pub phase: u8,
+    pub count: u8,
+}
+
+impl PlayerColliderAnimation {
+    fn wait(phase: u8, count: u8) -> Self {
+        Self {
+            diff_pose: Pose::default(),
+            easing: Easing::Linear,
+            frames: count,
+            phase,
+            count,
+        }
+    }
+    // change of the pose on the next frame of the segment
+    fn step(&self) -> Pose {
+        let frames = self.frames.max(1) as f32;
+        let done = self.frames.saturating_sub(self.count) as f32;
+        self.diff_pose * (self.easing.apply((done + 1.0) / frames) - self.easing.apply(done / frames))
+    }
+}
+
+// what is left of the pose a move interrupted, it fades out in `BLEND_FRAMES` frames
+#[derive(Default, Clone)]
+pub struct PoseBlend {
+    diff_pose: Pose,
+    count: u8,
+}
+
+impl PoseBlend {
+    // part of the difference still left
+    fn remaining(&self) -> Pose {
+        let t = (BLEND_FRAMES - self.count) as f32 / BLEND_FRAMES as f32;
+        self.diff_pose * (1.0 - Easing::EaseOut.apply(t))
+    }
+}
+
+#[derive(Clone)]
+pub struct Player {
+    pub character_id: isize,
+    // the character of `character_id`, shared by the copies of the fighter
+    profile: Arc<CharacterProfile>,
+    pub pose: Pose,
+    // the poses the animations are made of, see `PoseSet`
+    pub poses: PoseSet,
+    pub animation: PlayerColliderAnimation,
+    pub blend: PoseBlend,
+    pub animation_frame_max: usize,
+    pub sprite: SpriteKind,
+    pub frame: usize,
+    pub state: PlayerState,
+    pub velocity: Vec2,
+    // walking speed, slower in the arena of a phone, see `Arena::profile`
+    pub agility: f32,
+    pub health: u32,
+    pub energy: u16,
+    pub fire_charge: u16,
+    // dizzy meter, see `apply_hit`
+    pub dizzy: u16,
+    // frames left of the invincibility after a dizzy, hits do damage but do not stun
+    pub invincible: u16,
+    // length of the invincibility after a dizzy, see `MatchRules::invincible_time`
+    pub invincible_frames: u16,
+    pub guard: u16,
+    // guard button or the direction away from the opponent is held
+    pub guard_input: bool,
+    // the combo the opponent is landing on this fighter
+    pub combo: Combo,
+    // melee move in progress, see frame_data.rs
+    pub attack: Option<ActiveMove>,
+    // frames since this fighter grabbed the opponent, 0 while not holding anyone
+    pub grab: u16,
+    // throw button pressed while being thrown, see throw.rs
+    pub throw_tech: bool,
+    // recent buttons and motions, see input_buffer.rs
+    pub input_buffer: InputBuffer,
+    // frames left of the freeze after a hit connected
+    pub hitstop: u8,
+    // speed in px per frame given by a hit, see `apply_impact`
+    pub knockback: Vec2,
+    // frames left of the invulnerability after getting up from a knockdown
+    pub wakeup: u16,
+    // every damage this fighter takes is multiplied by this, see `MatchRules`
+    pub damage_ratio: f32,
+    pub handicap: Handicap,
+    // energy gained with the meter rate of the handicap but not a whole point yet
+    pub energy_gain: f32,
+}
+
+// the idle pose a fighter starts a round with, player 2 mirrored to face player 1
+fn start_pose(poses: &PoseSet, id: u8) -> Pose {
+    Pose {
+        facing: id == 0,
+        ..poses.get(PoseName::Idle)
+    }
+}
+
+impl Player {
+    pub fn new(profile: Arc<CharacterProfile>, poses: PoseSet) -> Self {
+        Self {
+            character_id: profile.id,
+            pose: start_pose(&poses, 0),
+            poses,
+            animation: PlayerColliderAnimation::wait(1, 10),
+            blend: PoseBlend::default(),
+            animation_frame_max: profile.frames(SpriteKind::Idle),
+            sprite: SpriteKind::Idle,
+            frame: 0,
+            state: PlayerState::default(),
+            velocity: Vec2::ZERO,
+            agility: profile.agility,
+            health: profile.health,
+            energy: 0,
+            fire_charge: FIRE_CHARGE_MAX,
+            dizzy: 0,
+            invincible: 0,
+            invincible_frames: MatchRules::default().invincible_frames(),
+            guard: GUARD_MAX,
+            guard_input: false,
+            combo: Combo::default(),
+            attack: None,
+            grab: 0,
+            throw_tech: false,
+            input_buffer: InputBuffer::default(),
+            hitstop: 0,
+            knockback: Vec2::ZERO,
+            wakeup: 0,
+            damage_ratio: 1.0,
+            handicap: Handicap::default(),
+            energy_gain: 0.0,
+            profile,
+        }
+    }
+    pub fn new_opposite(profile: Arc<CharacterProfile>, poses: PoseSet) -> Self {
+        Self {
+            pose: start_pose(&poses, 1),
+            ..Self::new(profile, poses)
+        }
+    }
+    /// Gives the fighter what the rules give at the beginning of the match
+    pub fn apply_rules(&mut self, rules: &MatchRules) {
+        self.energy = rules.start_energy();
+        self.fire_charge = rules.start_fire_charge();
+        self.damage_ratio = rules.damage_ratio;
+        self.invincible_frames = rules.invincible_frames();
+    }
+    /// Gives the fighter its handicap, the health follows the new maximum
+    pub fn apply_handicap(&mut self, handicap: Handicap) {
+        self.handicap = handicap;
+        self.health = self.max_health();
+    }
+    /// Health at the beginning of a round
+    pub fn max_health(&self) -> u32 {
+        (self.profile().health as f32 * self.handicap.health).round() as u32
+    }
+    /// Swaps the character of the fighter, `reset` puts it at the beginning of a round.
+    /// The `Match` gives it the agility for its arena afterwards.
+    pub fn change_character(&mut self, profile: Arc<CharacterProfile>) {
+        self.character_id = profile.id;
+        self.agility = profile.agility;
+        self.profile = profile;
+    }
+    /// Puts the fighter of a team battle back at the beginning of a round like `reset`.
+    /// The winner of the last round stays in with the health it had left,
+    /// the next member of the team comes in with full health.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The player ID (0 for player 1, 1 for player 2)
+    /// * `profile` - Member of the team who fights the round
+    /// * `rules` - Rules of the match
+    pub fn start_team_round(&mut self, id: u8, profile: &Arc<CharacterProfile>, rules: &MatchRules) {
+        let health = self.health;
+        let stays = profile.id == self.character_id;
+        if !stays {
+            self.change_character(profile.clone());
+        }
+        self.reset(id, rules);
+        if stays {
+            self.health = health;
+        }
+    }
+    /// Leaves the fighter the little health of a sudden death round
+    pub fn start_sudden_death(&mut self) {
+        self.health = ((self.max_health() as f32 * SUDDEN_DEATH_HEALTH).ceil() as u32).max(1);
+    }
+    /// true if the fighter has the meter or the charge `button` needs
+    pub fn can_afford(&self, button: Button) -> bool {
+        match button {
+            Button::Fire => self.fire_charge == FIRE_CHARGE_MAX,
+            Button::Special => self.energy >= SPECIAL_COST,
+            Button::Skill => self.energy >= SKILL_COST,
+            _ => true,
+        }
+    }
+    /// Adds energy at the meter rate of the handicap
+    pub fn gain_energy(&mut self, amount: u16) {
+        self.energy_gain += amount as f32 * self.handicap.meter;
+        let whole = self.energy_gain.floor();
+        self.energy += whole as u16;
+        self.energy_gain -= whole;
+    }
+    /// The projectile the fighter launches, `ex` for the EX version
+    pub fn projectile_def(&self, ex: bool) -> ProjectileDef {
+        let def = self.profile().projectile;
+        let def = if ex { def.ex() } else { def };
+        ProjectileDef {
+            damage: self.handicap.damage_of(def.damage),
+            ..def
+        }
+    }
+    /// Puts the fighter back at the beginning of a round, the energy is kept
+    pub fn reset(&mut self, id: u8, rules: &MatchRules) {
+        self.pose = start_pose(&self.poses, id);
+        self.animation = PlayerColliderAnimation::wait(1, 10);
+        self.blend = PoseBlend::default();
+        self.set_sprite(SpriteKind::Idle, 0);
+        self.state = PlayerState::default();
+        self.velocity = Vec2::ZERO;
+        self.health = self.max_health();
+        self.fire_charge = rules.start_fire_charge();
+        self.dizzy = 0;
+        self.invincible = 0;
+        self.guard = GUARD_MAX;
+        self.guard_input = false;
+        self.combo = Combo::default();
+        self.attack = None;
+        self.grab = 0;
+        self.throw_tech = false;
+        self.input_buffer = InputBuffer::default();
+        self.hitstop = 0;
+        self.knockback = Vec2::ZERO;
+        self.wakeup = 0;
+    }
+    /// switch the sprite sheet and start it from `frame`
+    pub fn set_sprite(&mut self, sprite: SpriteKind, frame: usize) {
+        self.sprite = sprite;
+        self.frame = frame;
+        self.animation_frame_max = self.profile().frames(sprite);
+    }
+    pub fn profile(&self) -> &CharacterProfile {
+        &self.profile
+    }
+    pub fn skill(&self) -> SkillKind {
+        self.profile().skill
+    }
+    /// the damage this fighter actually takes from a hit of `damage`
+    pub fn scaled_damage(&self, damage: u32) -> u32 {
+        (damage as f32 * self.damage_ratio).round() as u32
+    }
+    /// Move to the key `pose` in `count` frames, easing in and out.
+    pub fn set_animation(&mut self, pose: Pose, phase: u8, count: u8) {
+        self.set_animation_eased(pose, phase, count, Easing::EaseInOut);
+    }
+    /// Move to the key `pose` in `count` frames along `easing`.
+    /// The angles turn the shorter way around, and a blend still running from
+    /// `start_pose` is left out so it keeps fading on top of the segment.
+    ///
+    /// # Arguments
+    /// * `pose` - Key at the end of the segment
+    /// * `phase` - Phase of the move during the segment
+    /// * `count` - Length of the segment in frames
+    /// * `easing` - Timing curve of the segment
+    pub fn set_animation_eased(&mut self, pose: Pose, phase: u8, count: u8, easing: Easing) {
+        let from = self.pose - self.blend.remaining();
+        self.animation = PlayerColliderAnimation {
+            diff_pose: from.arc_to(&pose),
+            easing,
+            frames: count,
+            phase,
+            count,
+        };
+    }
+    /// Jump to the first key of a new move. The difference to the pose it interrupts
+    /// fades out over the next frames instead of snapping.
+    pub fn start_pose(&mut self, pose: Pose) {
+        let mut key = self.pose;
+        key.set(pose);
+        self.blend = PoseBlend {
+            diff_pose: key.arc_to(&self.pose),
+            count: BLEND_FRAMES,
+        };
+        self.pose = key + self.blend.diff_pose;
+    }
+    // fade the blend of `start_pose` by one frame
+    fn update_blend(&mut self) {
+        if self.blend.count == 0 {
+            return;
+        }
+        let before = self.blend.remaining();
+        self.blend.count -= 1;
+        self.pose += self.blend.remaining() - before;
+    }
+    pub fn update_animation(&mut self) {
+        if self.animation.count == 0 {
+            return;
+        }
+        if !(self.state.check(PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD) && self.state.check(PlayerState::KICKING)) {
+            if self.state.check(PlayerState::ROLL_BACK)
+                || (self.state.check(PlayerState::WALKING) && self.state.check(PlayerState::DIRECTION) && !self.pose.facing)
+                || (self.state.check(PlayerState::WALKING) && !self.state.check(PlayerState::DIRECTION) && self.pose.facing) {
+                self.frame = self.frame.saturating_sub(1);
+                if self.frame == 0 {
+                    self.frame = self.animation_frame_max - 1;
+                }
+            } else {
+                self.frame += 1;
+                if self.frame >= self.animation_frame_max {
+                    self.frame = 0;
+                }
+            }
+            self.pose += self.animation.step();
+        }
+        self.animation.count -= 1;
+    }
+    pub fn update_animation_final(&mut self) {
+        if self.animation.count == 0 {
+            return;
+        }
+        self.frame += 1;
+        if self.frame >= self.animation_frame_max {
+            self.frame = self.animation_frame_max - 1;
+        }
+        self.animation.count -= 1;
+    }
+    /// back to the idle loop on the ground
+    fn set_idle(&mut self, phase: u8) {
+        self.enter(Action::Idle);
+        self.set_animation(IDLE_POSE2, phase, 15);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BodyParts {
+    flags: u8,
+}
+
+#[allow(dead_code)]
+impl BodyParts {
+    pub const NULL: Self = Self { flags: 0b00000 };
+    pub const HEAD: Self = Self { flags: 0b10000 };
+    pub const BODY: Self = Self { flags: 0b01000 };
+    pub fn new(head: bool, body: bool, arm: bool, right: bool, upper: bool) -> Self {
+        Self {
+            flags: (head as u8) << 4
+                | (body as u8) << 3
+                | (arm as u8) << 2
+                | (right as u8) << 1
+                | (upper as u8),
+        }
+    }
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+    pub fn is_head(&self) -> bool {
+        self.flags & 0b10000 != 0
+    }
+    pub fn is_body(&self) -> bool {
+        self.flags & 0b01000 != 0
+    }
+    pub fn is_arm(&self) -> bool {
+        self.flags & 0b00100 != 0
+    }
+    pub fn is_leg(&self) -> bool {
+        self.flags & 0b11100 == 0
+    }
+    pub fn is_right(&self) -> bool {
+        self.flags & 0b00010 != 0
+    }
+    pub fn is_upper(&self) -> bool {
+        self.flags & 0b00001 != 0
+    }
+}
+
+impl Debug for BodyParts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.flags {
+            0b10000 => write!(f, "BodyParts: Head"),
+            0b01000 => write!(f, "BodyParts: Body"),
+            0b00111 => write!(f, "BodyParts: Right Upper Arm"),
+            0b00110 => write!(f, "BodyParts: Right Lower Arm"),
+            0b00011 => write!(f, "BodyParts: Right Upper Leg"),
+            0b00010 => write!(f, "BodyParts: Right Lower Leg"),
+            0b00101 => write!(f, "BodyParts: Left Upper Arm"),
+            0b00100 => write!(f, "BodyParts: Left Lower Arm"),
+            0b00001 => write!(f, "BodyParts: Left Upper Leg"),
+            0b00000 => write!(f, "BodyParts: Left Lower Leg"),
+            _ => write!(f, "BodyParts: Unkown"),
+        }
+    }
+}
+
+/// Buttons of one fighter for a single logic frame.
+/// `left`, `right` and `down` are held buttons, the others are true only on the frame they are pressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FighterInput {
+    pub left: bool,
+    pub right: bool,
+    pub down: bool,
+    pub jump: bool,
+    pub kick: bool,
+    pub punch: bool,
+    pub fire: bool,
+    pub back_kick: bool,
+    pub skill: bool,
+    pub special: bool,
+    // held like the directions
+    pub guard: bool,
+    // held while pressing an attack for its EX version
+    pub ex: bool,
+    pub throw: bool,
+    // recognised from a double tap by the input buffer, devices leave them false
+    pub dash_forward: bool,
+    pub dash_back: bool,
+}
+
+/// Things happened in the simulation which the view has to show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimEvent {
+    // fighter started the skill animation
+    SkillStarted { id: usize },
+    // ranged attack released its projectile
+    // `ex` is true for the EX version, see `ProjectileDef::ex`
+    FireLaunched { id: usize, position: Vec2, facing: bool, ex: bool },
+    // melee attack landed, `combo` is the number of hits in the combo so far
+    Hit { attacker: usize, victim: usize, damage: u32, head: bool, combo: u16 },
+    // melee attack or fire arrow was blocked, only the chip damage went through
+    Blocked { attacker: usize, victim: usize, damage: u32, broken: bool },
+    // throw landed, throws ignore the guard
+    Thrown { attacker: usize, victim: usize, damage: u32 },
+    // the victim escaped the throw by pressing throw in time
+    ThrowTeched { attacker: usize, victim: usize },
+    // projectile hit the opponent
+    FireHit { owner: usize, victim: usize, damage: u32 },
+    // projectiles of both fighters touched and cancelled each other
+    FireClash { position: Vec2 },
+    // a back kick sent a projectile back, `owner` is the kicker
+    FireReflected { owner: usize },
+    // projectile is gone: used up, out of the stage or under the floor, `owner` is the one who launched it
+    FireVanished { owner: usize },
+    // skill dealt damage
+    SkillHit { attacker: usize, victim: usize, damage: u32 },
+    // cue of the skill animation of fighter `id`, see `step_skill`
+    Cue { id: usize, cue: SkillCue },
+    // a soul of 魂吸収 reached fighter `id`, see `absorb_soul`
+    SoulAbsorbed { id: usize },
+    // the round is decided, winner 0 means draw
+    RoundOver { winner: u8, ko: bool },
+    // the winner or loser pose of fighter `id` after a KO is over
+    PoseFinished { id: usize },
+}
+
+/// Applies the buttons of one frame to the fighter.
+/// The buttons go through the input buffer of the fighter first, so a press made during
+/// the recovery of a move is done on the first frame it can be, and motions of the
+/// character are turned into their moves.
+///
+/// # Arguments
+///
+/// * `player` - The fighter to control
+/// * `input` - Buttons of this frame
+///
+/// Returns true when the fighter started the skill, so that the caller can start the skill animation.
+pub fn apply_input(player: &mut Player, input: &FighterInput) -> bool {
+    let back = if player.pose.facing { input.left } else { input.right };
+    player.guard_input = input.guard || back;
+    let affordable = Button::ALL.map(|button| player.can_afford(button));
+    let input = &player.input_buffer.push(input, player.pose.facing, &player.profile.motions, |button| {
+        affordable[button as usize]
+    });
+    // the buttons pressed during the hitstop stay buffered
+    if player.hitstop > 0 || player.state.check(PlayerState::COOLDOWN) {
+        return false;
+    }
+    if player.action() == Action::KnockedDown {
+        get_up(player, input);
+        return false;
+    }
+    if input.guard {
+        if player.can_enter(Action::Guard) {
+            // player will stand still and guard
+            player.enter(Action::Guard);
+        }
+    } else if player.action() == Action::Guard {
+        // player stops guarding
+        player.enter(Action::Idle);
+        player.set_animation(IDLE_POSE2, 1, 15);
+    }
+    if input.right || input.left {
+        // direction is right or left
+        if input.right {
+            player.state |= PlayerState::DIRECTION;
+        } else {
+            player.state &= !PlayerState::DIRECTION;
+        }
+        if player.can_enter(Action::Walk) {
+            // player is just walking
+            player.enter(Action::Walk);
+            player.set_animation(WALKING_POSE2, 1, 15);
+        }
+    } else if player.action() == Action::Walk {
+        // player is not walking
+        player.enter(Action::Idle);
+        player.set_animation(IDLE_POSE2, 1, 15);
+    }
+    if input.down {
+        if player.can_enter(Action::BendDown) {
+            // player is idle
+            // then player will bend down
+            player.enter(Action::BendDown);
+            player.set_animation_eased(player.poses.get(PoseName::BendDown), 0, 27, Easing::EaseOut);
+        } else if player.action() == Action::Walk {
+            // player is walking toward or away from the opponent
+            // then player will roll in that direction
+            start_roll(player, player.pose.facing == player.state.is_forward());
+        }
+    } else if player.action() == Action::BendDown && player.animation.phase != 2 {
+        // player is bending down
+        // then stop bending down
+        player.set_animation(BEND_DOWN_POSE1, 2, 23);
+    }
+    if input.dash_forward || input.dash_back {
+        let forward = input.dash_forward;
+        let to = if forward { Action::RollForward } else { Action::RollBack };
+        if player.can_enter(to) {
+            // player double tapped a direction
+            // then player will dash in that direction
+            if forward == player.pose.facing {
+                player.state |= PlayerState::DIRECTION;
+            } else {
+                player.state &= !PlayerState::DIRECTION;
+            }
+            start_roll(player, forward);
+            player.input_buffer.consume(if forward { Button::DashForward } else { Button::DashBack });
+        }
+    }
+    if input.jump {
+        if player.can_enter(Action::JumpUp) {
+            // player is idle
+            // then player will jump up
+            player.enter(Action::JumpUp);
+            player.set_animation(player.poses.get(PoseName::JumpUp2), 0, 11);
+            player.gain_energy(1);
+            player.input_buffer.consume(Button::Jump);
+        } else if player.action() == Action::Walk {
+            if player.pose.facing == player.state.is_forward() {
+                // player is walking toward the opponent
+                // then player will jump forward
+                player.enter(Action::JumpForward);
+            } else {
+                // player is walking away from the opponent
+                // then player will jump backward
+                player.enter(Action::JumpBackward);
+            }
+            // stop moving for preparing motion
+            player.set_animation(player.poses.get(PoseName::JumpUp1), 0, 11);
+            player.gain_energy(1);
+            player.input_buffer.consume(Button::Jump);
+        }
+    }
+    if input.kick {
+        if player.can_enter(Action::Kick) {
+            // player is idle
+            // then player will kick
+            player.enter(Action::Kick);
+            player.set_animation_eased(player.poses.get(PoseName::Kick2), 0, 21, Easing::EaseIn);
+            player.gain_energy(2);
+            player.input_buffer.consume(Button::Kick);
+            try_ex(player, input.ex);
+        } else if player.can_enter(Action::JumpKick) {
+            // player is jumping
+            // then just adding state
+            player.enter(Action::JumpKick);
+            player.gain_energy(2);
+            player.input_buffer.consume(Button::Kick);
+            try_ex(player, input.ex);
+        }
+    }
+    if input.punch && player.can_enter(Action::Punch) {
+        // player is idle
+        // then player will punch
+        player.enter(Action::Punch);
+        player.set_animation_eased(player.poses.get(PoseName::Punch), 0, 19, Easing::EaseIn);
+        player.gain_energy(2);
+        player.input_buffer.consume(Button::Punch);
+        try_ex(player, input.ex);
+    }
+    if input.fire && player.can_afford(Button::Fire) && player.can_enter(Action::RangedAttack) {
+        // player is idle
+        // player will do ranged attack
+        player.fire_charge = 0;
+        player.enter(Action::RangedAttack);
+        player.set_animation_eased(player.poses.get(PoseName::Punch), 0, 19, Easing::EaseIn);
+        player.gain_energy(2);
+        player.input_buffer.consume(Button::Fire);
+        try_ex(player, input.ex);
+    }
+    if input.back_kick && player.can_enter(Action::BackKick) {
+        // player is idle
+        // then player will back kick
+        player.enter(Action::BackKick);
+        player.set_animation_eased(player.poses.get(PoseName::BackKick2), 0, 6, Easing::EaseIn);
+        player.gain_energy(2);
+        player.input_buffer.consume(Button::BackKick);
+        try_ex(player, input.ex);
+    }
+    if input.throw {
+        if player.action() == Action::Thrown {
+            // player is being thrown
+            // then try to escape, see throw.rs
+            player.throw_tech = true;
+            player.input_buffer.consume(Button::Throw);
+        } else if player.can_enter(Action::Throw) {
+            // player is standing
+            // then player will reach for the opponent
+            player.enter(Action::Throw);
+            player.set_animation_eased(THROW_POSE1, 0, 8, Easing::EaseIn);
+            player.input_buffer.consume(Button::Throw);
+        }
+    }
+    if input.special && player.can_afford(Button::Special) && player.can_enter(Action::Special) {
+        // player is standing
+        // then player will do the special of the character
+        player.input_buffer.consume(Button::Special);
+        start_special(player);
+    }
+    if input.skill && player.can_afford(Button::Skill) && player.can_enter(Action::Skill) {
+        // player is idle
+        // then player will use skill
+        player.input_buffer.consume(Button::Skill);
+        start_skill(player);
+        return true;
+    }
+    false
+}
+
+// turns the move just started into its EX version when `ex` is held and a stock is full
+fn try_ex(player: &mut Player, ex: bool) {
+    if ex && player.energy >= EX_COST {
+        player.energy -= EX_COST;
+        player.state |= PlayerState::EX;
+    }
+}
+
+// starts a roll toward the opponent when `forward` is true, away from it otherwise
+// the direction of the roll is the DIRECTION flag
+fn start_roll(player: &mut Player, forward: bool) {
+    if forward {
+        player.enter(Action::RollForward);
+        player.set_animation_eased(player.poses.get(PoseName::RollForward2), 0, 11, Easing::Linear);
+    } else {
+        player.enter(Action::RollBack);
+        player.set_animation_eased(player.poses.get(PoseName::RollForward5), 0, 4, Easing::Linear);
+    }
+}
+
+// while lying on the floor, jump gets up at once and down with a direction rolls
+// toward that direction instead of getting up
+fn get_up(player: &mut Player, input: &FighterInput) {
+    if player.animation.phase != 1 {
+        return;
+    }
+    if input.jump {
+        // quick rise
+        player.set_animation_eased(player.poses.get(PoseName::Idle), 2, QUICK_RISE_FRAMES, Easing::EaseOut);
+        player.input_buffer.consume(Button::Jump);
+    } else if input.down && input.left != input.right {
+        // recovery roll
+        if input.right {
+            player.state |= PlayerState::DIRECTION;
+        } else {
+            player.state &= !PlayerState::DIRECTION;
+        }
+        start_roll(player, input.right == player.pose.facing);
+    }
+}
+
+/// Knocks the fighter down, it falls to the floor and lies there until it gets up.
+/// Nothing can hit it until a while after it got up, see `Player::is_invulnerable`.
+pub fn knock_down(player: &mut Player) {
+    player.enter(Action::KnockedDown);
+    player.set_animation_eased(KNOCKDOWN_POSE, 0, KNOCKDOWN_FALL_FRAMES, Easing::EaseOut);
+}
+
+/// Puts the fighter into the first phase of the skill animation and spends the energy
+pub fn start_skill(player: &mut Player) {
+    player.energy -= SKILL_COST;
+    player.enter(Action::Skill);
+}
+
+/// Starts the second special of the character and spends the energy.
+/// Phase 0 of the animation is the startup, phase 1 the active frames and phase 2
+/// the recovery, they match the frame data of the move of the special.
+pub fn start_special(player: &mut Player) {
+    player.energy -= SPECIAL_COST;
+    player.enter(Action::Special);
+    let kind = player.profile().special;
+    player.attack = kind.move_kind().map(ActiveMove::new);
+    let (startup, _, _) = special_frames(kind);
+    match kind {
+        SpecialKind::Rush => {
+            player.set_sprite(SpriteKind::Punch, 0);
+            player.start_pose(player.poses.get(PoseName::Kick1));
+            player.set_animation_eased(player.poses.get(PoseName::Punch), 0, startup, Easing::EaseIn);
+        }
+        SpecialKind::Heal => {
+            player.set_sprite(SpriteKind::Idle, 0);
+            player.set_animation_eased(GUARD_POSE, 0, startup, Easing::EaseOut);
+        }
+        SpecialKind::Quake => {
+            player.set_sprite(SpriteKind::Kick, 0);
+            player.start_pose(player.poses.get(PoseName::Kick1));
+            player.set_animation_eased(player.poses.get(PoseName::Kick2), 0, startup, Easing::EaseIn);
+        }
+    }
+}
+
+// startup, active and recovery frames of a special
+fn special_frames(kind: SpecialKind) -> (u8, u8, u8) {
+    match kind.move_kind() {
+        Some(kind) => {
+            let data = kind.frame_data();
+            (data.startup as u8, data.active as u8, data.recovery as u8)
+        }
+        None => HEAL_FRAMES,
+    }
+}
+
+/// Turns the fighter toward the opponent while idle or walking.
+///
+/// # Arguments
+///
+/// * `player` - The fighter to turn
+/// * `position` - Position of the fighter, shifted by the pose offset when turning
+/// * `opponent_x` - X position of the opponent
+pub fn face_opponent(player: &mut Player, position: &mut Vec2, opponent_x: f32) {
+    if !(player.state.is_idle() || player.state.check(PlayerState::WALKING | PlayerState::GUARD)) {
+        return;
+    }
+    if position.x < opponent_x {
+        if player.pose.facing {
+            return;
+        }
+        player.pose.facing = true;
+        position.x -= player.pose.offset[0] * 2.0;
+    } else {
+        if !player.pose.facing {
+            return;
+        }
+        player.pose.facing = false;
+        position.x += player.pose.offset[0] * 2.0;
+    }
+}
+
+/// Advances one fighter by a single logic frame.
+///
+/// # Arguments
+///
+/// * `id` - The player ID (0 for player 1, 1 for player 2)
+/// * `player` - The fighter to advance
+/// * `position` - Position of the fighter, updated in place
+/// * `ground_y` - Y position of the floor
+/// * `free` - false while the fighter's body is blocked by the opponent
+///
+/// Returns `SimEvent::FireLaunched` on the frame a ranged attack releases its arrow.
+pub fn step_fighter(id: usize, player: &mut Player, position: &mut Vec2, ground_y: f32, free: bool) -> Option<SimEvent> {
+    let mut event = None;
+    if player.hitstop > 0 {
+        // frozen by the impact of a hit, nothing moves
+        player.hitstop -= 1;
+        return event;
+    }
+    apply_knockback(player, position, ground_y);
+    if player.invincible > 0 {
+        player.invincible -= 1;
+    }
+    if !matches!(player.action(), Action::Stun | Action::Dizzy | Action::KnockedDown | Action::Thrown) {
+        player.dizzy = player.dizzy.saturating_sub(DIZZY_RECOVERY);
+    }
+    if player.wakeup > 0 {
+        player.wakeup -= 1;
+    }
+    if let Some(attack) = &mut player.attack {
+        attack.frame += 1;
+    }
+    player.update_blend();
+    // the auto-block of a handicap does not keep the guard from coming back
+    if (player.guard_stance().is_none() || player.handicap.auto_block) && player.guard < GUARD_MAX {
+        player.guard += 1;
+    }
+
+    if !player.state.check(PlayerState::RANGED_ATTACK) && player.fire_charge < FIRE_CHARGE_MAX {
+        player.fire_charge += 1;
+    }
+    if player.energy > ENERGY_MAX {
+        player.energy = ENERGY_MAX;
+    }
+
+    // player is idle
+    if player.state.is_idle() {
+        player.velocity = Vec2::ZERO;
+        if player.animation.phase == 0 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                if player.state.check(PlayerState::COOLDOWN) {
+                    player.state &= !PlayerState::COOLDOWN;
+                }
+                if player.state.check(PlayerState::ATTACK_DISABLED) {
+                    player.state &= !PlayerState::ATTACK_DISABLED;
+                }
+                player.set_animation(IDLE_POSE2, 1, 15);
+            }
+        } else if player.animation.phase == 1 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation(player.poses.get(PoseName::Idle), 2, 15);
+            }
+        } else if player.animation.phase == 2 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                // Reset sprite index when looping back to phase 1
+                player.frame = 0;
+                player.set_animation(IDLE_POSE2, 1, 15);
+            }
+        }
+    }
+
+    // player is stunning
+    // the hit sends the player away with the knockback
+    if player.state.check(PlayerState::STUN) {
+        if player.animation.phase == 0 {
+            // Apply gravity during stun animation phase 0 if in the air
+            if position.y > ground_y {
+                player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+            }
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation(STUN_POSE1, 1, 18);
+            }
+        } else if player.animation.phase == 1 {
+            // Apply gravity during stun animation phase 1 if in the air
+            if position.y > ground_y {
+                player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+            }
+            player.update_animation();
+            if player.animation.count == 0 {
+                if position.y > ground_y {
+                    // Still in the air - enter falling phase
+                    player.animation.phase = 2;
+                    // Don't reset velocity - let gravity take effect
+                } else {
+                    // On the ground - return to idle
+                    player.velocity = Vec2::ZERO;
+                    player.set_idle(1);
+                }
+            }
+        } else if player.animation.phase == 2 {
+            if position.y == ground_y {
+                // Landed on ground - return to idle
+                player.velocity = Vec2::ZERO;
+                player.set_idle(1);
+            } else {
+                // Still falling - apply gravity
+                player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+            }
+        }
+    } else if player.state.check(PlayerState::DIZZY) {
+        // player is dizzy, swaying on the spot until the meter is empty
+        if position.y > ground_y {
+            player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+        }
+        player.update_animation();
+        if player.animation.count == 0 {
+            if player.animation.phase == 1 {
+                player.set_animation(STUN_POSE2, 2, DIZZY_SWAY_FRAMES);
+            } else {
+                player.set_animation(STUN_POSE1, 1, DIZZY_SWAY_FRAMES);
+            }
+        }
+        player.dizzy = player.dizzy.saturating_sub(DIZZY_MAX / DIZZY_FRAMES);
+        if player.dizzy == 0 && position.y <= ground_y {
+            player.velocity = Vec2::ZERO;
+            player.set_idle(1);
+        }
+    } else if player.state.check(PlayerState::KNOCKDOWN) {
+        // player is knocked down
+        if player.animation.phase == 0 {
+            // falling to the floor
+            if position.y > ground_y {
+                player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+            } else {
+                position.y = ground_y;
+                player.velocity = Vec2::ZERO;
+            }
+            player.update_animation();
+            if player.animation.count == 0 && position.y == ground_y {
+                // lying on the floor, see `get_up` for getting up early
+                player.animation = PlayerColliderAnimation::wait(1, KNOCKDOWN_FRAMES);
+            }
+        } else if player.animation.phase == 1 {
+            // the sprite stays on its last frame
+            player.update_animation_final();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::Idle), 2, GET_UP_FRAMES, Easing::EaseOut);
+            }
+        } else if player.animation.phase == 2 {
+            // getting up
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_idle(1);
+            }
+        }
+    } else if player.state.check(PlayerState::JUMP_UP | PlayerState::JUMP_BACKWARD | PlayerState::JUMP_FORWARD) {
+        // player is jumping
+
+        // prepare for jump
+        // TODO: calculate gravity outside of JUMPING state
+        if player.animation.phase != 0 && player.animation.phase != 4 {
+            player.velocity -= Vec2::new(0.0, GRAVITY_ACCEL * 3.0 / FPS);
+        }
+
+        if player.animation.phase == 0 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                let x_vel = if player.state.check(PlayerState::JUMP_UP) {
+                    0.0
+                } else if player.state.check(PlayerState::DIRECTION) {
+                    player.agility * 3.0
+                } else {
+                    -player.agility * 3.0
+                };
+                player.velocity = Vec2::new(x_vel, 12.0);
+                player.set_animation_eased(JUMP_POSE3, 1, 14, Easing::EaseOut);
+            }
+        } else if player.animation.phase == 1 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.animation.phase = 2;
+            }
+        } else if player.animation.phase == 2 {
+            if player.velocity.y > 0.0 {
+                player.animation.count += 1;
+            } else {
+                player.animation.count -= 1;
+                if player.animation.count == 3 {
+                    player.animation.count = 0;
+                }
+            }
+            if player.animation.count == 0 {
+                player.set_animation(player.poses.get(PoseName::JumpUp2), 3, 17);
+            }
+        } else if player.animation.phase == 3 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                if player.action() == Action::JumpKick {
+                    let mut jump = player.state;
+                    jump &= !PlayerState::KICKING;
+                    player.enter(Action::of(jump));
+                    player.set_sprite(SpriteKind::Jump, 42);
+                    player.start_pose(player.poses.get(PoseName::JumpUp2));
+                }
+                player.set_animation_eased(player.poses.get(PoseName::JumpUp1), 4, 18, Easing::EaseOut);
+            }
+        } else if player.animation.phase == 4 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_idle(1);
+            }
+        }
+    } else if player.state.check(PlayerState::BEND_DOWN) {
+        // player is bending down
+        if player.animation.phase == 0 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                // Bend Down Pose lasts until the player stands up
+                player.animation.phase = 1;
+            }
+        } else if player.animation.phase == 2 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_idle(0);
+            }
+        }
+    } else if player.state.check(PlayerState::ROLL_FORWARD) {
+        // player is rolling forward
+        if player.animation.phase == 0 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward3), 1, 7, Easing::Linear);
+            }
+        } else if player.animation.phase == 1 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward4), 2, 6, Easing::Linear);
+            }
+        } else if player.animation.phase == 2 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward5), 3, 6, Easing::Linear);
+            }
+        } else if player.animation.phase == 3 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward6), 4, 5, Easing::Linear);
+            }
+        } else if player.animation.phase == 4 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_idle(0);
+                player.state |= PlayerState::COOLDOWN;
+            }
+        }
+    } else if player.state.check(PlayerState::ROLL_BACK) {
+        // player is rolling back
+        if player.animation.phase == 0 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward4), 1, 6, Easing::Linear);
+            }
+        } else if player.animation.phase == 1 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward3), 2, 6, Easing::Linear);
+            }
+        } else if player.animation.phase == 2 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward2), 3, 7, Easing::Linear);
+            }
+        } else if player.animation.phase == 3 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_animation_eased(player.poses.get(PoseName::RollForward1), 4, 12, Easing::Linear);
+            }
+        } else if player.animation.phase == 4 {
+            player.update_animation();
+            if player.animation.count == 0 {
+                player.set_idle(0);
+                player.state |= PlayerState::COOLDOWN;
+            }
+        }
+    } else {
+        if player.state.check(PlayerState::KICKING) {
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::Idle), 1, 24, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.enter(Action::Idle);
+                    player.set_animation(IDLE_POSE2, 1, 25);
+                }
+            }
+        } else if player.state.check(PlayerState::RANGED_ATTACK) {
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::Idle), 1, 12, Easing::EaseOut);
+                    event = Some(SimEvent::FireLaunched {
+                        id,
+                        position: *position,
+                        facing: player.pose.facing,
+                        ex: player.state.check(PlayerState::EX),
+                    });
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.enter(Action::Idle);
+                    player.set_animation(IDLE_POSE2, 1, 25);
+                }
+            }
+        } else if player.state.check(PlayerState::BACK_KICKING) {
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.set_animation_eased(BACK_KICK_POSE3, 1, 13, Easing::Cubic);
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::BackKick2), 2, 10, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 2 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.set_animation_eased(player.poses.get(PoseName::BackKick1), 3, 19, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 3 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.set_idle(1);
+                }
+            }
+        } else if player.state.check(PlayerState::PUNCHING) {
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::Idle), 1, 12, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.enter(Action::Idle);
+                    player.set_animation(IDLE_POSE2, 1, 15);
+                }
+            }
+        } else if player.state.check(PlayerState::SPECIAL) {
+            let kind = player.profile().special;
+            let (_, active, recovery) = special_frames(kind);
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    match kind {
+                        SpecialKind::Rush => {
+                            // dash toward the opponent with the fist out
+                            let sign = if player.pose.facing { 1.0 } else { -1.0 };
+                            player.velocity = Vec2::new(sign * player.agility * 3.0, 0.0);
+                            player.animation = PlayerColliderAnimation::wait(1, active);
+                        }
+                        SpecialKind::Heal => {
+                            player.health = (player.health + HEAL_AMOUNT).min(player.max_health());
+                            player.guard = GUARD_MAX;
+                            player.animation = PlayerColliderAnimation::wait(1, active);
+                        }
+                        SpecialKind::Quake => {
+                            // stomp the floor
+                            player.set_animation_eased(BEND_DOWN_POSE1, 1, active, Easing::EaseIn);
+                        }
+                    }
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.velocity = Vec2::ZERO;
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::Idle), 2, recovery, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 2 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.enter(Action::Idle);
+                    player.set_animation(IDLE_POSE2, 1, 15);
+                }
+            }
+        } else if player.state.check(PlayerState::THROWING) {
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    // the throw missed
+                    player.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+                    player.set_animation_eased(player.poses.get(PoseName::Idle), 1, 25, Easing::EaseOut);
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.enter(Action::Idle);
+                    player.set_animation(IDLE_POSE2, 1, 15);
+                }
+            } else if player.animation.phase == 2 {
+                // carrying the opponent, `step_throw` moves both fighters
+                player.update_animation();
+            }
+        } else if player.state.check(PlayerState::THROWN) {
+            player.update_animation();
+        }
+        if player.state.check(PlayerState::WALKING) {
+            let agility = player.agility;
+            if player.state.is_forward() && player.velocity.x < agility {
+                player.velocity += Vec2::new(1.0, 0.0) * PIXELS_PER_METER / FPS;
+            } else if !player.state.is_forward() && player.velocity.x > -agility {
+                player.velocity += Vec2::new(-1.0, 0.0) * PIXELS_PER_METER / FPS;
+            }
+            player.velocity.x = player.velocity.x.clamp(-agility, agility);
+            if player.animation.phase == 0 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.set_animation(WALKING_POSE2, 1, 15);
+                }
+            } else if player.animation.phase == 1 {
+                player.update_animation();
+                if player.animation.count == 0 {
+                    player.set_animation(WALKING_POSE1, 0, 14);
+                }
+            }
+        }
+    }
+    if free {
+        // no collision, player moves freely
+        if !player.state.check(PlayerState::STUN) {
+            // if not in stun state, apply velocity
+            if !player.state.check(PlayerState::BACK_KICKING) {
+                *position += player.velocity * PIXELS_PER_METER / FPS;
+            }
+        } else if player.animation.phase == 2 {
+            // if in stun state and falling down, apply velocity
+            position.y += player.velocity.y * PIXELS_PER_METER / FPS;
+        }
+    } else {
+        // collision, player cannot move along x-axis
+        position.y += player.velocity.y * PIXELS_PER_METER / FPS;
+    }
+    event
+}
+
+// moves the fighter by the knockback of a hit and slows it down
+fn apply_knockback(player: &mut Player, position: &mut Vec2, ground_y: f32) {
+    if player.knockback == Vec2::ZERO {
+        return;
+    }
+    *position += player.knockback;
+    player.knockback.x *= KNOCKBACK_FRICTION;
+    if player.knockback.x.abs() < 0.5 {
+        player.knockback.x = 0.0;
+    }
+    if player.knockback.y != 0.0 {
+        player.knockback.y -= KNOCKBACK_GRAVITY;
+        if position.y <= ground_y {
+            position.y = ground_y;
+            player.knockback.y = 0.0;
+        }
+    }
+}
+
+/// Advances the winner and loser pose after a KO.
+/// Returns true when the pose is finished.
+pub fn step_final_pose(player: &mut Player, position: &mut Vec2, ground_y: f32) -> bool {
+    if player.animation.count == 0 {
+        return true;
+    }
+    position.y += (ground_y - position.y) / player.animation.count as f32;
+    player.update_animation_final();
+    if player.animation.count == 0 {
+        player.animation.phase = 1;
+        return true;
+    }
+    false
+}
+
+/// Puts the fighter back on the floor after a jump or a stun in the air
+pub fn check_landing(player: &mut Player, position: &mut Vec2, ground_y: f32) {
+    // phase 0 is the preliminary motion
+    if player.animation.phase == 0 {
+        return;
+    }
+    if player.state.check(PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD | PlayerState::JUMP_BACKWARD | PlayerState::STUN | PlayerState::DIZZY)
+        && position.y < ground_y
+    {
+        position.y = ground_y;
+        player.velocity = Vec2::ZERO;
+    }
+}
+
+/// Scrolls the stage when a fighter reaches the edge of the screen.
+/// The stage stops scrolling when one of its walls is on the screen.
+///
+/// # Arguments
+///
+/// * `positions` - Positions of both fighters, indexed by player ID
+/// * `stage_x` - X position of the ground, moved instead of the camera
+/// * `arena` - The arena, gives the visible width and the walls
+pub fn scroll_stage(positions: &mut [Vec2; 2], stage_x: &mut f32, arena: &Arena) {
+    let half_width = arena.half_width;
+    // the wall is at the side of the screen, minus the margin, when the stage is scrolled this far
+    let limit = (arena.wall_x + EDGE_MARGIN - half_width).max(0.0);
+    let left = -half_width + EDGE_MARGIN;
+    let right = half_width - EDGE_MARGIN;
+    // Check if players are at opposite ends of the screen
+    // 0 means player isn't at edge, 1 means player is at left edge, 2 means player is at right edge
+    // 3 and 4 mean player is exactly on the left or right edge
+    let mut at_edges: [u8; 2] = [0; 2];
+    for (id, position) in positions.iter().enumerate() {
+        if position.x < left {
+            at_edges[id] = 1;
+        } else if position.x > right {
+            at_edges[id] = 2;
+        } else if position.x == left {
+            at_edges[id] = 3;
+        } else if position.x == right {
+            at_edges[id] = 4;
+        }
+    }
+
+    let clamp_stage = |stage_x: &mut f32| -> bool {
+        if *stage_x < -limit {
+            *stage_x = -limit;
+            true
+        } else if *stage_x > limit {
+            *stage_x = limit;
+            true
+        } else {
+            false
+        }
+    };
+
+    for id in 0..2 {
+        let other = 1 - id;
+        if (at_edges[id] == 1 || at_edges[id] == 2) && at_edges[other] == 0 {
+            // If the player is at the edge, move the ground and the opponent instead of the player
+            let edge = if at_edges[id] == 1 { left } else { right };
+            let diff = edge - positions[id].x;
+            *stage_x += diff;
+            positions[id].x = edge;
+            if !clamp_stage(stage_x) {
+                positions[other].x += diff;
+            }
+            return;
+        }
+    }
+    if at_edges[0] != 0 && at_edges[1] != 0 {
+        if (at_edges[0] == 1 && at_edges[1] == 3) || (at_edges[0] == 3 && at_edges[1] == 1) {
+            // If both players are at the same edge, move the camera to the edge
+            let (outer, inner) = if at_edges[0] == 1 { (0, 1) } else { (1, 0) };
+            let diff = left - positions[outer].x;
+            positions[outer].x = left;
+            *stage_x += diff;
+            positions[inner].x += diff;
+        } else if (at_edges[0] == 2 && at_edges[1] == 4) || (at_edges[0] == 4 && at_edges[1] == 2) {
+            // If both players are at the same edge, move the camera to the edge
+            let (outer, inner) = if at_edges[0] == 2 { (0, 1) } else { (1, 0) };
+            let diff = right - positions[outer].x;
+            positions[outer].x = right;
+            *stage_x += diff;
+            positions[inner].x += diff;
+        } else {
+            // If players are at opposite edges, don't move
+            for (id, position) in positions.iter_mut().enumerate() {
+                if at_edges[id] == 1 || at_edges[id] == 3 {
+                    position.x = left;
+                } else {
+                    position.x = right;
+                }
+            }
+        }
+    }
+}
+
+/// true if a fighter at `x` stands against a wall of the stage and cannot be pushed further
+///
+/// # Arguments
+///
+/// * `x` - X position of the fighter
+/// * `stage_x` - X position of the ground, see `scroll_stage`
+/// * `arena` - The arena, gives the walls
+pub fn at_wall(x: f32, stage_x: f32, arena: &Arena) -> bool {
+    // the walls move with the ground, and a fighter stopped by one stands right on it
+    (x - stage_x).abs() >= arena.wall_x - 1.0
+}
+
+/// Keeps the fighter between the walls of the stage.
+/// A knocked down fighter flying into a wall bounces off it when the stage allows it.
+///
+/// # Arguments
+///
+/// * `player` - The fighter
+/// * `position` - Position of the fighter, updated in place
+/// * `stage_x` - X position of the ground, see `scroll_stage`
+/// * `arena` - The arena, gives the walls
+///
+/// Returns true when the fighter was stopped by a wall.
+pub fn stop_at_wall(player: &mut Player, position: &mut Vec2, stage_x: f32, arena: &Arena) -> bool {
+    let left = stage_x - arena.wall_x;
+    let right = stage_x + arena.wall_x;
+    if (left..=right).contains(&position.x) {
+        return false;
+    }
+    // the side of the wall the fighter hit, -1 for the left one
+    let side = if position.x < left { -1.0 } else { 1.0 };
+    position.x = position.x.clamp(left, right);
+    if arena.wall_bounce
+        && player.action() == Action::KnockedDown
+        && player.knockback.x * side >= WALL_BOUNCE_SPEED
+    {
+        player.knockback = Vec2::new(-player.knockback.x * WALL_BOUNCE_RATIO, WALL_BOUNCE_LIFT);
+    }
+    true
+}
+
+/// Pushes the fighters away from each other when their bodies overlap.
+///
+/// # Arguments
+///
+/// * `positions` - Positions of both fighters, indexed by player ID
+/// * `at_wall` - Which fighters stand against a wall, a fighter in the corner stays there
+///   and the other one is pushed out of it
+/// * `width` - Width of the pushbox of a fighter, see `PUSHBOX_WIDTH`
+pub fn push_apart(positions: &mut [Vec2; 2], at_wall: [bool; 2], width: f32) {
+    let diff = positions[1].x - positions[0].x;
+    if diff.abs() >= width {
+        return;
+    }
+    let sign = if diff >= 0.0 { 1.0 } else { -1.0 };
+    match at_wall {
+        [true, false] => positions[1].x = positions[0].x + sign * width,
+        [false, true] => positions[0].x = positions[1].x - sign * width,
+        _ => {
+            let center = (positions[0].x + positions[1].x) / 2.0;
+            positions[0].x = center - sign * width / 2.0;
+            positions[1].x = center + sign * width / 2.0;
+        }
+    }
+}
+
+// coefficiency for each attack
+const SKILL_COEFFICIENT: [f32; 4] = [
+    1.0, // punch
+    1.2, // kick
+    1.5, // back kick
+    1.3, // special
+];
+// coefficiency for each body part
+const PARTS_COEFFICIENT: [f32; 4] = [
+    1.5, // head
+    1.0, // body
+    0.9, // arm
+    0.8, // leg
+];
+const DEFENCE_COEFICIENCY: f32 = 20.0;
+const DEFENCE_OFFSET: f32 = 50.0;
+
+/// Damage of a melee hit.
+///
+/// # Arguments
+///
+/// * `attacker_info` - Character and state of the attacker
+/// * `opponent_info` - Character and state of the fighter who was hit
+/// * `opponent_parts` - Body part which was hit
+/// * `handicap` - Handicap of the attacker
+pub fn calculate_damage(
+    attacker_info: (&CharacterProfile, PlayerState),
+    opponent_info: (&CharacterProfile, PlayerState),
+    opponent_parts: &BodyParts,
+    handicap: &Handicap,
+) -> u32 {
+    let attacker_profile = attacker_info.0;
+    let opponent_profile = opponent_info.0;
+    let mut damage = attacker_profile.power * handicap.damage;
+
+    // Apply damage multipliers based on player states
+    if attacker_info.1.check(PlayerState::PUNCHING) {
+        damage *= SKILL_COEFFICIENT[0];
+    } else if attacker_info.1.check(PlayerState::KICKING) {
+        damage *= SKILL_COEFFICIENT[1];
+    } else if attacker_info.1.check(PlayerState::BACK_KICKING) {
+        damage *= SKILL_COEFFICIENT[2];
+    } else if attacker_info.1.check(PlayerState::SPECIAL) {
+        damage *= SKILL_COEFFICIENT[3];
+    }
+
+    // the EX version of a move spent a stock of energy
+    if attacker_info.1.check(PlayerState::EX) {
+        damage *= EX_DAMAGE_RATIO;
+    }
+
+    // If attacker is performes a jumping kick, increase the damage
+    if attacker_info
+        .1
+        .check(PlayerState::JUMP_UP | PlayerState::JUMP_FORWARD)
+    {
+        damage *= 1.5;
+    }
+
+    // Apply damage multipliers based on opponent body parts
+    if opponent_parts.is_head() {
+        damage *= PARTS_COEFFICIENT[0];
+    } else if opponent_parts.is_body() {
+        damage *= PARTS_COEFFICIENT[1];
+    } else if opponent_parts.is_arm() {
+        damage *= PARTS_COEFFICIENT[2];
+    } else {
+        damage *= PARTS_COEFFICIENT[3];
+    }
+
+    let mut defence_bonus = 0.0;
+    if opponent_info.1.check(PlayerState::BEND_DOWN) {
+        defence_bonus += 50.0;
+    }
+
+    // Apply damage reduction based on opponent defense
+    (damage * DEFENCE_COEFICIENCY
+        / (opponent_profile.defense + defence_bonus + DEFENCE_OFFSET))
+        .floor() as u32
+}
+
+// how much a hit of `damage` on `part` fills the dizzy meter, None for a fire arrow or a throw
+fn dizzy_fill(damage: u32, part: Option<&BodyParts>) -> u16 {
+    let ratio = match part {
+        Some(part) if part.is_head() => DIZZY_PARTS[0],
+        Some(part) if part.is_arm() => DIZZY_PARTS[2],
+        Some(part) if part.is_leg() => DIZZY_PARTS[3],
+        _ => DIZZY_PARTS[1],
+    };
+    (damage as f32 * DIZZY_PER_DAMAGE * ratio).round() as u16
+}
+
+/// Applies a landed hit to the victim and stuns it.
+/// The hit fills the dizzy meter, and the fighter gets dizzy instead of stunned when it is full.
+///
+/// # Arguments
+///
+/// * `victim` - The fighter who was hit
+/// * `damage` - Damage of the hit
+/// * `part` - Body part hit by a melee move, None for a fire arrow or a throw
+///
+/// A bending fighter takes the damage but is not stunned, nor is a fighter
+/// still invincible after a dizzy.
+pub fn apply_hit(victim: &mut Player, damage: u32, part: Option<&BodyParts>) {
+    victim.health = victim.health.saturating_sub(damage);
+    if victim.state.check(PlayerState::BEND_DOWN) || victim.invincible > 0 {
+        return;
+    }
+    // the combo which made the fighter dizzy does not fill the meter again
+    if !victim.combo.dizzy {
+        victim.dizzy = victim.dizzy.saturating_add(dizzy_fill(damage, part)).min(DIZZY_MAX);
+        if victim.dizzy == DIZZY_MAX {
+            victim.combo.dizzy = true;
+            victim.enter(Action::Dizzy);
+            victim.set_animation_eased(STUN_POSE2, 0, 6, Easing::EaseOut);
+            return;
+        }
+    }
+    victim.enter(Action::Stun);
+    victim.set_animation_eased(STUN_POSE2, 0, 6, Easing::EaseOut);
+}
+
+/// How a fighter guards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardStance {
+    // standing, the legs are open
+    High,
+    // bending down, the head is open
+    Low,
+}
+
+impl GuardStance {
+    /// true if the stance blocks a hit on `part`
+    pub fn covers(&self, part: &BodyParts) -> bool {
+        match self {
+            GuardStance::High => !part.is_leg(),
+            GuardStance::Low => !part.is_head(),
+        }
+    }
+}
+
+impl Player {
+    /// The guard of the fighter, None when it is not guarding
+    pub fn guard_stance(&self) -> Option<GuardStance> {
+        // the auto-block of the handicap guards as if the button was held
+        let guarding = self.guard_input || self.handicap.auto_block;
+        match self.action() {
+            Action::Guard => Some(GuardStance::High),
+            Action::Idle if self.handicap.auto_block => Some(GuardStance::High),
+            // walking away from the opponent
+            Action::Walk if guarding => Some(GuardStance::High),
+            Action::BendDown if guarding => Some(GuardStance::Low),
+            _ => None,
+        }
+    }
+    /// true while the fighter lies on the floor or has just got up, no move touches it
+    pub fn is_invulnerable(&self) -> bool {
+        self.action() == Action::KnockedDown || self.wakeup > 0
+    }
+}
+
+/// Hits taken in a row while stunned.
+/// The combo ends when the fighter gets out of the stun.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Combo {
+    pub hits: u16,
+    // total damage of the hits
+    pub damage: u32,
+    // the combo made the fighter dizzy, the invincibility starts when it is over
+    pub dizzy: bool,
+}
+
+impl Combo {
+    /// Damage of the next hit of the combo
+    pub fn scale(&self, damage: u32) -> u32 {
+        let scaling = COMBO_SCALING[(self.hits as usize).min(COMBO_SCALING.len() - 1)];
+        (damage as f32 * scaling).floor() as u32
+    }
+}
+
+/// What a hit did to the fighter who received it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitResult {
+    // the hit went through
+    Hit(u32),
+    // the guard took the hit, the value is the chip damage
+    Blocked(u32),
+    // the guard took the hit and was emptied, the fighter is staggered
+    GuardBreak(u32),
+}
+
+/// Applies a hit to the victim through its guard.
+/// A hit on a stunned fighter continues the combo and is scaled down by it.
+///
+/// # Arguments
+///
+/// * `victim` - The fighter who was hit
+/// * `damage` - Damage of the hit when it is not blocked
+/// * `part` - Body part hit by a melee move, None for a fire arrow which any guard blocks
+pub fn receive_hit(victim: &mut Player, damage: u32, part: Option<&BodyParts>) -> HitResult {
+    let damage = victim.scaled_damage(damage);
+    let blocked = victim
+        .guard_stance()
+        .is_some_and(|stance| part.is_none_or(|part| stance.covers(part)));
+    if !blocked {
+        if !matches!(victim.action(), Action::Stun | Action::Dizzy) {
+            victim.combo = Combo::default();
+        }
+        let damage = victim.combo.scale(damage);
+        victim.combo.hits += 1;
+        victim.combo.damage += damage;
+        apply_hit(victim, damage, part);
+        return HitResult::Hit(damage);
+    }
+    let chip = damage / if part.is_some() { MELEE_CHIP_DIVISOR } else { FIRE_CHIP_DIVISOR };
+    victim.health = victim.health.saturating_sub(chip);
+    victim.guard = victim.guard.saturating_sub((damage as u16).saturating_mul(GUARD_DRAIN));
+    if victim.guard > 0 {
+        return HitResult::Blocked(chip);
+    }
+    // guard break, the guard comes back full after the stagger
+    victim.guard = GUARD_MAX;
+    victim.enter(Action::Stun);
+    victim.set_animation_eased(STUN_POSE2, 0, GUARD_BREAK_FRAMES, Easing::EaseOut);
+    HitResult::GuardBreak(chip)
+}
+
+/// Freezes both fighters and sends the victim away after a move connected.
+/// The values come from the frame data of the move.
+///
+/// # Arguments
+///
+/// * `attacker` - The fighter whose move connected
+/// * `victim` - The fighter who received it
+/// * `data` - Frame data of the move
+/// * `result` - What the hit did, a blocked hit pushes less, never lifts the victim
+///   and never knocks it down. The EX version of a move freezes longer and always knocks down.
+/// * `victim_at_edge` - true if the victim stands against an end of the stage,
+///   the attacker is pushed back instead
+pub fn apply_impact(attacker: &mut Player, victim: &mut Player, data: &FrameData, result: HitResult, victim_at_edge: bool) {
+    let ex = attacker.state.check(PlayerState::EX);
+    let hitstop = if ex { data.hitstop + EX_HITSTOP } else { data.hitstop };
+    attacker.hitstop = hitstop;
+    victim.hitstop = hitstop;
+    let sign = if attacker.pose.facing { 1.0 } else { -1.0 };
+    let knockback = match result {
+        HitResult::Blocked(_) => Vec2::new(data.knockback.x * BLOCK_KNOCKBACK_RATIO, 0.0),
+        HitResult::Hit(_) | HitResult::GuardBreak(_) => data.knockback,
+    };
+    if victim_at_edge {
+        victim.knockback = Vec2::new(0.0, knockback.y);
+        attacker.knockback = Vec2::new(-sign * knockback.x, 0.0);
+    } else {
+        victim.knockback = Vec2::new(sign * knockback.x, knockback.y);
+    }
+    // a heavy move knocks down a victim it stunned
+    if (data.knockdown || ex) && matches!(result, HitResult::Hit(_)) && victim.action() == Action::Stun {
+        knock_down(victim);
+    }
+}
+
+/// Freezes and pushes the victim of a fire arrow flying toward the right when `facing` is true
+pub fn apply_fire_impact(victim: &mut Player, facing: bool) {
+    let sign = if facing { 1.0 } else { -1.0 };
+    victim.hitstop = FIRE_HITSTOP;
+    victim.knockback = Vec2::new(sign * FIRE_KNOCKBACK.x, FIRE_KNOCKBACK.y);
+}
+
+/// Tests the hitbox of the attacker's move against the hurtboxes of the victim.
+///
+/// # Arguments
+///
+/// * `attacker` - The fighter who may be attacking
+/// * `attacker_position` - Position of the attacker
+/// * `victim` - The opponent
+/// * `victim_position` - Position of the opponent
+/// * `body` - Sizes of the body parts
+/// * `scale` - Scale of the arena
+///
+/// Returns the body part hit on an active frame of the move, the move cannot hit again after that.
+/// A victim who is knocked down or getting up is never hit.
+pub fn melee_hit(
+    attacker: &mut Player,
+    attacker_position: Vec2,
+    victim: &Player,
+    victim_position: Vec2,
+    body: &BodyLayout,
+    scale: f32,
+) -> Option<BodyParts> {
+    if victim.is_invulnerable() {
+        return None;
+    }
+    // a throw grabs instead of hitting, see throw.rs
+    let attack = attacker
+        .attack
+        .as_mut()
+        .filter(|attack| attack.kind != MoveKind::Throw && attack.is_active())?;
+    let hitbox = attack.hitbox(attacker_position, attacker.pose.facing, scale);
+    let part = find_hit(hitbox, &hurtboxes(&victim.pose, victim_position, body, scale))?;
+    attack.hit = true;
+    Some(part)
+}
+
+/// Damage of a projectile to `victim`, None when the victim dodges it by rolling
+/// or the projectile flies over a knocked down victim
+///
+/// # Arguments
+///
+/// * `victim` - The fighter the projectile reaches
+/// * `damage` - Damage of the projectile, see `ProjectileDef`
+pub fn fire_damage(victim: &Player, damage: u32) -> Option<u32> {
+    if victim.is_invulnerable() {
+        None
+    } else if victim.state.check(PlayerState::BEND_DOWN) {
+        Some(damage * 4 / 5)
+    } else if victim.state.check(PlayerState::ROLL_BACK | PlayerState::ROLL_FORWARD) {
+        // Rolling dodge the fire attack
+        None
+    } else {
+        Some(damage)
+    }
+}
+
+/// Cues of the skill animation.
+/// The simulation decides when they happen, the view decides how they look.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkillCue {
+    // darken the screen with the given alpha
+    Dim(f32),
+    // brighten the curtain color for the lightning flash
+    Flash(f32),
+    ShowName,
+    HideName,
+    // hide or show the fighter using the skill
+    Hide,
+    Show,
+    Thunder(f32),
+    HideThunder,
+    SpawnSoul(Vec2),
+    SpawnFist(f32),
+    DropFist(f32),
+    DespawnFist,
+    // shake the camera with the given amplitude
+    Shake(f32),
+    ResetCamera,
+    Sound(&'static str),
+    Damage(u32),
+    // the damage knocks the opponent down
+    KnockDown,
+    Finished,
+}
+
+/// Advances the skill animation of the fighter by one frame.
+///
+/// # Arguments
+///
+/// * `player` - The fighter using the skill
+/// * `position` - Position of the fighter, updated in place
+/// * `opponent_position` - Position of the opponent
+/// * `ground_y` - Y position of the floor
+/// * `cues` - Cues for the view are pushed here
+///
+/// 魂吸収 waits in phase 3 until every soul is absorbed, see `absorb_soul`.
+pub fn step_skill(player: &mut Player, position: &mut Vec2, opponent_position: Vec2, ground_y: f32, cues: &mut Vec<SkillCue>) {
+    if player.animation.phase == 0 {
+        player.animation.count += 1;
+        // change curtain color to draken the screen
+        cues.push(SkillCue::Dim(player.animation.count as f32 / 60.0));
+        if player.animation.count == 20 {
+            // show skill name
+            cues.push(SkillCue::ShowName);
+            player.animation.phase = 1;
+            player.animation.count = 0;
+        }
+    } else if player.animation.phase == 1 {
+        player.animation.count += 1;
+        if player.animation.count == 60 {
+            cues.push(SkillCue::HideName);
+            player.animation.phase = 2;
+            player.animation.count = 0;
+        }
+    } else if player.animation.phase == 2 {
+        if player.skill() == SkillKind::Thunder {
+            // 神速雷光
+            player.animation.count += 1;
+            cues.push(SkillCue::Dim((player.animation.count + 20) as f32 / 60.0));
+            if player.animation.count == 35 {
+                player.animation.phase = 3;
+                player.animation.count = 0;
+            }
+        } else if player.skill() == SkillKind::SoulAbsorb {
+            // 魂吸収
+            // create a soul on the opponent
+            player.animation.count += 1;
+            cues.push(SkillCue::SpawnSoul(Vec2::new(opponent_position.x, opponent_position.y + 50.0)));
+            if player.animation.count == 100 {
+                player.animation.phase = 3;
+                player.animation.count = 0;
+            }
+        } else if player.skill() == SkillKind::IronFist {
+            // 鉄拳制裁
+            cues.push(SkillCue::SpawnFist(opponent_position.x));
+            player.animation.phase = 3;
+            player.animation.count = 60;
+        }
+    } else if player.animation.phase == 3 {
+        if player.skill() == SkillKind::Thunder {
+            player.animation.count += 1;
+            if player.animation.count == 30 {
+                cues.push(SkillCue::Thunder(position.x));
+                cues.push(SkillCue::Hide);
+                player.sprite = SpriteKind::Punch;
+                player.frame = 20;
+                player.pose.set(player.poses.get(PoseName::Punch));
+                if player.pose.facing {
+                    position.x = opponent_position.x - 100.0;
+                } else {
+                    position.x = opponent_position.x + 100.0;
+                }
+                position.y = opponent_position.y;
+                cues.push(SkillCue::Sound("thunder.ogg"));
+                player.animation.phase = 4;
+                player.animation.count = 0;
+            }
+        } else if player.skill() == SkillKind::IronFist {
+            cues.push(SkillCue::DropFist(200.0 / 60.0));
+            player.animation.count -= 1;
+            if player.animation.count == 0 {
+                // the fist only hits a grounded opponent
+                if opponent_position.y - ground_y < 50.0 {
+                    cues.push(SkillCue::Damage(player.handicap.damage_of(250)));
+                    cues.push(SkillCue::KnockDown);
+                }
+                player.animation.phase = 4;
+                player.animation.count = 0;
+            }
+        }
+    } else if player.animation.phase == 4 {
+        if player.skill() == SkillKind::Thunder {
+            player.animation.count += 1;
+            if player.animation.count <= 5 {
+                cues.push(SkillCue::Flash(player.animation.count as f32 / 5.0));
+            } else {
+                cues.push(SkillCue::Dim(2.0 - player.animation.count as f32 / 5.0));
+            }
+            // earthquake effect
+            cues.push(SkillCue::Shake(100.0));
+            if player.animation.count == 10 {
+                cues.push(SkillCue::Damage(player.handicap.damage_of(200)));
+                cues.push(SkillCue::Show);
+                cues.push(SkillCue::Flash(0.0));
+                cues.push(SkillCue::HideThunder);
+                player.set_animation(player.poses.get(PoseName::Idle), 5, 12);
+                player.velocity = Vec2::ZERO;
+                cues.push(SkillCue::ResetCamera);
+            }
+        } else if player.skill() == SkillKind::SoulAbsorb {
+            player.animation.count += 1;
+            cues.push(SkillCue::Dim((20 - player.animation.count) as f32 / 60.0));
+            if player.animation.count == 20 {
+                player.animation.phase = 7;
+            }
+        } else if player.skill() == SkillKind::IronFist {
+            player.animation.count += 1;
+            cues.push(SkillCue::Dim((20 - player.animation.count) as f32 / 60.0));
+            // earthquake effect
+            cues.push(SkillCue::Shake(50.0));
+            if player.animation.count == 20 {
+                cues.push(SkillCue::DespawnFist);
+                player.animation.phase = 7;
+                player.animation.count = 0;
+                cues.push(SkillCue::ResetCamera);
+            }
+        }
+    } else if player.animation.phase == 5 {
+        if player.skill() == SkillKind::Thunder {
+            player.update_animation();
+            if position.y > ground_y {
+                player.velocity.y -= GRAVITY_ACCEL * 4.0 / FPS;
+                position.y += player.velocity.y;
+                if position.y < ground_y {
+                    position.y = ground_y;
+                }
+            }
+            if player.animation.count == 0 {
+                if position.y == ground_y {
+                    player.set_sprite(SpriteKind::Idle, 0);
+                    player.set_animation(IDLE_POSE2, 7, 15);
+                } else {
+                    // player position have to be reset
+                    player.animation.phase = 6;
+                }
+            }
+        }
+    } else if player.animation.phase == 6 {
+        if player.skill() == SkillKind::Thunder && position.y > ground_y {
+            player.velocity.y -= GRAVITY_ACCEL * 4.0 / FPS;
+            position.y += player.velocity.y;
+            if position.y <= ground_y {
+                position.y = ground_y;
+                player.set_sprite(SpriteKind::Idle, 0);
+                player.set_animation(IDLE_POSE2, 7, 15);
+            }
+        }
+    } else {
+        // finish skill
+        player.animation.phase = 0;
+        player.animation.count = 0;
+        player.enter(Action::Idle);
+        cues.push(SkillCue::Finished);
+    }
+}
+
+/// Moves a soul of 魂吸収 toward `destination`.
+/// `jitter` is a small random offset in [-2, 2] for each axis.
+pub fn step_soul(soul: Vec2, destination: Vec2, jitter: Vec2) -> Vec2 {
+    let direction = (destination - soul).normalize_or_zero();
+    soul + direction * SOUL_SPEED + jitter
+}
+
+/// true when the soul reached the fighter who absorbs it
+pub fn soul_arrived(soul: Vec2, destination: Vec2) -> bool {
+    destination.distance(soul) < 10.0
+}
+
+/// An absorbed soul moves 1 health from the opponent to the user of 魂吸収
+pub fn absorb_soul(player: &mut Player) {
+    if player.skill() == SkillKind::SoulAbsorb {
+        player.health += 1;
+    } else {
+        player.health = player.health.saturating_sub(1);
+    }
+}
+
+/// The winner when the time is up, 0 means draw
+pub fn time_up_winner(health_ratios: [f32; 2]) -> u8 {
+    if health_ratios[0] < health_ratios[1] {
+        2
+    } else if health_ratios[0] == health_ratios[1] {
+        0
+    } else {
+        1
+    }
+}
+
+/// The winner of the whole match from the winners of each round, 0 means draw
+pub fn total_winner(winners: &[u8]) -> u8 {
+    let player1 = winners.iter().filter(|winner| **winner == 1).count();
+    let player2 = winners.iter().filter(|winner| **winner == 2).count();
+    if player1 > player2 {
+        1
+    } else if player1 < player2 {
+        2
+    } else {
+        0
+    }
+}
+
+/// health of the fighter relative to the maximum health of the character
+pub fn health_ratio(player: &Player) -> f32 {
+    player.health as f32 / player.max_health() as f32
+}
+
+/// Size of the arena a fight takes place in, the stage in world coordinates.
+/// The view builds it from the stage and the layout, see `StageDef::arena`.
+#[derive(Debug, Clone, Copy)]
+pub struct Arena {
+    // half of the visible width, fighters scroll the stage beyond it
+    pub half_width: f32,
+    pub ground_y: f32,
+    // distance of each fighter from the center at the beginning of a round
+    pub spawn_x: f32,
+    // distance of each wall from the center of the stage, see `stop_at_wall`
+    pub wall_x: f32,
+    pub wall_bounce: bool,
+    pub body: BodyLayout,
+    pub scale: f32,
+    // the fighters walk slower in the arena of a phone, see `CharacterProfile::agility_in`
+    pub profile: LayoutProfile,
+}
+
+impl Default for Arena {
+    // the first stage on a full HD screen with the desktop layout
+    fn default() -> Self {
+        let stage = stage();
+        Self {
+            half_width: 960.0,
+            ground_y: stage.floor_y,
+            spawn_x: stage.spawn_x,
+            wall_x: stage.wall_x,
+            wall_bounce: stage.wall_bounce,
+            body: DESKTOP_BODY,
+            scale: 1.0,
+            profile: LayoutProfile::Desktop,
+        }
+    }
+}
+
+/// A soul of 魂吸収 flying toward the fighter who absorbs it
+#[derive(Debug, Clone, Copy)]
+pub struct Soul {
+    // the view follows the soul by it
+    pub id: u32,
+    pub position: Vec2,
+}
+
+/// A whole match of two fighters.
+///
+/// `step` advances the match by one logic frame (1/60 s), so the outcome of a fight only
+/// depends on the inputs and the seed. The game holds it in the `Game` resource, steps it
+/// and only shows it, see player.rs.
+pub struct Match {
+    pub players: [Player; 2],
+    pub positions: [Vec2; 2],
+    pub stage_x: f32,
+    pub projectiles: Vec<Projectile>,
+    pub souls: Vec<Soul>,
+    // 0 means normal fight and n means player n is using the skill
+    pub skill: u8,
+    pub round: u8,
+    // winner of each round played, 0 means draw
+    pub winners: Vec<u8>,
+    // true if the round ended with a KO
+    pub win_types: Vec<bool>,
+    // frames left in the round, None without time limit
+    pub time_left: Option<u32>,
+    // sudden death rounds played, they are always the last rounds of the match
+    pub sudden_deaths: u8,
+    pub arena: Arena,
+    pub rules: MatchRules,
+    // fighters of each side in order, only the first one fights unless it is a team battle,
+    // the opponents of a survival come one after another from the second side
+    pub teams: [Vec<Arc<CharacterProfile>>; 2],
+    // player 1 fights the bots one after another, see survival.rs
+    pub survival: bool,
+    round_over: bool,
+    seed: u32,
+    // id of the last fire arrow or soul
+    last_id: u32,
+}
+
+impl Match {
+    /// A match at the beginning of its first round
+    ///
+    /// # Arguments
+    ///
+    /// * `teams` - Fighters of each side in order, a single one unless it is a team battle
+    ///   or the opponents of a survival
+    /// * `handicaps` - Handicap of each fighter
+    /// * `poses` - Poses the fighters move with
+    /// * `arena` - The arena of the fight
+    /// * `rules` - Rounds, timer, starting meter and damage of the match
+    /// * `survival` - true for a survival, the second side is the bots in order
+    /// * `seed` - Seed of the random numbers of the simulation
+    pub fn new(
+        teams: [Vec<CharacterProfile>; 2],
+        handicaps: [Handicap; 2],
+        poses: PoseSet,
+        arena: Arena,
+        rules: MatchRules,
+        survival: bool,
+        seed: u32,
+    ) -> Self {
+        let teams = teams.map(|team| team.into_iter().map(Arc::new).collect::<Vec<_>>());
+        let mut new_match = Self {
+            players: [Player::new(teams[0][0].clone(), poses), Player::new_opposite(teams[1][0].clone(), poses)],
+            positions: [Vec2::ZERO; 2],
+            stage_x: 0.0,
+            projectiles: Vec::new(),
+            souls: Vec::new(),
+            skill: 0,
+            round: 1,
+            winners: Vec::new(),
+            win_types: Vec::new(),
+            time_left: rules.round_frames(false),
+            sudden_deaths: 0,
+            arena,
+            rules,
+            teams,
+            survival,
+            round_over: false,
+            // xorshift must not start from 0
+            seed: seed.max(1),
+            last_id: 0,
+        };
+        for (player, handicap) in new_match.players.iter_mut().zip(handicaps) {
+            player.apply_handicap(handicap);
+            player.apply_rules(&rules);
+        }
+        new_match.reset_round();
+        new_match
+    }
+
+    // a survival has a single fighter on each side whatever the format is
+    fn is_team_battle(&self) -> bool {
+        self.rules.is_team_battle() && !self.survival
+    }
+
+    fn reset_round(&mut self) {
+        let sudden_death = self.is_sudden_death();
+        let team_battle = self.is_team_battle();
+        let positions = team_positions(&self.winners);
+        let defeated = survival::defeated(&self.winners);
+        for (id, player) in self.players.iter_mut().enumerate() {
+            if self.survival && id == 0 {
+                // the player keeps the health left and recovers part of it
+                let health = player.health;
+                player.reset(0, &self.rules);
+                player.health = survival::recovered_health(health, player.max_health());
+            } else if self.survival {
+                // the next opponent comes in with full health, after the last one the first again
+                let opponents = &self.teams[1];
+                let opponent = &opponents[defeated as usize % opponents.len()];
+                if opponent.id != player.character_id {
+                    player.change_character(opponent.clone());
+                }
+                player.reset(1, &self.rules);
+            } else if team_battle {
+                // the last fighters of the teams fight the sudden death
+                let team = &self.teams[id];
+                player.start_team_round(id as u8, &team[positions[id].min(team.len() - 1)], &self.rules);
+            } else {
+                player.reset(id as u8, &self.rules);
+            }
+            if sudden_death {
+                player.start_sudden_death();
+            }
+            player.agility = player.profile().agility_in(self.arena.profile);
+        }
+        self.positions = [
+            Vec2::new(-self.arena.spawn_x, self.arena.ground_y),
+            Vec2::new(self.arena.spawn_x, self.arena.ground_y),
+        ];
+        self.stage_x = 0.0;
+        self.projectiles.clear();
+        self.souls.clear();
+        self.skill = 0;
+        self.time_left = self.rules.round_frames(sudden_death);
+        self.round_over = false;
+    }
+
+    /// Starts the next round, returns false when the match is over
+    pub fn next_round(&mut self) -> bool {
+        let team_sizes = self.teams.each_ref().map(|team| team.len());
+        // a match which would end in a draw goes on with a sudden death round
+        let sudden_death =
+            !self.survival && self.rules.needs_sudden_death(&self.winners, self.sudden_deaths, team_sizes);
+        // a survival goes on as long as the player wins, one round for each opponent
+        let over = if self.survival {
+            survival::is_over(&self.winners)
+        } else {
+            self.rules.is_over(&self.winners, team_sizes) && !sudden_death
+        };
+        if over {
+            return false;
+        }
+        self.round += 1;
+        if sudden_death {
+            self.sudden_deaths += 1;
+        }
+        self.reset_round();
+        true
+    }
+
+    /// The winner of the whole match, 0 means draw
+    pub fn get_winner(&self) -> u8 {
+        total_winner(&self.winners)
+    }
+
+    /// Whether the round is decided, it stays so when the match is over
+    pub fn is_round_over(&self) -> bool {
+        self.round_over
+    }
+
+    /// The winner of the current round, 0 means draw or not decided yet
+    pub fn round_winner(&self) -> u8 {
+        self.winners.get(self.round as usize - 1).copied().unwrap_or(0)
+    }
+
+    /// true if the current round is a sudden death round
+    pub fn is_sudden_death(&self) -> bool {
+        self.sudden_deaths > 0
+    }
+
+    /// Starts the winner and loser pose of the fighters after a KO, `step` plays them.
+    /// There are no winner and loser sprites on the web.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start_final_poses(&mut self) {
+        let winner = self.round_winner();
+        for (id, player) in self.players.iter_mut().enumerate() {
+            let sprite = if winner == id as u8 + 1 { SpriteKind::Victory } else { SpriteKind::Defeated };
+            player.set_sprite(sprite, 0);
+            player.animation.count = player.profile().frames(sprite) as u8;
+        }
+    }
+
+    // deterministic random number in [0, 1)
+    fn rand(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.last_id = self.last_id.wrapping_add(1);
+        self.last_id
+    }
+
+    /// Advances the match by one logic frame and returns what happened.
+    /// Once the round is over only the final poses are played until `next_round`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Buttons of both fighters on this frame
+    pub fn step(&mut self, inputs: [FighterInput; 2]) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.round_over {
+            self.step_final_poses(&mut events);
+            return events;
+        }
+        self.step_souls(&mut events);
+
+        if self.skill != 0 {
+            self.step_skill(&mut events);
+            // a KO by the skill is decided once its animation is over
+            if self.skill == 0 {
+                self.check_round_over(&mut events);
+            }
+            return events;
+        }
+
+        for id in 0..2 {
+            let opponent_x = self.positions[1 - id].x;
+            face_opponent(&mut self.players[id], &mut self.positions[id], opponent_x);
+        }
+        for (id, input) in inputs.iter().enumerate() {
+            if self.skill == 0 && apply_input(&mut self.players[id], input) {
+                self.skill = id as u8 + 1;
+                events.push(SimEvent::SkillStarted { id });
+            }
+        }
+        if self.skill != 0 {
+            return events;
+        }
+        for id in 0..2 {
+            if let Some(SimEvent::FireLaunched { id, position, facing, ex }) =
+                step_fighter(id, &mut self.players[id], &mut self.positions[id], self.arena.ground_y, true)
+            {
+                let def = self.players[id].projectile_def(ex);
+                let mut projectile = Projectile::new(id, position, facing, def);
+                projectile.id = self.next_id();
+                self.projectiles.push(projectile);
+                events.push(SimEvent::FireLaunched { id, position, facing, ex });
+            }
+            check_landing(&mut self.players[id], &mut self.positions[id], self.arena.ground_y);
+        }
+        self.check_throws(&mut events);
+        // except while one is carried over the head of the other
+        if self.players.iter().all(|player| player.grab == 0) {
+            push_apart(&mut self.positions, [false; 2], PUSHBOX_WIDTH * self.arena.scale);
+        }
+        scroll_stage(&mut self.positions, &mut self.stage_x, &self.arena);
+        let mut at_wall = [false; 2];
+        for (id, player) in self.players.iter_mut().enumerate() {
+            at_wall[id] = stop_at_wall(player, &mut self.positions[id], self.stage_x, &self.arena);
+        }
+        if at_wall.contains(&true) && self.players.iter().all(|player| player.grab == 0) {
+            push_apart(&mut self.positions, at_wall, PUSHBOX_WIDTH * self.arena.scale);
+        }
+        self.check_melee(&mut events);
+        self.step_projectiles(&mut events);
+
+        self.time_left = self.time_left.map(|time_left| time_left.saturating_sub(1));
+        self.check_round_over(&mut events);
+        events
+    }
+
+    fn check_throws(&mut self, events: &mut Vec<SimEvent>) {
+        for attacker in 0..2 {
+            let victim = 1 - attacker;
+            let [first, second] = &mut self.players;
+            let [first_position, second_position] = &mut self.positions;
+            let (throwing, opponent, throwing_position, opponent_position) = if attacker == 0 {
+                (first, second, first_position, second_position)
+            } else {
+                (second, first, second_position, first_position)
+            };
+            match step_throw(
+                throwing,
+                throwing_position,
+                opponent,
+                opponent_position,
+                self.arena.ground_y,
+                &self.arena.body,
+                self.arena.scale,
+            ) {
+                Some(ThrowResult::Teched) => events.push(SimEvent::ThrowTeched { attacker, victim }),
+                Some(ThrowResult::Landed(damage)) => events.push(SimEvent::Thrown { attacker, victim, damage }),
+                _ => {}
+            }
+        }
+    }
+
+    // player 1 attacks first, a stunned fighter cannot hit back on the same frame
+    fn check_melee(&mut self, events: &mut Vec<SimEvent>) {
+        for attacker in 0..2 {
+            let victim = 1 - attacker;
+            let state = self.players[attacker].state;
+            let [first, second] = &mut self.players;
+            let (attacking, opponent) = if attacker == 0 { (first, &*second) } else { (second, &*first) };
+            let Some(part) = melee_hit(
+                attacking,
+                self.positions[attacker],
+                opponent,
+                self.positions[victim],
+                &self.arena.body,
+                self.arena.scale,
+            ) else {
+                continue;
+            };
+            let damage = calculate_damage(
+                (self.players[attacker].profile(), state),
+                (self.players[victim].profile(), self.players[victim].state),
+                &part,
+                &self.players[attacker].handicap,
+            );
+            let result = receive_hit(&mut self.players[victim], damage, Some(&part));
+            let at_edge = at_wall(self.positions[victim].x, self.stage_x, &self.arena);
+            let [first, second] = &mut self.players;
+            let (attacking, opponent) = if attacker == 0 { (first, second) } else { (second, first) };
+            if let Some(attack) = attacking.attack {
+                apply_impact(attacking, opponent, attack.kind.frame_data(), result, at_edge);
+            }
+            events.push(match result {
+                HitResult::Hit(damage) => SimEvent::Hit {
+                    attacker,
+                    victim,
+                    damage,
+                    head: part.is_head(),
+                    combo: self.players[victim].combo.hits,
+                },
+                HitResult::Blocked(damage) => SimEvent::Blocked { attacker, victim, damage, broken: false },
+                HitResult::GuardBreak(damage) => SimEvent::Blocked { attacker, victim, damage, broken: true },
+            });
+        }
+    }
+
+    fn step_projectiles(&mut self, events: &mut Vec<SimEvent>) {
+        for projectile in self.projectiles.iter_mut() {
+            projectile.step();
+        }
+        for first in 0..self.projectiles.len() {
+            for second in first + 1..self.projectiles.len() {
+                let (before, after) = self.projectiles.split_at_mut(second);
+                if clash(&mut before[first], &mut after[0]) {
+                    let position = (before[first].position + after[0].position) / 2.0;
+                    events.push(SimEvent::FireClash { position });
+                }
+            }
+        }
+        for projectile in self.projectiles.iter_mut() {
+            for id in 0..2 {
+                if projectile.reflect(id, &self.players[id], self.positions[id], self.arena.scale) {
+                    events.push(SimEvent::FireReflected { owner: id });
+                }
+            }
+        }
+
+        let mut remaining = Vec::new();
+        for mut projectile in self.projectiles.drain(..) {
+            let victim = 1 - projectile.owner;
+            if projectile.reaches(self.positions[victim]) {
+                if let Some(damage) = fire_damage(&self.players[victim], projectile.def.damage) {
+                    let owner = projectile.owner;
+                    let result = receive_hit(&mut self.players[victim], damage, None);
+                    apply_fire_impact(&mut self.players[victim], projectile.facing());
+                    projectile.hit();
+                    events.push(match result {
+                        HitResult::Hit(damage) => SimEvent::FireHit { owner, victim, damage },
+                        HitResult::Blocked(damage) => SimEvent::Blocked { attacker: owner, victim, damage, broken: false },
+                        HitResult::GuardBreak(damage) => SimEvent::Blocked { attacker: owner, victim, damage, broken: true },
+                    });
+                }
+            }
+            if projectile.is_gone(self.stage_x, &self.arena) {
+                events.push(SimEvent::FireVanished { owner: projectile.launcher });
+                continue;
+            }
+            remaining.push(projectile);
+        }
+        self.projectiles = remaining;
+    }
+
+    // the skill animation stops the fight, every cue of it goes to the view
+    fn step_skill(&mut self, events: &mut Vec<SimEvent>) {
+        let id = self.skill as usize - 1;
+        let opponent = 1 - id;
+        let mut cues = Vec::new();
+        let opponent_position = self.positions[opponent];
+        step_skill(&mut self.players[id], &mut self.positions[id], opponent_position, self.arena.ground_y, &mut cues);
+        for cue in cues {
+            match cue {
+                SkillCue::SpawnSoul(position) => {
+                    let soul = Soul { id: self.next_id(), position };
+                    self.souls.push(soul);
+                }
+                SkillCue::Damage(damage) => {
+                    let damage = self.players[opponent].scaled_damage(damage);
+                    self.players[opponent].health = self.players[opponent].health.saturating_sub(damage);
+                    events.push(SimEvent::SkillHit { attacker: id, victim: opponent, damage });
+                }
+                SkillCue::KnockDown => knock_down(&mut self.players[opponent]),
+                SkillCue::Finished => self.skill = 0,
+                _ => {}
+            }
+            events.push(SimEvent::Cue { id, cue });
+        }
+        // 魂吸収 ends when all the souls are absorbed
+        if self.players[id].skill() == SkillKind::SoulAbsorb
+            && self.players[id].animation.phase == 3
+            && self.souls.is_empty()
+        {
+            self.players[id].animation.phase = 4;
+        }
+    }
+
+    fn step_souls(&mut self, events: &mut Vec<SimEvent>) {
+        let Some(id) = self.players.iter().position(|player| player.skill() == SkillKind::SoulAbsorb) else {
+            return;
+        };
+        let destination = self.positions[id] + Vec2::new(0.0, 50.0);
+        let mut souls = std::mem::take(&mut self.souls);
+        let before = souls.len();
+        souls.retain(|soul| !soul_arrived(soul.position, destination));
+        for _ in souls.len()..before {
+            for player in self.players.iter_mut() {
+                absorb_soul(player);
+            }
+            events.push(SimEvent::SoulAbsorbed { id });
+        }
+        for soul in souls.iter_mut() {
+            let jitter = Vec2::new(self.rand() * 4.0 - 2.0, self.rand() * 4.0 - 2.0);
+            soul.position = step_soul(soul.position, destination, jitter);
+        }
+        self.souls = souls;
+    }
+
+    fn step_final_poses(&mut self, events: &mut Vec<SimEvent>) {
+        for id in 0..2 {
+            if self.players[id].animation.count != 0
+                && step_final_pose(&mut self.players[id], &mut self.positions[id], self.arena.ground_y)
+            {
+                events.push(SimEvent::PoseFinished { id });
+            }
+        }
+    }
+
+    fn check_round_over(&mut self, events: &mut Vec<SimEvent>) {
+        let (winner, ko) = if let Some(loser) = self.players.iter().position(|player| player.health == 0) {
+            (if loser == 0 { 2 } else { 1 }, true)
+        } else if self.time_left == Some(0) {
+            (time_up_winner([health_ratio(&self.players[0]), health_ratio(&self.players[1])]), false)
+        } else {
+            return;
+        };
+        self.winners.push(winner);
+        self.win_types.push(ko);
+        self.round_over = true;
+        events.push(SimEvent::RoundOver { winner, ko });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_match(seed: u32) -> Match {
+        Match::new(
+            [vec![character(0).clone()], vec![character(1).clone()]],
+            [Handicap::default(); 2],
+            PoseSet::default(),
+            Arena::default(),
+            MatchRules::default(),
+            false,
+            seed,
+        )
+    }
+
+    // steps the match `frames` times without any button and returns what happened
+    fn wait(game: &mut Match, frames: usize) -> Vec<SimEvent> {
+        (0..frames).flat_map(|_| game.step([FighterInput::default(); 2])).collect()
+    }
+
+    // player 1 punches player 2 standing right in front of it
+    fn punch(game: &mut Match) -> Vec<SimEvent> {
+        game.positions[1].x = game.positions[0].x + PUSHBOX_WIDTH;
+        let mut events = wait(game, 1);
+        let punch = FighterInput {
+            punch: true,
+            ..Default::default()
+        };
+        events.extend(game.step([punch, FighterInput::default()]));
+        events.extend(wait(game, 60));
+        events
+    }
+
+    // player 1 grabs player 2 standing right in front of it
+    fn grab(game: &mut Match) -> Vec<SimEvent> {
+        game.positions[1].x = game.positions[0].x + PUSHBOX_WIDTH;
+        let mut events = wait(game, 1);
+        let throw = FighterInput {
+            throw: true,
+            ..Default::default()
+        };
+        events.extend(game.step([throw, FighterInput::default()]));
+        for _ in 0..30 {
+            if game.players[0].grab != 0 {
+                break;
+            }
+            events.extend(wait(game, 1));
+        }
+        assert_eq!(game.players[1].action(), Action::Thrown, "the throw did not grab");
+        events
+    }
+
+    #[test]
+    fn a_punch_deals_damage() {
+        let mut game = new_match(1);
+        wait(&mut game, 20);
+        let health = game.players[1].health;
+        let events = punch(&mut game);
+        let damage = events
+            .iter()
+            .find_map(|event| match event {
+                SimEvent::Hit { attacker: 0, victim: 1, damage, .. } => Some(*damage),
+                _ => None,
+            })
+            .expect("the punch did not hit");
+        assert!(damage > 0);
+        assert_eq!(game.players[1].health, health - damage);
+        assert_eq!(game.players[0].health, game.players[0].max_health());
+    }
+
+    #[test]
+    fn a_ko_ends_the_round() {
+        let mut game = new_match(1);
+        wait(&mut game, 20);
+        game.players[1].health = 1;
+        let events = punch(&mut game);
+        assert!(events.contains(&SimEvent::RoundOver { winner: 1, ko: true }));
+        assert_eq!(game.round_winner(), 1);
+        assert!(game.win_types[0]);
+
+        // nothing happens any more until the next round
+        let positions = game.positions;
+        let punch = FighterInput {
+            punch: true,
+            ..Default::default()
+        };
+        assert!(game.step([FighterInput::default(), punch]).is_empty());
+        assert_eq!(game.positions, positions);
+
+        assert!(game.next_round());
+        assert_eq!(game.round, 2);
+        assert_eq!(game.round_winner(), 0);
+        assert_eq!(game.players[1].health, game.players[1].max_health());
+    }
+
+    #[test]
+    fn a_throw_carries_the_victim_and_drops_it() {
+        let mut game = new_match(1);
+        wait(&mut game, 20);
+        let health = game.players[1].health;
+        grab(&mut game);
+        // the victim goes up over the head of the thrower
+        wait(&mut game, 10);
+        assert!(game.positions[1].y > game.arena.ground_y);
+        let events = wait(&mut game, 60);
+        let damage = events
+            .iter()
+            .find_map(|event| match event {
+                SimEvent::Thrown { attacker: 0, victim: 1, damage } => Some(*damage),
+                _ => None,
+            })
+            .expect("the throw did not land");
+        assert!(damage > 0);
+        assert_eq!(game.players[1].health, health - damage);
+        assert_eq!(game.players[0].grab, 0);
+        assert_eq!(game.positions[1].y, game.arena.ground_y);
+    }
+
+    #[test]
+    fn a_throw_is_teched_in_the_first_frames_only() {
+        let tech = FighterInput {
+            throw: true,
+            ..Default::default()
+        };
+        let mut game = new_match(1);
+        wait(&mut game, 20);
+        let health = game.players[1].health;
+        grab(&mut game);
+        let events = game.step([FighterInput::default(), tech]);
+        assert_eq!(events, vec![SimEvent::ThrowTeched { attacker: 0, victim: 1 }]);
+        assert_eq!(game.players[0].grab, 0);
+        assert_eq!(game.players.each_ref().map(|player| player.action()), [Action::Idle; 2]);
+        assert_eq!(game.players[1].health, health);
+
+        // too late, the victim is carried on
+        let mut game = new_match(1);
+        wait(&mut game, 20);
+        grab(&mut game);
+        wait(&mut game, THROW_TECH_FRAMES as usize);
+        let events = game.step([FighterInput::default(), tech]);
+        assert!(!events.contains(&SimEvent::ThrowTeched { attacker: 0, victim: 1 }));
+        assert_eq!(game.players[1].action(), Action::Thrown);
+        assert!(wait(&mut game, 60).iter().any(|event| matches!(event, SimEvent::Thrown { .. })));
+    }
+
+    #[test]
+    fn the_fighters_start_in_the_idle_pose_facing_each_other() {
+        let mut game = new_match(1);
+        let idle = game.players[0].poses.get(PoseName::Idle);
+        assert_eq!(game.players[0].pose, idle);
+        assert_eq!(game.players[1].pose, Pose { facing: false, ..idle });
+        game.players[1].pose = PoseName::Punch.fallback();
+        game.players[1].reset(1, &MatchRules::default());
+        assert_eq!(game.players[1].pose, Pose { facing: false, ..idle });
+    }
+
+    #[test]
+    fn the_bots_of_a_survival_come_in_order() {
+        let mut game = Match::new(
+            [vec![character(0).clone()], vec![character(1).clone(), character(2).clone()]],
+            [Handicap::default(); 2],
+            PoseSet::default(),
+            Arena::default(),
+            MatchRules::default(),
+            true,
+            1,
+        );
+        for opponent in [1, 2, 1] {
+            assert_eq!(game.players[1].character_id, opponent);
+            wait(&mut game, 20);
+            game.players[1].health = 1;
+            punch(&mut game);
+            assert!(game.next_round());
+        }
+    }
+
+    #[test]
+    fn the_round_ends_when_the_time_is_up() {
+        let mut game = new_match(1);
+        game.time_left = Some(3);
+        assert!(wait(&mut game, 2).is_empty());
+        assert_eq!(game.step([FighterInput::default(); 2]), vec![SimEvent::RoundOver { winner: 0, ko: false }]);
+        assert_eq!(game.time_left, Some(0));
+        assert!(!game.win_types[0]);
+    }
+
+    #[test]
+    fn the_same_inputs_give_the_same_fight() {
+        // buttons pressed at random but the same ones for both matches,
+        // the fighters mostly walk toward each other
+        let mut state = 12345u32;
+        let inputs: Vec<[FighterInput; 2]> = (0..1200)
+            .map(|_| {
+                [false, true].map(|right_side| {
+                    state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                    let bits = state >> 16;
+                    let forward = bits & 0x3 != 0;
+                    FighterInput {
+                        left: forward == right_side,
+                        right: forward != right_side,
+                        down: bits & 0x1c == 0x4,
+                        jump: bits & 0x3e0 == 0x20,
+                        punch: bits & 0x1c00 == 0x400,
+                        kick: bits & 0x1c00 == 0x800,
+                        fire: bits & 0xe000 == 0x2000,
+                        ..Default::default()
+                    }
+                })
+            })
+            .collect();
+        let run = || {
+            let mut game = Match::new(
+                [vec![character(0).clone()], vec![character(2).clone()]],
+                [Handicap::default(); 2],
+                PoseSet::default(),
+                Arena::default(),
+                MatchRules::default(),
+                false,
+                7,
+            );
+            let events = inputs.iter().flat_map(|input| game.step(*input)).collect::<Vec<_>>();
+            (events, game.positions, game.players.each_ref().map(|player| player.health))
+        };
+        let (events, positions, health) = run();
+        assert!(events.iter().any(|event| matches!(event, SimEvent::Hit { .. } | SimEvent::FireHit { .. })));
+        assert_eq!(run(), (events, positions, health));
+    }
+}
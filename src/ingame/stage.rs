@@ -0,0 +1,61 @@
+// Stages.
+//
+// A stage is the floor the fighters stand on and the two walls at its ends. The values
+// are in px of the desktop layout, measured from the center of the background image, so
+// they do not depend on the size of the window: the floor stays on the floor of the picture
+// and the walls stay at its ends whatever the resolution. The view scales them with
+// `Layout::scale` into the `Arena` the `Match` is fought in.
+// The camera follows the fighters by scrolling the background (see `scroll_stage`), it
+// stops when a wall reaches the side of the screen.
+use super::sim::Arena;
+use crate::layout::Layout;
+
+/// A place to fight in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageDef {
+    pub name: &'static str,
+    // background image, found with the image prefix of the layout
+    pub image: &'static str,
+    // distance of each wall from the center of the stage
+    pub wall_x: f32,
+    // height of the floor from the center of the background image
+    pub floor_y: f32,
+    // distance of each fighter from the center at the beginning of a round
+    pub spawn_x: f32,
+    // a fighter knocked down into a wall bounces off it, see `stop_at_wall`
+    pub wall_bounce: bool,
+}
+
+pub const STAGES: [StageDef; 1] = [StageDef {
+    name: "Arena",
+    image: "background.png",
+    wall_x: 1900.0,
+    floor_y: -270.0,
+    spawn_x: 500.0,
+    wall_bounce: true,
+}];
+
+/// The stage every fight takes place in for now
+pub fn stage() -> &'static StageDef {
+    &STAGES[0]
+}
+
+impl StageDef {
+    /// The arena of the stage in world coordinates of the view.
+    ///
+    /// # Arguments
+    /// * `layout` - Layout of the screen, gives the scale and where the background is
+    /// * `half_width` - Half of the visible width
+    pub fn arena(&self, layout: &Layout, half_width: f32) -> Arena {
+        Arena {
+            half_width,
+            ground_y: layout.background_y + self.floor_y * layout.scale,
+            spawn_x: self.spawn_x * layout.scale,
+            wall_x: self.wall_x * layout.scale,
+            wall_bounce: self.wall_bounce,
+            body: layout.body,
+            scale: layout.scale,
+            profile: layout.profile,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// Survival mode.
+//
+// The player fights one bot after another, a single round each, until a fight is not won.
+// The health left carries over to the next fight with part of it recovered, and the bots
+// get stronger: every opponent is the next character of the roster, and the level goes up
+// every few opponents. The `Match` decides from the winners of the fights so far.
+use super::agent::Level;
+use crate::character_def::characters;
+
+// part of the maximum health the player recovers between two fights
+pub const SURVIVAL_RECOVERY: f32 = 0.3;
+// opponents to defeat before the bot gets one level stronger
+const OPPONENTS_PER_LEVEL: u32 = 2;
+
+/// true when the run is over, the player did not win the last fight
+///
+/// # Arguments
+///
+/// * `winners` - Winner of each fight so far, 0 means draw
+pub fn is_over(winners: &[u8]) -> bool {
+    winners.last().is_some_and(|winner| *winner != 1)
+}
+
+/// Number of opponents the player defeated
+pub fn defeated(winners: &[u8]) -> u32 {
+    winners.iter().filter(|winner| **winner == 1).count() as u32
+}
+
+/// Level of the bot after `defeated` opponents, starting from the level of the settings
+pub fn opponent_level(start: Level, defeated: u32) -> Level {
+    Level::from((start as u32 + defeated / OPPONENTS_PER_LEVEL).min(Level::Hard as u32))
+}
+
+/// Characters of the bots in the order they come, the run starts over from the first one
+/// after the last. The bots go through the roster in order from the first one, leaving out
+/// the character of the player as long as there is another one.
+///
+/// # Arguments
+///
+/// * `first` - Character of the first bot
+/// * `player` - Character of the player
+pub fn opponents(first: isize, player: isize) -> Vec<isize> {
+    let others = (0..characters().len() as isize)
+        .filter(|character_id| *character_id != player)
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        return vec![first];
+    }
+    let start = others.iter().position(|character_id| *character_id == first).unwrap_or(0);
+    others.iter().cycle().skip(start).take(others.len()).copied().collect()
+}
+
+/// Health the player starts the next fight with
+///
+/// # Arguments
+///
+/// * `health` - Health left at the end of the last fight
+/// * `max_health` - Maximum health of the player
+pub fn recovered_health(health: u32, max_health: u32) -> u32 {
+    (health + (max_health as f32 * SURVIVAL_RECOVERY).round() as u32).min(max_health)
+}
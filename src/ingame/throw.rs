@@ -0,0 +1,163 @@
+// Throws.
+//
+// A throw is a melee move whose hitbox grabs instead of hitting. It ignores the guard
+// and the damage reduction of bending down, but only reaches an opponent standing on the
+// ground right in front of the thrower. Once grabbed, the thrower carries the victim over
+// the head and drops it behind, moving both fighters itself. Pressing throw during the
+// first frames of the grab breaks it (throw tech) and pushes both fighters apart.
+use super::{fighter_state::Action, frame_data::*, pose::*, sim::*};
+use crate::layout::BodyLayout;
+use bevy::math::Vec2;
+use std::f32::consts::PI;
+
+// frames after the grab in which the victim can still break the throw
+pub const THROW_TECH_FRAMES: u16 = 10;
+// frames it takes to carry the victim over the head
+const THROW_CARRY_FRAMES: u16 = 30;
+// damage of a landed throw, neither the defence nor a combo reduce it
+pub const THROW_DAMAGE: u32 = 120;
+// distance of the victim from the thrower at the start and at the end of the carry
+const THROW_REACH: f32 = 90.0;
+// height of the top of the arc the victim is carried along
+const THROW_HEIGHT: f32 = 160.0;
+// each fighter steps back this far when the throw is teched
+const TECH_PUSH: f32 = 60.0;
+
+/// What a throw did on this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrowResult {
+    // the victim was grabbed and is being carried
+    Grabbed,
+    // the victim broke the throw
+    Teched,
+    // the victim hit the floor, the value is the damage
+    Landed(u32),
+}
+
+/// true if `victim` can be grabbed: standing, walking, guarding or bending down on the
+/// ground, and neither invincible after a dizzy nor getting up from a knockdown
+pub fn throwable(victim: &Player, position: Vec2, ground_y: f32) -> bool {
+    position.y <= ground_y && victim.invincible == 0 && !victim.is_invulnerable() && victim.can_enter(Action::Thrown)
+}
+
+/// Advances the throw of `attacker` on `victim` by one frame.
+/// On the active frames of the throw it grabs the victim, then carries it over the head
+/// and drops it behind the attacker.
+///
+/// # Arguments
+///
+/// * `attacker` - The fighter who may be throwing
+/// * `attacker_position` - Position of the attacker, updated in place
+/// * `victim` - The opponent
+/// * `victim_position` - Position of the opponent, updated in place
+/// * `ground_y` - Y position of the floor
+/// * `body` - Sizes of the body parts
+/// * `scale` - Scale of the arena
+pub fn step_throw(
+    attacker: &mut Player,
+    attacker_position: &mut Vec2,
+    victim: &mut Player,
+    victim_position: &mut Vec2,
+    ground_y: f32,
+    body: &BodyLayout,
+    scale: f32,
+) -> Option<ThrowResult> {
+    if attacker.grab == 0 {
+        if victim.action() == Action::Thrown {
+            // the thrower was knocked out of the throw, the victim falls down
+            victim.enter(Action::Stun);
+            victim.set_animation_eased(STUN_POSE2, 0, 6, Easing::EaseOut);
+            return None;
+        }
+        return grab(attacker, *attacker_position, victim, *victim_position, ground_y, body, scale)
+            .then_some(ThrowResult::Grabbed);
+    }
+    if victim.action() != Action::Thrown {
+        // the victim was knocked out of the throw by a fire arrow
+        release(attacker);
+        return None;
+    }
+    let sign = if attacker.pose.facing { 1.0 } else { -1.0 };
+    if victim.throw_tech && attacker.grab <= THROW_TECH_FRAMES {
+        // both let go and step back
+        attacker.grab = 0;
+        attacker_position.x -= sign * TECH_PUSH * scale;
+        victim_position.x += sign * TECH_PUSH * scale;
+        victim_position.y = ground_y;
+        for player in [attacker, victim] {
+            player.enter(Action::Idle);
+            player.set_animation(IDLE_POSE2, 1, 15);
+        }
+        return Some(ThrowResult::Teched);
+    }
+
+    attacker.grab += 1;
+    // the victim goes from the front of the thrower to its back along half a circle
+    let t = ((attacker.grab - 1) as f32 / THROW_CARRY_FRAMES as f32).min(1.0);
+    let angle = Easing::EaseInOut.apply(t) * PI;
+    victim_position.x = attacker_position.x + sign * THROW_REACH * angle.cos() * scale;
+    victim_position.y = ground_y + THROW_HEIGHT * angle.sin() * scale;
+    if attacker.grab <= THROW_CARRY_FRAMES {
+        return None;
+    }
+
+    // the victim hits the floor behind the thrower and turns back to it
+    victim_position.y = ground_y;
+    if victim.pose.facing != attacker.pose.facing {
+        victim.pose.facing = attacker.pose.facing;
+        victim_position.x -= sign * victim.pose.offset[0] * 2.0;
+    }
+    victim.combo = Combo::default();
+    let damage = victim.scaled_damage(attacker.handicap.damage_of(THROW_DAMAGE));
+    apply_hit(victim, damage, None);
+    // the victim lies behind the thrower, so it slides the other way
+    let data = MoveKind::Throw.frame_data();
+    attacker.hitstop = data.hitstop;
+    victim.hitstop = data.hitstop;
+    victim.knockback = Vec2::new(-sign * data.knockback.x, data.knockback.y);
+    if victim.action() == Action::Thrown {
+        victim.enter(Action::Idle);
+        victim.set_animation(IDLE_POSE2, 1, 15);
+    }
+    release(attacker);
+    Some(ThrowResult::Landed(damage))
+}
+
+// grabs the victim if the hitbox of the throw touches it on an active frame
+fn grab(
+    attacker: &mut Player,
+    attacker_position: Vec2,
+    victim: &mut Player,
+    victim_position: Vec2,
+    ground_y: f32,
+    body: &BodyLayout,
+    scale: f32,
+) -> bool {
+    let Some(attack) = attacker
+        .attack
+        .as_mut()
+        .filter(|attack| attack.kind == MoveKind::Throw && attack.is_active())
+    else {
+        return false;
+    };
+    if !throwable(victim, victim_position, ground_y) {
+        return false;
+    }
+    let hitbox = attack.hitbox(attacker_position, attacker.pose.facing, scale);
+    if find_hit(hitbox, &hurtboxes(&victim.pose, victim_position, body, scale)).is_none() {
+        return false;
+    }
+    attack.hit = true;
+    attacker.grab = 1;
+    attacker.set_animation_eased(THROW_POSE2, 2, THROW_CARRY_FRAMES as u8, Easing::EaseInOut);
+    victim.enter(Action::Thrown);
+    victim.set_animation_eased(STUN_POSE2, 0, THROW_CARRY_FRAMES as u8, Easing::EaseInOut);
+    true
+}
+
+// lets go of the victim and recovers from the throw
+fn release(attacker: &mut Player) {
+    attacker.grab = 0;
+    attacker.state |= PlayerState::COOLDOWN | PlayerState::ATTACK_DISABLED;
+    attacker.set_animation_eased(attacker.poses.get(PoseName::Idle), 1, 20, Easing::EaseOut);
+}
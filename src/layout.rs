@@ -0,0 +1,228 @@
+// Layout of the screens, chosen at runtime.
+//
+// The same binary is used on PCs and on phones: the layout profile is picked from
+// the size of the window (or from the settings) and every size which depends on the
+// screen is read from the `Layout` resource. The UI is scaled with `UiScale`, while
+// the arena, the HUD bars and the fighters use the values below.
+use crate::{AppState, CharacterTextures, GameConfig, PATH_IMAGE_PREFIX};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+/// Windows smaller than this (logical pixels) use the phone layout
+const PHONE_MAX_WIDTH: f32 = 1000.0;
+const PHONE_MAX_HEIGHT: f32 = 600.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutProfile {
+    #[default]
+    Desktop,
+    Phone,
+}
+
+impl LayoutProfile {
+    /// Pick the profile for a window of the given size
+    pub fn choose(setting: LayoutSetting, window_size: Vec2) -> Self {
+        match setting {
+            LayoutSetting::Desktop => LayoutProfile::Desktop,
+            LayoutSetting::Phone => LayoutProfile::Phone,
+            LayoutSetting::Auto => {
+                // a portrait screen is a phone as well
+                if window_size.x < PHONE_MAX_WIDTH
+                    || window_size.y < PHONE_MAX_HEIGHT
+                    || window_size.y > window_size.x
+                {
+                    LayoutProfile::Phone
+                } else {
+                    LayoutProfile::Desktop
+                }
+            }
+        }
+    }
+}
+
+/// Layout selected in the settings
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum LayoutSetting {
+    #[default]
+    Auto = 1,
+    Desktop = 2,
+    Phone = 3,
+}
+
+impl From<u32> for LayoutSetting {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => LayoutSetting::Auto,
+            2 => LayoutSetting::Desktop,
+            3 => LayoutSetting::Phone,
+            _ => panic!("Invalid LayoutSetting: {}", value),
+        }
+    }
+}
+
+/// Size and position of the body parts of a fighter
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLayout {
+    pub upper_arm_length: f32,
+    pub upper_leg_length: f32,
+    pub limb_length: f32,
+    pub neck_length: f32,
+    pub limb_radius: f32,
+    pub body_thickness: f32,
+    pub body_length: f32,
+    // half size of the body collider
+    pub body_collider: Vec2,
+    pub head_position: Vec3,
+    pub head_radius: f32,
+    pub head_offset: f32,
+    pub body_offset: f32,
+    pub upper_arm_offset: f32,
+    pub lower_arm_offset: f32,
+    pub upper_leg_offset: f32,
+    pub lower_leg_offset: f32,
+}
+
+pub const DESKTOP_BODY: BodyLayout = BodyLayout {
+    upper_arm_length: 20.0,
+    upper_leg_length: 40.0,
+    limb_length: 30.0,
+    neck_length: 40.0,
+    limb_radius: 10.0,
+    body_thickness: 10.0,
+    body_length: 65.0,
+    body_collider: Vec2::new(20.0, 65.0),
+    head_position: Vec3::new(0.0, 20.0, -1.0),
+    head_radius: 40.0,
+    head_offset: 80.0,
+    body_offset: -20.0,
+    upper_arm_offset: 0.0,
+    lower_arm_offset: -50.0,
+    upper_leg_offset: -90.0,
+    lower_leg_offset: -70.0,
+};
+
+const PHONE_BODY: BodyLayout = BodyLayout {
+    upper_arm_length: 10.0,
+    upper_leg_length: 20.0,
+    limb_length: 15.0,
+    neck_length: 20.0,
+    limb_radius: 7.5,
+    body_thickness: 5.0,
+    body_length: 32.5,
+    body_collider: Vec2::new(5.0, 32.5),
+    head_position: Vec3::new(0.0, 10.0, 0.0),
+    head_radius: 20.0,
+    head_offset: 40.0,
+    body_offset: 20.0,
+    upper_arm_offset: 15.0,
+    lower_arm_offset: -30.0,
+    upper_leg_offset: -50.0,
+    lower_leg_offset: -30.0,
+};
+
+/// Sizes of everything which depends on the screen
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Layout {
+    pub profile: LayoutProfile,
+    // scale of the UI (fonts, borders and nodes in pixels)
+    pub ui_scale: f32,
+    // scale of the arena and the HUD bars
+    pub scale: f32,
+    // brightness of the HUD, it glows with the bloom on large screens
+    pub glow: f32,
+    // size of a frame in the sprite sheets of the characters
+    pub sprite_size: u32,
+    // directory of the images which have a smaller version for phones
+    pub image_prefix: &'static str,
+    pub sky_image: &'static str,
+    pub sky_width: f32,
+    pub background_y: f32,
+    pub body: BodyLayout,
+    // show the virtual stick for touch screens
+    pub touch_controller: bool,
+}
+
+impl Layout {
+    pub fn new(profile: LayoutProfile) -> Self {
+        match profile {
+            LayoutProfile::Desktop => Self {
+                profile,
+                ui_scale: 1.0,
+                scale: 1.0,
+                glow: 1.0,
+                sprite_size: 512,
+                image_prefix: PATH_IMAGE_PREFIX,
+                sky_image: "sky_upscaled.png",
+                sky_width: 4800.0,
+                background_y: 0.0,
+                body: DESKTOP_BODY,
+                touch_controller: false,
+            },
+            LayoutProfile::Phone => Self {
+                profile,
+                ui_scale: 0.35,
+                scale: 0.5,
+                glow: 0.1,
+                sprite_size: 256,
+                image_prefix: "images/phone/",
+                sky_image: "sky_original.png",
+                sky_width: 1200.0,
+                background_y: 70.0,
+                body: PHONE_BODY,
+                touch_controller: true,
+            },
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::new(LayoutProfile::default())
+    }
+}
+
+/// Use the layout for the current window and settings.
+/// The character textures are reloaded since the phone uses smaller ones.
+pub fn apply_layout(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    ui_scale: &mut UiScale,
+    profile: LayoutProfile,
+) {
+    let layout = Layout::new(profile);
+    info!("layout: {:?}", profile);
+    ui_scale.0 = layout.ui_scale;
+    commands.insert_resource(CharacterTextures::new(asset_server, texture_atlas_layouts, &layout));
+    commands.insert_resource(layout);
+}
+
+/// Switch the layout when the window is resized or the setting is changed.
+/// This only runs on the menus, the screens spawned later use the new layout.
+fn update_layout(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut ui_scale: ResMut<UiScale>,
+    layout: Res<Layout>,
+    config: Res<GameConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let profile = LayoutProfile::choose(config.layout, window.size());
+    if profile != layout.profile {
+        apply_layout(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut ui_scale, profile);
+    }
+}
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Layout>().add_systems(
+            Update,
+            update_layout.run_if(in_state(AppState::Mainmenu).or(in_state(AppState::Settings))),
+        );
+    }
+}
@@ -1,5 +1,6 @@
 #[allow(unused_imports)]
 use bevy::{
+    asset::io::AssetSourceBuilder,
     audio::Volume,
     core_pipeline::bloom::Bloom,
     prelude::*,
@@ -12,21 +13,21 @@ mod confirm;
 #[cfg(not(target_arch = "wasm32"))]
 mod connect_controller;
 mod ingame;
+mod layout;
 mod mainmenu;
 mod result;
 mod settings;
 
+use character_def::{characters, CharacterProfile};
 use ingame::agent::Level;
+use ingame::rules::{Handicap, MatchRules};
+use ingame::sim::SpriteKind;
+use layout::{apply_layout, Layout, LayoutProfile, LayoutSetting};
 
 const GAMETITLE: &str = "DynamicArena";
-#[cfg(not(feature="phone"))]
+// font sizes on a large screen, `UiScale` shrinks them for the phone layout
 const TITLE_FONT_SIZE: f32 = 100.0;
-#[cfg(feature="phone")]
-const TITLE_FONT_SIZE: f32 = 30.0;
-#[cfg(not(feature="phone"))]
 const DEFAULT_FONT_SIZE: f32 = 50.0;
-#[cfg(feature="phone")]
-const DEFAULT_FONT_SIZE: f32 = 15.0;
 //const PATH_FONT: &str = "fonts/Orbitron/Orbitron-Regular.ttf";
 const PATH_BOLD_FONT: &str = "fonts/Orbitron/Orbitron-Bold.ttf";
 const PATH_EXTRA_BOLD_FONT: &str = "fonts/Orbitron/Orbitron-ExtraBold.ttf";
@@ -55,39 +56,89 @@ pub struct CharacterTexture {
 }
 
 impl CharacterTexture {
-    pub fn new(character_id: isize, asset_server: &Res<AssetServer>) -> Self {
+    pub fn new(profile: &CharacterProfile, asset_server: &Res<AssetServer>, layout: &Layout) -> Self {
         Self {
-            idle: asset_server.load(format!("{}character{}/idle.png", PATH_IMAGE_PREFIX, character_id+1)),
-            walk: asset_server.load(format!("{}character{}/walk.png", PATH_IMAGE_PREFIX, character_id+1)),
-            jump: asset_server.load(format!("{}character{}/jump.png", PATH_IMAGE_PREFIX, character_id+1)),
-            kick: asset_server.load(format!("{}character{}/kick.png", PATH_IMAGE_PREFIX, character_id+1)),
-            jump_kick: asset_server.load(format!("{}character{}/jump_kick.png", PATH_IMAGE_PREFIX, character_id+1)),
-            punch: asset_server.load(format!("{}character{}/punch.png", PATH_IMAGE_PREFIX, character_id+1)),
-            back_kick: asset_server.load(format!("{}character{}/back_kick.png", PATH_IMAGE_PREFIX, character_id+1)),
-            bend_down: asset_server.load(format!("{}character{}/bend_down.png", PATH_IMAGE_PREFIX, character_id+1)),
-            roll: asset_server.load(format!("{}character{}/roll.png", PATH_IMAGE_PREFIX, character_id+1)),
-            attacked: asset_server.load(format!("{}character{}/attacked.png", PATH_IMAGE_PREFIX, character_id+1)),
+            idle: asset_server.load(format!("{}{}/idle.png", layout.image_prefix, profile.textures)),
+            walk: asset_server.load(format!("{}{}/walk.png", layout.image_prefix, profile.textures)),
+            jump: asset_server.load(format!("{}{}/jump.png", layout.image_prefix, profile.textures)),
+            kick: asset_server.load(format!("{}{}/kick.png", layout.image_prefix, profile.textures)),
+            jump_kick: asset_server.load(format!("{}{}/jump_kick.png", layout.image_prefix, profile.textures)),
+            punch: asset_server.load(format!("{}{}/punch.png", layout.image_prefix, profile.textures)),
+            back_kick: asset_server.load(format!("{}{}/back_kick.png", layout.image_prefix, profile.textures)),
+            bend_down: asset_server.load(format!("{}{}/bend_down.png", layout.image_prefix, profile.textures)),
+            roll: asset_server.load(format!("{}{}/roll.png", layout.image_prefix, profile.textures)),
+            attacked: asset_server.load(format!("{}{}/attacked.png", layout.image_prefix, profile.textures)),
+            // there is no smaller version of these sheets, see `CharacterTextures::atlas`
             #[cfg(not(target_arch = "wasm32"))]
-            victory: asset_server.load(format!("{}character{}/victory.png", PATH_IMAGE_PREFIX, character_id+1)),
+            victory: asset_server.load(format!("{}{}/victory.png", PATH_IMAGE_PREFIX, profile.textures)),
             #[cfg(not(target_arch = "wasm32"))]
-            defeated: asset_server.load(format!("{}character{}/defeated.png", PATH_IMAGE_PREFIX, character_id+1)),
+            defeated: asset_server.load(format!("{}{}/defeated.png", PATH_IMAGE_PREFIX, profile.textures)),
         }
     }
 }
 
 #[derive(Resource)]
 pub struct CharacterTextures {
-    textures: [CharacterTexture; 3],
+    textures: Vec<CharacterTexture>,
+    // the sprite sheets have 30 sprites in a row and 4 rows
+    atlas: Handle<TextureAtlasLayout>,
+    #[cfg(not(target_arch = "wasm32"))]
+    full_size_atlas: Handle<TextureAtlasLayout>,
 }
 
 impl CharacterTextures {
-    pub fn new(asset_server: &Res<AssetServer>) -> Self {
+    pub fn new(
+        asset_server: &Res<AssetServer>,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+        layout: &Layout,
+    ) -> Self {
         Self {
-            textures: [
-                CharacterTexture::new(0, asset_server),
-                CharacterTexture::new(1, asset_server),
-                CharacterTexture::new(2, asset_server),
-            ],
+            textures: characters()
+                .iter()
+                .map(|profile| CharacterTexture::new(profile, asset_server, layout))
+                .collect(),
+            atlas: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                UVec2::splat(layout.sprite_size),
+                30,
+                4,
+                None,
+                None,
+            )),
+            #[cfg(not(target_arch = "wasm32"))]
+            full_size_atlas: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                UVec2::splat(512),
+                30,
+                4,
+                None,
+                None,
+            )),
+        }
+    }
+    /// atlas layout of the sprite sheet
+    pub fn atlas(&self, sprite: SpriteKind) -> &Handle<TextureAtlasLayout> {
+        match sprite {
+            #[cfg(not(target_arch = "wasm32"))]
+            SpriteKind::Victory | SpriteKind::Defeated => &self.full_size_atlas,
+            _ => &self.atlas,
+        }
+    }
+    pub fn get(&self, character_id: isize, sprite: SpriteKind) -> &Handle<Image> {
+        let texture = &self.textures[character_id as usize];
+        match sprite {
+            SpriteKind::Idle => &texture.idle,
+            SpriteKind::Walk => &texture.walk,
+            SpriteKind::Jump => &texture.jump,
+            SpriteKind::Kick => &texture.kick,
+            SpriteKind::JumpKick => &texture.jump_kick,
+            SpriteKind::Punch => &texture.punch,
+            SpriteKind::BackKick => &texture.back_kick,
+            SpriteKind::BendDown => &texture.bend_down,
+            SpriteKind::Roll => &texture.roll,
+            SpriteKind::Attacked => &texture.attacked,
+            #[cfg(not(target_arch = "wasm32"))]
+            SpriteKind::Victory => &texture.victory,
+            #[cfg(not(target_arch = "wasm32"))]
+            SpriteKind::Defeated => &texture.defeated,
         }
     }
 }
@@ -102,6 +153,8 @@ pub struct BGM(bool);
 pub enum GameMode {
     SinglePlayer = 1,
     MultiPlayer = 2,
+    // one bot after another until a fight is lost, see ingame/survival.rs
+    Survival = 3,
 }
 
 impl From<u32> for GameMode {
@@ -109,11 +162,19 @@ impl From<u32> for GameMode {
         match value {
             1 => GameMode::SinglePlayer,
             2 => GameMode::MultiPlayer,
+            3 => GameMode::Survival,
             _ => panic!("Invalid GameMode: {}", value),
         }
     }
 }
 
+impl GameMode {
+    /// true if player 2 is a bot
+    pub fn has_bot(&self) -> bool {
+        *self != GameMode::MultiPlayer
+    }
+}
+
 #[derive(Resource)]
 pub struct Score(u32);
 
@@ -123,7 +184,12 @@ struct GameConfig {
     mode: GameMode,
     level: Level,
     characters_id: [isize; 2],
+    // order of the fighters of each side in a team battle, see `RoundFormat::Team`
+    teams: [Vec<isize>; 2],
+    // chosen on the confirm screen, back to none when the characters are chosen again
+    handicaps: [Handicap; 2],
     sound_volume: f32,
+    layout: LayoutSetting,
     #[cfg(not(target_arch = "wasm32"))]
     gamepads: [Entity; 2],
 }
@@ -135,13 +201,31 @@ impl Default for GameConfig {
             mode: GameMode::SinglePlayer,
             level: Level::Normal,
             characters_id: [-1, -1],
+            teams: [Vec::new(), Vec::new()],
+            handicaps: [Handicap::default(); 2],
             sound_volume: 0.5,
+            layout: LayoutSetting::Auto,
             #[cfg(not(target_arch = "wasm32"))]
             gamepads: [Entity::from_raw(0), Entity::from_raw(0)],
         }
     }
 }
 
+impl GameConfig {
+    /// true if the match is a team battle, survival always has one fighter on each side
+    fn is_team_battle(&self, rules: &MatchRules) -> bool {
+        rules.is_team_battle() && self.mode != GameMode::Survival
+    }
+    /// The character side `id` starts the match with, the first of its team in a team battle
+    fn starting_character(&self, id: usize, rules: &MatchRules) -> isize {
+        if self.is_team_battle(rules) {
+            self.teams[id][0]
+        } else {
+            self.characters_id[id]
+        }
+    }
+}
+
 #[derive(States, Default, Debug, Hash, PartialEq, Eq, Clone)]
 enum AppState {
     #[default]
@@ -162,6 +246,10 @@ fn main() {
     let mut app = App::new();
     #[cfg(not(target_arch = "wasm32"))]
     app.add_plugins(connect_controller::ConnectControllerPlugin);
+    // poses written by pose-editor.py, loaded as "pose://<file>"
+    // the source has to be registered before the AssetPlugin in DefaultPlugins
+    #[cfg(not(target_arch = "wasm32"))]
+    app.register_asset_source("pose", AssetSourceBuilder::platform_default("pose", None));
     app.add_plugins(DefaultPlugins)
         .init_state::<AppState>()
         .insert_resource(GameConfig::default())
@@ -169,6 +257,7 @@ fn main() {
         .insert_resource(GlobalVolume::new(Volume::Linear(0.5)))
         .insert_resource(Score(0))
         .add_systems(Startup, setup)
+        .add_plugins(layout::LayoutPlugin)
         .add_plugins(mainmenu::MainmenuPlugin)
         .add_plugins(settings::SettingsPlugin)
         .add_plugins(choose_character::ChooseCharacterPlugin)
@@ -184,6 +273,8 @@ fn setup(
     monitors: Query<&Monitor>,
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
     asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut ui_scale: ResMut<UiScale>,
     mut config: ResMut<GameConfig>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
@@ -234,8 +325,9 @@ fn setup(
     ));
 
     // load textures
-    info!("Loading textures...");
-    commands.insert_resource(CharacterTextures::new(&asset_server));
+    info!("Loading textures of {} characters...", characters().len());
+    let profile = LayoutProfile::choose(config.layout, config.window_size);
+    apply_layout(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut ui_scale, profile);
     info!("Complete");
 
     next_state.set(AppState::Mainmenu);
@@ -247,6 +339,8 @@ fn setup(
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
     mut config: ResMut<GameConfig>,
     asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut ui_scale: ResMut<UiScale>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     info!("main: setup(wasm)");
@@ -277,8 +371,9 @@ fn setup(
     ));
 
     // load textures
-    info!("Loading textures...");
-    commands.insert_resource(CharacterTextures::new(&asset_server));
+    info!("Loading textures of {} characters...", characters().len());
+    let profile = LayoutProfile::choose(config.layout, config.window_size);
+    apply_layout(&mut commands, &asset_server, &mut texture_atlas_layouts, &mut ui_scale, profile);
     info!("Complete");
 
     next_state.set(AppState::Mainmenu);
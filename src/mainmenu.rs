@@ -113,10 +113,7 @@ fn setup(
                             Node {
                                 width: Val::Percent(50.0),
                                 height: Val::Percent(10.0),
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(2.0)),
                                 margin: UiRect::all(Val::Percent(1.0)),
                                 justify_content: JustifyContent::Center,
                                 align_items: AlignItems::Center,
@@ -145,10 +142,7 @@ fn setup(
                             Node {
                                 width: Val::Percent(50.0),
                                 height: Val::Percent(10.0),
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(2.0)),
                                 margin: UiRect::all(Val::Percent(1.0)),
                                 justify_content: JustifyContent::Center,
                                 align_items: AlignItems::Center,
@@ -177,10 +171,7 @@ fn setup(
                             Node {
                                 width: Val::Percent(50.0),
                                 height: Val::Percent(10.0),
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(2.0)),
                                 margin: UiRect::all(Val::Percent(1.0)),
                                 justify_content: JustifyContent::Center,
                                 align_items: AlignItems::Center,
@@ -1,5 +1,5 @@
 use crate::{
-    ingame::GameState, AppState, GameConfig, GameMode, SoundEffect, Score, BGM, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT, PATH_EXTRA_BOLD_FONT,
+    ingame::{survival, Game}, AppState, GameConfig, GameMode, SoundEffect, Score, BGM, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT, PATH_BOLD_JP_FONT, PATH_EXTRA_BOLD_FONT,
     PATH_IMAGE_PREFIX, TITLE_FONT_SIZE,
 };
 use bevy::prelude::*;
@@ -7,6 +7,9 @@ use bevy::prelude::*;
 #[derive(Component)]
 struct ShowResult;
 
+// rounds shown in a row each, a longer match is summed up in one box
+const ROUND_ROWS: usize = 3;
+
 /// Structure to record play count
 #[derive(Debug, Default)]
 struct PlayCount {
@@ -128,7 +131,7 @@ impl PlayCount {
             
             // New count (initialize according to mode)
             let (single, multi) = match mode {
-                GameMode::SinglePlayer => (1, 0),
+                GameMode::SinglePlayer | GameMode::Survival => (1, 0),
                 GameMode::MultiPlayer => (0, 1),
             };
             
@@ -162,7 +165,7 @@ impl PlayCount {
     /// Increment the play count based on game mode
     fn increment(&mut self, mode: GameMode) {
         match mode {
-            GameMode::SinglePlayer => self.single_mode += 1,
+            GameMode::SinglePlayer | GameMode::Survival => self.single_mode += 1,
             GameMode::MultiPlayer => self.multi_mode += 1,
         }
     }
@@ -171,7 +174,7 @@ impl PlayCount {
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    gamestate: Res<GameState>,
+    game: Res<Game>,
     game_config: Res<GameConfig>,
     mut score: ResMut<Score>,
     audio_query: Query<Entity, With<BGM>>
@@ -251,10 +254,22 @@ fn setup(
                             ..default()
                         })
                         .with_children(|spawner| {
-                            create_round_result(spawner, &asset_server, 1, gamestate.winners[0]);
-                            create_round_result(spawner, &asset_server, 2, gamestate.winners[1]);
-                            create_round_result(spawner, &asset_server, 3, gamestate.winners[2]);
-                            create_total_result(spawner, &asset_server, gamestate.get_winner(), score.0);
+                            // a survival has one fight for each opponent, only the run is shown
+                            if game_config.mode == GameMode::Survival {
+                                create_survival_result(spawner, &asset_server, survival::defeated(&game.winners), score.0);
+                                return;
+                            }
+                            // the sudden death rounds are the last ones
+                            let first_sudden_death = game.winners.len() - game.sudden_deaths as usize;
+                            if game.winners.len() > ROUND_ROWS {
+                                // too many rounds for a row each, one box sums them up
+                                create_rounds_summary(spawner, &asset_server, &game.winners, first_sudden_death);
+                            } else {
+                                for (round, winner_id) in game.winners.iter().enumerate() {
+                                    create_round_result(spawner, &asset_server, round as u8 + 1, round >= first_sudden_death, *winner_id);
+                                }
+                            }
+                            create_total_result(spawner, &asset_server, game.get_winner(), score.0);
                         });
                     spawner
                         .spawn((
@@ -275,10 +290,7 @@ fn setup(
                                 height: Val::Percent(10.0),
                                 justify_self: JustifySelf::Center,
                                 align_self: AlignSelf::Center,
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(10.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(5.0)),
                                 ..default()
                             },
                             BorderRadius::all(Val::Px(10.0)),
@@ -308,6 +320,7 @@ fn create_round_result(
     spawner: &mut ChildSpawnerCommands,
     asset_server: &Res<AssetServer>,
     round: u8,
+    sudden_death: bool,
     winner_id: u8,
 ) {
     spawner
@@ -326,7 +339,11 @@ fn create_round_result(
         ))
         .with_children(|spawner| {
             spawner.spawn((
-                Text::new(format!("Round {} Result", round)),
+                Text::new(if sudden_death {
+                    "Sudden Death Result".to_string()
+                } else {
+                    format!("Round {} Result", round)
+                }),
                 TextFont {
                     font: asset_server.load(PATH_BOLD_FONT),
                     font_size: DEFAULT_FONT_SIZE,
@@ -377,6 +394,87 @@ fn create_round_result(
         });
 }
 
+// the wins of both players and the winner of every round in one line
+fn create_rounds_summary(spawner: &mut ChildSpawnerCommands, asset_server: &Res<AssetServer>, winners: &[u8], first_sudden_death: usize) {
+    let wins = [1, 2].map(|id| winners.iter().filter(|winner| **winner == id).count());
+    let rounds = winners
+        .iter()
+        .enumerate()
+        .map(|(round, winner_id)| {
+            let name = if round >= first_sudden_death { "SD".to_string() } else { format!("R{}", round + 1) };
+            match winner_id {
+                0 => format!("{} DRAW", name),
+                id => format!("{} {}P", name, id),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+    spawner
+        .spawn((
+            Node {
+                width: Val::Percent(90.0),
+                height: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BorderRadius::all(Val::Px(10.0)),
+            BorderColor(Color::BLACK),
+        ))
+        .with_children(|spawner| {
+            spawner.spawn((
+                Text::new(format!("{} Rounds Result", winners.len())),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_FONT),
+                    font_size: DEFAULT_FONT_SIZE,
+                    ..Default::default()
+                },
+                TextColor(Color::BLACK),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ));
+            spawner
+                .spawn(Node {
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Percent(5.0),
+                    ..default()
+                })
+                .with_children(|spawner| {
+                    for (id, color) in [(1, Color::srgba(1.0, 0.0, 0.0, 0.8)), (2, Color::srgba(0.0, 0.0, 1.0, 0.8))] {
+                        spawner.spawn((
+                            Text::new(format!("Player {}: {} WIN", id, wins[id - 1])),
+                            TextFont {
+                                font: asset_server.load(PATH_BOLD_FONT),
+                                font_size: DEFAULT_FONT_SIZE,
+                                ..Default::default()
+                            },
+                            TextColor(color),
+                        ));
+                    }
+                });
+            spawner.spawn((
+                Text::new(rounds),
+                TextFont {
+                    font: asset_server.load(PATH_BOLD_FONT),
+                    font_size: DEFAULT_FONT_SIZE * 0.8,
+                    ..Default::default()
+                },
+                TextColor(Color::BLACK),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
 fn create_total_result(spawner: &mut ChildSpawnerCommands, asset_server: &Res<AssetServer>, winner_id: u8, score: u32) {
     spawner
         .spawn((
@@ -441,6 +539,45 @@ fn create_total_result(spawner: &mut ChildSpawnerCommands, asset_server: &Res<As
         });
 }
 
+fn create_survival_result(spawner: &mut ChildSpawnerCommands, asset_server: &Res<AssetServer>, defeated: u32, score: u32) {
+    spawner
+        .spawn((
+            Node {
+                width: Val::Percent(90.0),
+                height: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            BorderRadius::all(Val::Px(10.0)),
+            BorderColor(Color::srgba(1.0, 0.0, 0.0, 0.8)),
+        ))
+        .with_children(|spawner| {
+            for (text, color) in [
+                ("Survival Result".to_string(), Color::BLACK),
+                (format!("Defeated: {}", defeated), Color::srgba(1.0, 0.0, 0.0, 0.8)),
+                (format!("Your Score: {}", score), Color::srgba(1.0, 0.0, 0.0, 0.8)),
+            ] {
+                spawner.spawn((
+                    Text::new(text),
+                    TextFont {
+                        font: asset_server.load(PATH_BOLD_FONT),
+                        font_size: DEFAULT_FONT_SIZE,
+                        ..Default::default()
+                    },
+                    TextColor(color),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Node {
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
 fn controller_input(
     mut next_state: ResMut<NextState<AppState>>,
     gamepads: Query<&Gamepad>,
@@ -1,5 +1,7 @@
 use crate::{
-    ingame::agent::Level, AppState, GameConfig, GameMode, BGM, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT,
+    character_def::{characters, ENERGY_STOCKS},
+    ingame::{agent::Level, rules::{MatchRules, RoundFormat, INVINCIBLE_TIMES, ROUND_FORMATS, ROUND_TIMES}},
+    layout::LayoutSetting, AppState, GameConfig, GameMode, BGM, SoundEffect, PATH_SOUND_PREFIX, DEFAULT_FONT_SIZE, PATH_BOLD_FONT,
     PATH_BOLD_JP_FONT, PATH_EXTRA_BOLD_JP_FONT, PATH_IMAGE_PREFIX, TITLE_FONT_SIZE,
 };
 use bevy::{
@@ -58,12 +60,38 @@ impl<T: Clone + ToString + Send + Sync + Display> SettingItem<T> {
 #[derive(Component)]
 struct ConfigElement(u32);
 
+// index of the last setting a gamepad can select
+const LAST_SETTING: u8 = 11;
+
+/// Applies a changed list setting of the match rules
+fn set_rule_int(rules: &mut MatchRules, element: u32, value: u32) {
+    match element {
+        4 => rules.format = ROUND_FORMATS[value as usize - 1],
+        5 => rules.round_time = ROUND_TIMES[value as usize - 1],
+        6 => rules.start_stocks = value as u16 - 1,
+        9 => rules.skip_decided = value == 1,
+        10 => rules.invincible_time = INVINCIBLE_TIMES[value as usize - 1],
+        11 => rules.sudden_death = value == 1,
+        _ => {}
+    }
+}
+
+/// Applies a changed numeric setting of the match rules
+fn set_rule_float(rules: &mut MatchRules, element: u32, value: f32) {
+    match element {
+        7 => rules.start_fire_charge = value,
+        8 => rules.damage_ratio = value,
+        _ => {}
+    }
+}
+
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     #[cfg(not(target_arch = "wasm32"))]
     setting_idx: Res<SettingIndex>,
-    config: Res<GameConfig>
+    config: Res<GameConfig>,
+    rules: Res<MatchRules>,
 ) {
     info!("setup");
     commands
@@ -95,10 +123,7 @@ fn setup(
                             Node {
                                 justify_self: JustifySelf::Start,
                                 align_self: AlignSelf::Start,
-                                #[cfg(not(feature="phone"))]
                                 border: UiRect::all(Val::Px(5.0)),
-                                #[cfg(feature="phone")]
-                                border: UiRect::all(Val::Px(1.0)),
                                 ..default()
                             },
                             BorderRadius::MAX,
@@ -181,10 +206,10 @@ fn setup(
                                 SettingItem::new(
                                     "ゲームモード".to_string(),
                                     1u32,
-                                    2,
+                                    3,
                                     1,
                                     config.mode as u32,
-                                    Some(vec!["シングル".to_string(), "マルチ".to_string()]),
+                                    Some(vec!["シングル".to_string(), "マルチ".to_string(), "サバイバル".to_string()]),
                                 ),
                                 1,
                             );
@@ -207,6 +232,171 @@ fn setup(
                                 ),
                                 2,
                             );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "画面レイアウト".to_string(),
+                                    1u32,
+                                    3,
+                                    1,
+                                    config.layout as u32,
+                                    Some(vec![
+                                        "自動".to_string(),
+                                        "PC".to_string(),
+                                        "スマホ".to_string(),
+                                    ]),
+                                ),
+                                3,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "ラウンド形式".to_string(),
+                                    1u32,
+                                    ROUND_FORMATS.len() as u32,
+                                    1,
+                                    ROUND_FORMATS.iter().position(|format| *format == rules.format).unwrap_or(0) as u32 + 1,
+                                    Some(
+                                        ROUND_FORMATS
+                                            .iter()
+                                            .map(|format| match format {
+                                                RoundFormat::Rounds(rounds) => format!("{}ラウンド", rounds),
+                                                RoundFormat::FirstTo(wins) => format!("{}本先取", wins),
+                                                RoundFormat::Team => format!("{0}対{0}チーム戦", characters().len()),
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                                4,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "制限時間".to_string(),
+                                    1u32,
+                                    ROUND_TIMES.len() as u32,
+                                    1,
+                                    ROUND_TIMES.iter().position(|time| *time == rules.round_time).unwrap_or(0) as u32 + 1,
+                                    Some(
+                                        ROUND_TIMES
+                                            .iter()
+                                            .map(|time| match time {
+                                                Some(seconds) => format!("{}秒", seconds),
+                                                None => "無制限".to_string(),
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                                5,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "初期エネルギー".to_string(),
+                                    1u32,
+                                    ENERGY_STOCKS as u32 + 1,
+                                    1,
+                                    rules.start_stocks as u32 + 1,
+                                    Some(
+                                        (0..=ENERGY_STOCKS)
+                                            .map(|stocks| if stocks == 0 { "なし".to_string() } else { format!("{}ストック", stocks) })
+                                            .collect(),
+                                    ),
+                                ),
+                                6,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "初期ファイア".to_string(),
+                                    0f32,
+                                    1.0,
+                                    0.1,
+                                    rules.start_fire_charge,
+                                    None,
+                                ),
+                                7,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "ダメージ倍率".to_string(),
+                                    0.5f32,
+                                    2.0,
+                                    0.1,
+                                    rules.damage_ratio,
+                                    None,
+                                ),
+                                8,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "決着後のラウンド".to_string(),
+                                    1u32,
+                                    2,
+                                    1,
+                                    if rules.skip_decided { 1 } else { 2 },
+                                    Some(vec!["省略する".to_string(), "行う".to_string()]),
+                                ),
+                                9,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "気絶後の無敵".to_string(),
+                                    1u32,
+                                    INVINCIBLE_TIMES.len() as u32,
+                                    1,
+                                    INVINCIBLE_TIMES.iter().position(|time| *time == rules.invincible_time).unwrap_or(0) as u32 + 1,
+                                    Some(
+                                        INVINCIBLE_TIMES
+                                            .iter()
+                                            .map(|time| if *time == 0.0 { "なし".to_string() } else { format!("{}秒", time) })
+                                            .collect(),
+                                    ),
+                                ),
+                                10,
+                            );
+                            create_setting_item(
+                                &asset_server,
+                                spawner,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                setting_idx.idx,
+                                SettingItem::new(
+                                    "引き分け時のサドンデス".to_string(),
+                                    1u32,
+                                    2,
+                                    1,
+                                    if rules.sudden_death { 1 } else { 2 },
+                                    Some(vec!["行う".to_string(), "行わない".to_string()]),
+                                ),
+                                11,
+                            );
                             #[cfg(target_arch = "wasm32")]
                             create_setting_item(
                                 &asset_server,
@@ -222,7 +412,7 @@ fn setup(
                                         "フルスクリーン".to_string(),
                                     ]),
                                 ),
-                                3,
+                                12,
                             );
                         });
                 });
@@ -241,7 +431,7 @@ fn create_setting_item<T: Clone + ToString + Send + Sync + Display>(
         .spawn((
             Node {
                 width: Val::Percent(90.0),
-                height: Val::Percent(10.0),
+                height: Val::Percent(7.0),
                 margin: UiRect {
                     top: Val::Px(5.0),
                     bottom: Val::Px(5.0),
@@ -290,10 +480,7 @@ fn create_setting_item<T: Clone + ToString + Send + Sync + Display>(
                     Node {
                         width: Val::Percent(5.0),
                         height: Val::Percent(100.0),
-                        #[cfg(not(feature="phone"))]
                         border: UiRect::all(Val::Px(5.0)),
-                        #[cfg(feature="phone")]
-                        border: UiRect::all(Val::Px(1.0)),
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
                         ..default()
@@ -351,10 +538,7 @@ fn create_setting_item<T: Clone + ToString + Send + Sync + Display>(
                     Node {
                         width: Val::Percent(5.0),
                         height: Val::Percent(100.0),
-                        #[cfg(not(feature="phone"))]
                         border: UiRect::all(Val::Px(5.0)),
-                        #[cfg(feature="phone")]
-                        border: UiRect::all(Val::Px(1.0)),
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
                         ..default()
@@ -401,6 +585,7 @@ fn update_setting(
     >,
     text_query: Query<&Text, Without<ConfigElement>>,
     mut config: ResMut<GameConfig>,
+    mut rules: ResMut<MatchRules>,
     mut global_volume: ResMut<GlobalVolume>,
     mut audio: Query<&mut AudioSink, With<BGM>>,
 ) {
@@ -447,6 +632,8 @@ fn update_setting(
                     global_volume.volume = Volume::Linear(new_value);
                     let mut sink = audio.single_mut().unwrap();
                     sink.set_volume(Volume::Linear(new_value));
+                } else {
+                    set_rule_float(&mut rules, element.0, new_value);
                 }
             }
         }
@@ -470,11 +657,15 @@ fn update_setting(
                 } else if element.0 == 2 {
                     config.level = Level::from(new_value);
                 } else if element.0 == 3 {
+                    config.layout = LayoutSetting::from(new_value);
+                } else if element.0 == 12 {
                     windows.single_mut().unwrap().mode = if { new_value } == 1 {
                         WindowMode::Windowed
                     } else {
                         WindowMode::BorderlessFullscreen(MonitorSelection::Primary)
                     };
+                } else {
+                    set_rule_int(&mut rules, element.0, new_value);
                 }
             }
         }
@@ -496,6 +687,7 @@ fn controller_input(
         (With<ConfigElement>, Without<SettingItem<f32>>),
     >,
     mut config: ResMut<GameConfig>,
+    mut rules: ResMut<MatchRules>,
     mut global_volume: ResMut<GlobalVolume>,
     mut audio: Query<&mut AudioSink, With<BGM>>,
 ) {
@@ -518,7 +710,7 @@ fn controller_input(
             }
 
         } else if gamepad.just_pressed(GamepadButton::DPadDown) {
-            if setting_index.idx != 2 {
+            if setting_index.idx != LAST_SETTING {
                 setting_index.idx += 1;
                 for (mut border_color, item_index) in border_query.iter_mut() {     
                     if border_color.0.alpha() != 0.0 {
@@ -559,6 +751,8 @@ fn controller_input(
                     global_volume.volume = Volume::Linear(new_value);
                     let mut sink = audio.single_mut().unwrap();
                     sink.set_volume(Volume::Linear(new_value));
+                } else {
+                    set_rule_float(&mut rules, element.0, new_value);
                 }
             }
         }
@@ -582,11 +776,15 @@ fn controller_input(
                 } else if element.0 == 2 {
                     config.level = Level::from(new_value);
                 } else if element.0 == 3 {
+                    config.layout = LayoutSetting::from(new_value);
+                } else if element.0 == 12 {
                     windows.single_mut().unwrap().mode = if { new_value } == 1 {
                         WindowMode::Windowed
                     } else {
                         WindowMode::BorderlessFullscreen(MonitorSelection::Primary)
                     };
+                } else {
+                    set_rule_int(&mut rules, element.0, new_value);
                 }
             }
         }